@@ -0,0 +1,46 @@
+//! Exports the shaders of a project to GLSL ES 3.0, HLSL and MSL.
+//!
+//! ```bash
+//! cargo run -p gyo_core --example export -- examples/examples/default exported
+//! ```
+//!
+//! The project is a `.gyo` file or an unpacked directory. Every target gets a
+//! subdirectory, and `bindings.txt` lists where the resources live. An
+//! optional third argument names the variant to export.
+
+use anyhow::Context;
+use gyo_core::export::{self, ExportTarget};
+use gyo_core::ProjectPacker;
+use std::path::{Path, PathBuf};
+
+fn main() -> anyhow::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let (Some(project), Some(out_dir)) = (args.next(), args.next()) else {
+        anyhow::bail!("Usage: export <project.gyo | project dir> <output dir> [variant]");
+    };
+    let variant = args.next();
+
+    let project = Path::new(&project);
+    let bytes = if project.is_dir() {
+        ProjectPacker::from_dir(project)?.to_bytes()?
+    } else {
+        std::fs::read(project).with_context(|| format!("Failed to read {}", project.display()))?
+    };
+
+    let mut report = String::new();
+    for target in ExportTarget::ALL {
+        let dir = PathBuf::from(&out_dir).join(target.extension());
+        std::fs::create_dir_all(&dir)?;
+        for shader in export::export_project(&bytes, variant.as_deref(), target)? {
+            let path = dir.join(shader.file_name());
+            std::fs::write(&path, &shader.code)?;
+            println!("📝 {}", path.display());
+            report.push_str(&format!("{}\n", shader));
+        }
+    }
+
+    let path = PathBuf::from(&out_dir).join("bindings.txt");
+    std::fs::write(&path, report)?;
+    println!("📋 {}", path.display());
+    Ok(())
+}
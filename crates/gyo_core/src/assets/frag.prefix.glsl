@@ -0,0 +1,30 @@
+#version 450
+
+// Same layout as the WGSL `Uniforms` (std140)
+layout(set = 0, binding = 0) uniform Uniforms {
+    vec2 cursor;
+    uint mouse_down;
+    vec2 mouse_press;
+    vec2 mouse_release;
+    vec2 resolution;
+    float time;
+} gyo;
+
+// Channel N: texture at binding 2N, sampler at binding 2N + 1
+layout(set = 1, binding = 0) uniform texture2D gyo_channel0;
+layout(set = 1, binding = 1) uniform sampler gyo_sampler0;
+layout(set = 1, binding = 2) uniform texture2D gyo_channel1;
+layout(set = 1, binding = 3) uniform sampler gyo_sampler1;
+layout(set = 1, binding = 4) uniform texture2D gyo_channel2;
+layout(set = 1, binding = 5) uniform sampler gyo_sampler2;
+layout(set = 1, binding = 6) uniform texture2D gyo_channel3;
+layout(set = 1, binding = 7) uniform sampler gyo_sampler3;
+
+// Shadertoy names
+#define iTime gyo.time
+#define iResolution vec3(gyo.resolution, 1.0)
+#define iMouse vec4(gyo.cursor, (gyo.mouse_down != 0u ? 1.0 : -1.0) * gyo.mouse_press)
+#define iChannel0 sampler2D(gyo_channel0, gyo_sampler0)
+#define iChannel1 sampler2D(gyo_channel1, gyo_sampler1)
+#define iChannel2 sampler2D(gyo_channel2, gyo_sampler2)
+#define iChannel3 sampler2D(gyo_channel3, gyo_sampler3)
@@ -0,0 +1,8 @@
+layout(location = 0) out vec4 gyo_frag_color;
+
+void main() {
+    // Shadertoy's fragCoord has its origin at the bottom-left
+    vec4 color = vec4(0.0, 0.0, 0.0, 1.0);
+    mainImage(color, vec2(gl_FragCoord.x, gyo.resolution.y - gl_FragCoord.y));
+    gyo_frag_color = color;
+}
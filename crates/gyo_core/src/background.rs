@@ -0,0 +1,68 @@
+//! The layer drawn behind the sprites of raw vertex/fragment shaders.
+
+use crate::validate::ValidatedShader;
+use crate::{AssetEntry, AssetKind};
+use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+
+/// What sprites are drawn over. Colours are linear RGBA, like shader output.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum Background {
+    /// Cleared to black
+    None,
+    /// The built-in drifting clouds
+    #[default]
+    Sky,
+    /// Cleared to a colour
+    Color([f32; 4]),
+    /// A vertical gradient
+    Gradient { bottom: [f32; 4], top: [f32; 4] },
+    /// An image shader (`SumiSource` with `main_image`), fed by the Uniform
+    /// and channels like the main shader
+    Shader { asset_id: String },
+}
+
+impl Background {
+    /// The shader asset drawn as the background, if any
+    pub fn asset_id(&self) -> Option<&str> {
+        match self {
+            Background::Shader { asset_id } => Some(asset_id),
+            _ => None,
+        }
+    }
+
+    /// Checks that a background shader is a `SumiSource` asset
+    pub fn check(&self, assets: &[AssetEntry]) -> anyhow::Result<()> {
+        let Some(asset_id) = self.asset_id() else { return Ok(()) };
+        anyhow::ensure!(
+            assets.iter().any(|a| a.id == asset_id && matches!(a.kind, AssetKind::SumiSource)),
+            "The background draws `{}`, which is not a SumiSource asset", asset_id,
+        );
+        Ok(())
+    }
+
+    /// Checks that a background shader is an image shader.
+    /// `shaders` are the validated shaders of the project.
+    pub fn check_shader(&self, shaders: &[(&AssetEntry, ValidatedShader)]) -> anyhow::Result<()> {
+        let Some(asset_id) = self.asset_id() else { return Ok(()) };
+        let shader = shaders.iter().find(|(asset, _)| asset.id == asset_id);
+        anyhow::ensure!(
+            shader.is_some_and(|(_, shader)| shader.is_image()),
+            "Background shader `{}` is not an image shader (`main_image`)", asset_id,
+        );
+        Ok(())
+    }
+}
+
+impl Hash for Background {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let bits = |color: &[f32; 4]| color.map(f32::to_bits);
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Background::None | Background::Sky => {}
+            Background::Color(color) => bits(color).hash(state),
+            Background::Gradient { bottom, top } => (bits(bottom), bits(top)).hash(state),
+            Background::Shader { asset_id } => asset_id.hash(state),
+        }
+    }
+}
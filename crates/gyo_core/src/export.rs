@@ -0,0 +1,391 @@
+//! Export of `SumiSource` shaders to GLSL ES 3.0, HLSL and MSL.
+//!
+//! The asset is wrapped in the runtime prelude exactly as `hanga` draws it,
+//! validated, and translated by naga's backends. Image shaders also export
+//! the full-screen triangle they are drawn with ([`FULLSCREEN_VERTEX_ID`]).
+//!
+//! Every export carries a binding report: where each uniform buffer, texture
+//! and sampler of the WGSL (`@group`/`@binding`) lives in the target language.
+
+use crate::shader::{self, ComposedShader};
+use crate::import::{self, Library};
+use crate::reflect::{kind_of, ResourceKind};
+use crate::validate::{self, ValidatedShader};
+use crate::{AssetKind, GyoshoFile, ShaderStage};
+use anyhow::Context;
+use binrw::BinRead;
+use naga::back::{glsl, hlsl, msl};
+use naga::{AddressSpace, GlobalVariable, Handle, Module, ResourceBinding, TypeInner};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Cursor;
+
+/// Asset id of the exported full-screen triangle vertex shader
+pub const FULLSCREEN_VERTEX_ID: &str = "fullscreen_triangle";
+
+/// A shading language to export to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExportTarget {
+    /// OpenGL ES 3.0 / WebGL 2. One source per entry point.
+    GlslEs300,
+    /// HLSL Shader Model 5.1 (Unity, Direct3D 12)
+    Hlsl,
+    /// Metal Shading Language 2.0 (iOS, macOS)
+    Msl,
+}
+
+impl ExportTarget {
+    pub const ALL: [Self; 3] = [Self::GlslEs300, Self::Hlsl, Self::Msl];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::GlslEs300 => "GLSL ES 3.0",
+            Self::Hlsl => "HLSL 5.1",
+            Self::Msl => "MSL 2.0",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::GlslEs300 => "glsl",
+            Self::Hlsl => "hlsl",
+            Self::Msl => "metal",
+        }
+    }
+}
+
+/// A member of a buffer's struct, for filling uniforms by hand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BufferField {
+    pub name: String,
+    /// Byte offset in the buffer
+    pub offset: u32,
+    pub size: u32,
+}
+
+/// Where one WGSL resource lives in the exported code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingReport {
+    pub group: u32,
+    pub binding: u32,
+    /// Variable name in the WGSL
+    pub name: String,
+    pub kind: ResourceKind,
+    /// The target's binding point, e.g. `register(b0, space0)` or `[[buffer(0)]]`
+    pub location: String,
+    /// Struct members of buffers (Empty for other kinds)
+    pub fields: Vec<BufferField>,
+}
+
+/// One exported source file.
+#[derive(Debug, Clone)]
+pub struct ExportedShader {
+    pub asset_id: String,
+    pub target: ExportTarget,
+    pub code: String,
+    /// Stage and name (In `code`) of every entry point
+    pub entry_points: Vec<(ShaderStage, String)>,
+    /// Resources used by the entry points, by group and binding
+    pub bindings: Vec<BindingReport>,
+}
+
+impl ExportedShader {
+    /// Suggested file name: `<asset>.<stage>.glsl` for GLSL (One stage per
+    /// file), `<asset>.hlsl` or `<asset>.metal` otherwise.
+    pub fn file_name(&self) -> String {
+        let stem = self.asset_id.rsplit_once('.').map_or(self.asset_id.as_str(), |(stem, _)| stem);
+        match (self.target, self.entry_points.as_slice()) {
+            (ExportTarget::GlslEs300, [(stage, _)]) => {
+                let stage = match stage {
+                    ShaderStage::Vertex => "vert",
+                    ShaderStage::Fragment => "frag",
+                    ShaderStage::Compute => "comp",
+                };
+                format!("{}.{}.glsl", stem, stage)
+            }
+            (target, _) => format!("{}.{}", stem, target.extension()),
+        }
+    }
+}
+
+/// The binding report of an export, as text.
+impl fmt::Display for ExportedShader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} ({}) from {}", self.file_name(), self.target.name(), self.asset_id)?;
+        for (stage, name) in &self.entry_points {
+            writeln!(f, "  {:?} entry point: {}", stage, name)?;
+        }
+        if self.bindings.is_empty() {
+            writeln!(f, "  No bindings")?;
+        }
+        for binding in &self.bindings {
+            writeln!(
+                f, "  @group({}) @binding({}) {}: {} -> {}",
+                binding.group, binding.binding, binding.name, binding.kind, binding.location,
+            )?;
+            for field in &binding.fields {
+                writeln!(f, "      {} (offset {}, {} bytes)", field.name, field.offset, field.size)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Exports every `SumiSource` of a `.gyo` file to `target`, except the
+/// libraries other sources import, preprocessed for `variant` (The default
+/// one for `None`).
+pub fn export_project(bytes: &[u8], variant: Option<&str>, target: ExportTarget) -> anyhow::Result<Vec<ExportedShader>> {
+    let file = GyoshoFile::read(&mut Cursor::new(bytes)).context("Failed to parse GYO header")?;
+    let manifest = file.manifest().context("Failed to deserialize Manifest")?;
+    let payload = file.decompress_payload().context("Failed to decompress GYO payload")?;
+
+    let mut sources = Vec::new();
+    for asset in manifest.assets.iter().filter(|a| matches!(a.kind, AssetKind::SumiSource)) {
+        let source = asset.bytes(&payload)?;
+        let source = std::str::from_utf8(source)
+            .with_context(|| format!("Shader {} is not valid UTF-8", asset.id))?;
+        sources.push((asset.id.as_str(), source));
+    }
+    let mut library = Library::new();
+    if let Some(variant) = manifest.variant(variant)? {
+        library.define_variant(variant);
+    }
+    for (id, source) in &sources {
+        library.insert(id, source);
+    }
+    let imported = library.imported();
+
+    let mut exports = Vec::new();
+    for (id, source) in sources.iter().filter(|(id, _)| !imported.contains(id)) {
+        for export in export_source(id, source, &library, target)? {
+            // Image shaders share one vertex shader
+            let exported = exports.iter().any(|e: &ExportedShader| e.asset_id == export.asset_id);
+            if !(exported && export.asset_id == FULLSCREEN_VERTEX_ID) {
+                exports.push(export);
+            }
+        }
+    }
+    anyhow::ensure!(!exports.is_empty(), "No SumiSource asset to export");
+    Ok(exports)
+}
+
+/// Exports a `SumiSource` the way the runtime composes it, with imports
+/// from `library`. Image shaders come with the full-screen triangle vertex shader.
+pub fn export_source(
+    asset_id: &str,
+    source: &str,
+    library: &Library,
+    target: ExportTarget,
+) -> anyhow::Result<Vec<ExportedShader>> {
+    let user = import::resolve(asset_id, source, library)?;
+    // OpenGL's fragment position starts at the bottom-left already
+    let composed = match target {
+        ExportTarget::GlslEs300 if shader::is_image_shader(&user.code) => shader::wrap_gl_image_shader(&user),
+        _ => shader::prepare_composed(user),
+    };
+    let fragment = validate::validate(composed)?;
+
+    let mut exports = Vec::new();
+    if fragment.is_image() {
+        let mut vertex = ComposedShader::default();
+        vertex.append(FULLSCREEN_VERTEX_ID, shader::VERTEX_SHADER);
+        exports.extend(export_shader(FULLSCREEN_VERTEX_ID, &validate::validate(vertex)?, target)?);
+    }
+    exports.extend(export_shader(asset_id, &fragment, target)?);
+    Ok(exports)
+}
+
+/// Translates a validated shader. GLSL yields one export per entry point,
+/// HLSL and MSL a single one with every entry point.
+pub fn export_shader(asset_id: &str, shader: &ValidatedShader, target: ExportTarget) -> anyhow::Result<Vec<ExportedShader>> {
+    let exports = match target {
+        ExportTarget::GlslEs300 => (0..shader.module.entry_points.len())
+            .map(|index| export_glsl(asset_id, shader, index))
+            .collect::<anyhow::Result<Vec<_>>>()?,
+        ExportTarget::Hlsl => vec![export_hlsl(asset_id, shader)?],
+        ExportTarget::Msl => vec![export_msl(asset_id, shader)?],
+    };
+    Ok(exports)
+}
+
+fn export_glsl(asset_id: &str, shader: &ValidatedShader, index: usize) -> anyhow::Result<ExportedShader> {
+    let (module, entry_point) = (&shader.module, &shader.module.entry_points[index]);
+    let options = glsl::Options {
+        version: glsl::Version::new_gles(300),
+        ..Default::default()
+    };
+    let pipeline_options = glsl::PipelineOptions {
+        shader_stage: entry_point.stage,
+        entry_point: entry_point.name.clone(),
+        multiview: None,
+    };
+
+    let mut code = String::new();
+    let reflection = glsl::Writer::new(&mut code, module, &shader.info, &options, &pipeline_options, Default::default())
+        .and_then(|mut writer| writer.write())
+        .with_context(|| format!("Failed to export {} to GLSL ES 3.0", asset_id))?;
+
+    // ES 3.0 has no explicit bindings: Resources are found by name
+    let bindings = resources(module, |handle| !shader.info.get_entry_point(index)[handle].is_empty())
+        .map(|(handle, var, binding)| {
+            let location = if let Some(block) = reflection.uniforms.get(&handle) {
+                format!("uniform block {}", block)
+            } else {
+                let mut samplers: Vec<&str> = reflection.texture_mapping.iter()
+                    .filter(|(_, m)| m.texture == handle || m.sampler == Some(handle))
+                    .map(|(name, _)| name.as_str())
+                    .collect();
+                samplers.sort();
+                format!("sampler2D {}", samplers.join(", "))
+            };
+            report(module, var, binding, location)
+        })
+        .collect();
+
+    Ok(ExportedShader {
+        asset_id: asset_id.to_string(),
+        target: ExportTarget::GlslEs300,
+        code,
+        entry_points: vec![(validate::stage_of(entry_point.stage), "main".to_string())],
+        bindings,
+    })
+}
+
+fn export_hlsl(asset_id: &str, shader: &ValidatedShader) -> anyhow::Result<ExportedShader> {
+    let module = &shader.module;
+
+    // `space` is the group, the register the binding
+    let mut options = hlsl::Options { fake_missing_bindings: false, ..Default::default() };
+    let mut bindings = Vec::new();
+    for (_, var, binding) in resources(module, |handle| is_used(shader, handle)) {
+        let register = match kind_of(module, var) {
+            ResourceKind::UniformBuffer { .. } => 'b',
+            ResourceKind::StorageBuffer { read_only: true } | ResourceKind::Texture => 't',
+            ResourceKind::Sampler => 's',
+            _ => 'u',
+        };
+        let space = u8::try_from(binding.group).context("HLSL register spaces end at 255")?;
+        options.binding_map.insert(binding.clone(), hlsl::BindTarget {
+            space,
+            register: binding.binding,
+            binding_array_size: None,
+        });
+        let location = format!("register({}{}, space{})", register, binding.binding, space);
+        bindings.push(report(module, var, binding, location));
+    }
+
+    let mut code = String::new();
+    let reflection = hlsl::Writer::new(&mut code, &options)
+        .write(module, &shader.info, None)
+        .with_context(|| format!("Failed to export {} to HLSL", asset_id))?;
+    let entry_points = entry_point_names(module, reflection.entry_point_names, "HLSL")?;
+
+    Ok(ExportedShader { asset_id: asset_id.to_string(), target: ExportTarget::Hlsl, code, entry_points, bindings })
+}
+
+fn export_msl(asset_id: &str, shader: &ValidatedShader) -> anyhow::Result<ExportedShader> {
+    let module = &shader.module;
+
+    // Metal numbers buffers, textures and samplers separately, in binding order
+    let mut resources_map = msl::BindingMap::new();
+    let mut slots = [0u8; 3];
+    let mut bindings = Vec::new();
+    for (_, var, binding) in resources(module, |handle| is_used(shader, handle)) {
+        let mut target = msl::BindTarget::default();
+        let location = match kind_of(module, var) {
+            kind @ (ResourceKind::UniformBuffer { .. } | ResourceKind::StorageBuffer { .. } | ResourceKind::Other) => {
+                let slot = next_slot(&mut slots[0])?;
+                target.buffer = Some(slot);
+                target.mutable = kind == ResourceKind::StorageBuffer { read_only: false };
+                format!("[[buffer({})]]", slot)
+            }
+            kind @ (ResourceKind::Texture | ResourceKind::StorageTexture) => {
+                let slot = next_slot(&mut slots[1])?;
+                target.texture = Some(slot);
+                target.mutable = kind == ResourceKind::StorageTexture;
+                format!("[[texture({})]]", slot)
+            }
+            ResourceKind::Sampler => {
+                let slot = next_slot(&mut slots[2])?;
+                target.sampler = Some(msl::BindSamplerTarget::Resource(slot));
+                format!("[[sampler({})]]", slot)
+            }
+        };
+        resources_map.insert(binding.clone(), target);
+        bindings.push(report(module, var, binding, location));
+    }
+
+    let options = msl::Options {
+        lang_version: (2, 0),
+        per_entry_point_map: module.entry_points.iter()
+            .map(|ep| (ep.name.clone(), msl::EntryPointResources {
+                resources: resources_map.clone(),
+                ..Default::default()
+            }))
+            .collect(),
+        fake_missing_bindings: false,
+        ..Default::default()
+    };
+    let (code, info) = msl::write_string(module, &shader.info, &options, &msl::PipelineOptions::default())
+        .with_context(|| format!("Failed to export {} to MSL", asset_id))?;
+    let entry_points = entry_point_names(module, info.entry_point_names, "MSL")?;
+
+    Ok(ExportedShader { asset_id: asset_id.to_string(), target: ExportTarget::Msl, code, entry_points, bindings })
+}
+
+fn next_slot(slot: &mut u8) -> anyhow::Result<u8> {
+    let current = *slot;
+    *slot = slot.checked_add(1).context("Too many Metal resources of one kind")?;
+    Ok(current)
+}
+
+/// True if any entry point of `shader` uses the global
+fn is_used(shader: &ValidatedShader, handle: Handle<GlobalVariable>) -> bool {
+    (0..shader.module.entry_points.len()).any(|i| !shader.info.get_entry_point(i)[handle].is_empty())
+}
+
+/// Bound globals accepted by `filter`, ordered by group and binding
+fn resources(
+    module: &Module,
+    filter: impl Fn(Handle<GlobalVariable>) -> bool,
+) -> impl Iterator<Item = (Handle<GlobalVariable>, &GlobalVariable, &ResourceBinding)> {
+    let sorted: BTreeMap<_, _> = module.global_variables.iter()
+        .filter(|(handle, _)| filter(*handle))
+        .filter_map(|(handle, var)| var.binding.as_ref().map(|binding| ((binding.group, binding.binding), (handle, var, binding))))
+        .collect();
+    sorted.into_values()
+}
+
+fn report(module: &Module, var: &GlobalVariable, binding: &ResourceBinding, location: String) -> BindingReport {
+    let fields = match &module.types[var.ty].inner {
+        TypeInner::Struct { members, .. } if var.space != AddressSpace::Handle => members.iter()
+            .map(|member| BufferField {
+                name: member.name.clone().unwrap_or_default(),
+                offset: member.offset,
+                size: module.types[member.ty].inner.size(module.to_ctx()),
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    BindingReport {
+        group: binding.group,
+        binding: binding.binding,
+        name: var.name.clone().unwrap_or_default(),
+        kind: kind_of(module, var),
+        location,
+        fields,
+    }
+}
+
+fn entry_point_names<E: std::error::Error + Send + Sync + 'static>(
+    module: &Module,
+    names: Vec<Result<String, E>>,
+    language: &str,
+) -> anyhow::Result<Vec<(ShaderStage, String)>> {
+    module.entry_points.iter().zip(names)
+        .map(|(ep, name)| {
+            let name = name.with_context(|| format!("Failed to export entry point {} to {}", ep.name, language))?;
+            Ok((validate::stage_of(ep.stage), name))
+        })
+        .collect()
+}
@@ -0,0 +1,236 @@
+//! Render graphs: Shadertoy-style buffer passes that feed each other.
+//!
+//! Each [`Pass`] draws an image shader into its own target texture, or into
+//! the screen for the one pass without a target. Its inputs are bound as the
+//! shader's channels (`iChannel0..3`) in order:
+//!
+//! - [`PassInput::Pass`]: What another pass drew this frame. That pass runs first.
+//! - [`PassInput::PreviousFrame`]: What a `PingPong` pass (possibly the
+//!   reading pass itself) drew the previous frame. Feedback loops go through these.
+//! - [`PassInput::Texture`]: A `TexturePng` asset.
+//!
+//! Passes run in declaration order, except that passes run after the passes
+//! they read this frame.
+
+use crate::validate::ValidatedShader;
+use crate::{AssetEntry, AssetKind};
+use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+
+/// A pass of the render graph.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Hash)]
+pub struct Pass {
+    /// Unique name, like "Buffer A"
+    pub name: String,
+    /// The image shader drawn (A `SumiSource`)
+    pub asset_id: String,
+    /// Where the pass draws, `None` for the screen
+    pub target: Option<PassTarget>,
+    /// Channels of the shader, in order (At most `shader::CHANNEL_COUNT`)
+    pub inputs: Vec<PassInput>,
+}
+
+impl Pass {
+    /// A pass drawing into the screen
+    pub fn new(name: impl Into<String>, asset_id: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            asset_id: asset_id.into(),
+            target: None,
+            inputs: Vec::new(),
+        }
+    }
+
+    pub fn with_target(mut self, target: PassTarget) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    pub fn with_input(mut self, input: PassInput) -> Self {
+        self.inputs.push(input);
+        self
+    }
+}
+
+/// The texture a pass draws into.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct PassTarget {
+    pub format: TargetFormat,
+    /// Size relative to the screen (0.5 is half the width and height)
+    pub scale: f32,
+    pub persistence: Persistence,
+}
+
+impl PassTarget {
+    /// Full screen size, cleared every frame
+    pub fn new(format: TargetFormat) -> Self {
+        Self { format, scale: 1.0, persistence: Persistence::Transient }
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_persistence(mut self, persistence: Persistence) -> Self {
+        self.persistence = persistence;
+        self
+    }
+
+    /// Size of the target for a `width` x `height` screen (At least 1x1)
+    pub fn size(&self, width: u32, height: u32) -> (u32, u32) {
+        let scaled = |size: u32| ((size as f32 * self.scale).round() as u32).max(1);
+        (scaled(width), scaled(height))
+    }
+}
+
+impl Hash for PassTarget {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.format, self.scale.to_bits(), self.persistence).hash(state);
+    }
+}
+
+/// Texel format of a pass target. Both are filterable.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TargetFormat {
+    Rgba8Unorm,
+    /// HDR values and precise feedback
+    Rgba16Float,
+}
+
+/// What a target holds when its pass starts drawing.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Persistence {
+    /// Cleared to black every frame
+    Transient,
+    /// Keeps what the pass drew the previous frame (For blending over it)
+    Persistent,
+    /// Two textures, swapped every frame, so the previous frame can be read
+    /// while drawing the next one
+    PingPong,
+}
+
+/// A channel of a pass.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PassInput {
+    /// The target of a pass, as drawn this frame
+    Pass(String),
+    /// The target of a `PingPong` pass, as drawn the previous frame
+    PreviousFrame(String),
+    /// A `TexturePng` asset
+    Texture(String),
+}
+
+/// Checks a render graph against the project's assets and returns the order
+/// the passes run in (Indices into `passes`).
+///
+/// Exactly one pass draws into the screen and nothing reads it; names are
+/// unique; inputs exist, and the passes read this frame do not form a cycle.
+pub fn schedule(passes: &[Pass], assets: &[AssetEntry]) -> anyhow::Result<Vec<usize>> {
+    let index_of = |name: &str| passes.iter().position(|p| p.name == name);
+
+    // 1. Declarations
+    let screens: Vec<&str> = passes.iter()
+        .filter(|p| p.target.is_none())
+        .map(|p| p.name.as_str())
+        .collect();
+    anyhow::ensure!(screens.len() == 1, "A render graph needs exactly one pass without a target (Found {})", screens.len());
+
+    for (i, pass) in passes.iter().enumerate() {
+        let what = format!("Pass `{}`", pass.name);
+        anyhow::ensure!(index_of(&pass.name) == Some(i), "{} is declared twice", what);
+        anyhow::ensure!(
+            assets.iter().any(|a| a.id == pass.asset_id && matches!(a.kind, AssetKind::SumiSource)),
+            "{} draws `{}`, which is not a SumiSource asset", what, pass.asset_id,
+        );
+        if let Some(target) = &pass.target {
+            anyhow::ensure!(target.scale > 0.0 && target.scale.is_finite(), "{} has a target scale of {}", what, target.scale);
+        }
+        anyhow::ensure!(
+            pass.inputs.len() <= crate::shader::CHANNEL_COUNT as usize,
+            "{} has {} inputs, shaders have {} channels", what, pass.inputs.len(), crate::shader::CHANNEL_COUNT,
+        );
+
+        // 2. Inputs
+        for input in &pass.inputs {
+            match input {
+                PassInput::Pass(name) | PassInput::PreviousFrame(name) => {
+                    let Some(source) = index_of(name).map(|j| &passes[j]) else {
+                        anyhow::bail!("{} reads `{}`, which is not a pass", what, name);
+                    };
+                    let Some(target) = &source.target else {
+                        anyhow::bail!("{} reads `{}`, which draws into the screen", what, name);
+                    };
+                    if matches!(input, PassInput::PreviousFrame(_)) {
+                        anyhow::ensure!(
+                            target.persistence == Persistence::PingPong,
+                            "{} reads the previous frame of `{}`, which is not `PingPong`", what, name,
+                        );
+                    }
+                }
+                PassInput::Texture(id) => anyhow::ensure!(
+                    assets.iter().any(|a| a.id == *id && matches!(a.kind, AssetKind::TexturePng)),
+                    "{} reads `{}`, which is not a TexturePng asset", what, id,
+                ),
+            }
+        }
+    }
+
+    // 3. Declaration order, moving passes after the passes they read
+    let mut order = Vec::with_capacity(passes.len());
+    let mut state = vec![Visit::New; passes.len()];
+    for i in 0..passes.len() {
+        visit(passes, i, &mut state, &mut order, &mut Vec::new())?;
+    }
+    Ok(order)
+}
+
+/// Checks that the shaders of the passes are WGSL image shaders.
+/// `shaders` are the validated shaders of the project.
+pub fn check_shaders(passes: &[Pass], shaders: &[(&AssetEntry, ValidatedShader)]) -> anyhow::Result<()> {
+    for pass in passes {
+        let shader = shaders.iter().find(|(asset, _)| asset.id == pass.asset_id);
+        anyhow::ensure!(
+            shader.is_some_and(|(_, shader)| shader.is_image()),
+            "Pass `{}` draws `{}`, which is not an image shader (`main_image`)", pass.name, pass.asset_id,
+        );
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    New,
+    Visiting,
+    Done,
+}
+
+/// Depth-first: Appends the passes `i` reads this frame, then `i`
+fn visit(passes: &[Pass], i: usize, state: &mut [Visit], order: &mut Vec<usize>, path: &mut Vec<usize>) -> anyhow::Result<()> {
+    match state[i] {
+        Visit::Done => return Ok(()),
+        Visit::Visiting => {
+            let start = path.iter().position(|&j| j == i).unwrap_or(0);
+            let cycle: Vec<&str> = path[start..].iter()
+                .chain(std::iter::once(&i))
+                .map(|&j| passes[j].name.as_str())
+                .collect();
+            anyhow::bail!("Passes read each other in the same frame: {} (Read a `PreviousFrame` to break the cycle)", cycle.join(" -> "));
+        }
+        Visit::New => {}
+    }
+
+    state[i] = Visit::Visiting;
+    path.push(i);
+    for input in &passes[i].inputs {
+        if let PassInput::Pass(name) = input {
+            if let Some(j) = passes.iter().position(|p| p.name == *name) {
+                visit(passes, j, state, order, path)?;
+            }
+        }
+    }
+    path.pop();
+    state[i] = Visit::Done;
+    order.push(i);
+    Ok(())
+}
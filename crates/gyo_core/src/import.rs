@@ -0,0 +1,186 @@
+//! `#import` of `SumiSource` assets by id, within one project.
+//!
+//! A line `#import "noise.wgsl"` at module scope is replaced by the imported
+//! asset, whose own imports are resolved the same way. Every asset is
+//! included once per module, where it is first imported, and import cycles
+//! are errors. The source map keeps pointing into the original assets, so
+//! diagnostics name the file a line came from.
+//!
+//! Every asset is preprocessed (See `preprocess`) with the library's defines
+//! before its imports are resolved, so imports can be conditional.
+
+use crate::preprocess::{self, Defines};
+use crate::shader::{self, ComposedShader};
+use crate::validate::{self, ShaderDiagnostic, ShaderError};
+use crate::ShaderVariant;
+use std::collections::{HashMap, HashSet};
+
+/// The directive, at the start of a line
+pub const DIRECTIVE: &str = "#import";
+
+/// `SumiSource` assets that can be imported, by id, and the defines of the
+/// variant they are compiled for.
+#[derive(Debug, Clone, Default)]
+pub struct Library<'a> {
+    sources: HashMap<&'a str, &'a str>,
+    defines: Defines,
+}
+
+impl<'a> Library<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, asset_id: &'a str, source: &'a str) -> &mut Self {
+        self.sources.insert(asset_id, source);
+        self
+    }
+
+    /// Defines `name` for every asset, over the assets' own `#define`s
+    pub fn define(&mut self, name: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        self.defines.insert(name.into(), value.into());
+        self
+    }
+
+    /// Defines everything a variant of the manifest defines
+    pub fn define_variant(&mut self, variant: &ShaderVariant) -> &mut Self {
+        for (name, value) in &variant.defines {
+            self.define(name.as_str(), value.as_str());
+        }
+        self
+    }
+
+    pub fn defines(&self) -> &Defines {
+        &self.defines
+    }
+
+    pub fn get(&self, asset_id: &str) -> Option<&'a str> {
+        self.sources.get(asset_id).copied()
+    }
+
+    /// Ids imported by any source of the library, preprocessed with its
+    /// defines (Imports in branches left out do not count). These are
+    /// libraries: They are validated as part of the modules importing them.
+    pub fn imported(&self) -> HashSet<&'a str> {
+        let mut imported = HashSet::new();
+        for (asset_id, source) in &self.sources {
+            // Sources that fail to preprocess report it where they are
+            // validated: Their imports count as written until then
+            let preprocessed = preprocess::preprocess(asset_id, source, &self.defines);
+            let source = preprocessed.as_deref().unwrap_or(source);
+            imported.extend(imports(source).into_iter()
+                .filter_map(|(_, id)| self.sources.get_key_value(id?))
+                .map(|(id, _)| *id));
+        }
+        imported
+    }
+}
+
+/// The `#import` lines of `source`: 1-based line and imported id (`None`
+/// if the line does not name an asset).
+pub fn imports(source: &str) -> Vec<(u32, Option<&str>)> {
+    source.lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let rest = line.trim_start().strip_prefix(DIRECTIVE)?;
+            // `#imported` is not the directive
+            if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+                return None;
+            }
+            let id = rest.trim().trim_end_matches(';').trim();
+            let id = id.strip_prefix('"').and_then(|id| id.strip_suffix('"')).unwrap_or(id);
+            Some((i as u32 + 1, Some(id).filter(|id| !id.is_empty() && !id.contains(char::is_whitespace))))
+        })
+        .collect()
+}
+
+/// Replaces the imports of a `SumiSource` with the imported assets.
+pub fn resolve(asset_id: &str, source: &str, library: &Library) -> Result<ComposedShader, ShaderError> {
+    let mut resolver = Resolver {
+        library,
+        composed: ComposedShader::default(),
+        stack: Vec::new(),
+        included: HashSet::new(),
+        diagnostics: Vec::new(),
+    };
+    resolver.include(asset_id, source);
+
+    if resolver.diagnostics.is_empty() {
+        Ok(resolver.composed)
+    } else {
+        Err(ShaderError { diagnostics: resolver.diagnostics })
+    }
+}
+
+/// `shader::prepare` with imports: Resolves them, then wraps image shaders
+/// in the runtime prelude.
+pub fn prepare(asset_id: &str, source: &str, library: &Library) -> Result<ComposedShader, ShaderError> {
+    resolve(asset_id, source, library).map(shader::prepare_composed)
+}
+
+struct Resolver<'l, 'a> {
+    library: &'l Library<'a>,
+    composed: ComposedShader,
+    /// Assets being included, outermost first
+    stack: Vec<String>,
+    included: HashSet<String>,
+    diagnostics: Vec<ShaderDiagnostic>,
+}
+
+impl Resolver<'_, '_> {
+    fn include(&mut self, asset_id: &str, source: &str) {
+        self.stack.push(asset_id.to_string());
+        self.included.insert(asset_id.to_string());
+
+        let source = match preprocess::preprocess(asset_id, source, self.library.defines()) {
+            Ok(source) => source,
+            Err(e) => {
+                self.diagnostics.extend(e.diagnostics);
+                self.stack.pop();
+                return;
+            }
+        };
+        let lines: Vec<&str> = source.lines().collect();
+        let mut next_line = 1;
+        for (line, id) in imports(&source) {
+            self.append_lines(asset_id, &lines, next_line, line);
+            next_line = line + 1;
+
+            let text = lines[line as usize - 1];
+            let Some(id) = id else {
+                self.error(asset_id, line, text, "Expected an asset id, like `#import \"noise.wgsl\"`".to_string());
+                continue;
+            };
+            if self.stack.iter().any(|s| s == id) {
+                let cycle = self.stack.iter()
+                    .skip_while(|s| *s != id)
+                    .chain(std::iter::once(&id.to_string()))
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                self.error(asset_id, line, text, format!("Import cycle: {}", cycle));
+            } else if self.included.contains(id) {
+                // Already part of the module
+            } else if let Some(imported) = self.library.get(id) {
+                self.include(id, imported);
+            } else {
+                self.error(asset_id, line, text, format!("No SumiSource asset `{}` to import", id));
+            }
+        }
+        self.append_lines(asset_id, &lines, next_line, lines.len() as u32 + 1);
+
+        self.stack.pop();
+    }
+
+    /// Appends lines `first..end` (1-based) of an asset
+    fn append_lines(&mut self, asset_id: &str, lines: &[&str], first: u32, end: u32) {
+        if first < end {
+            let text = lines[first as usize - 1..end as usize - 1].join("\n");
+            self.composed.append_from(asset_id, first, &text);
+        }
+    }
+
+    fn error(&mut self, asset_id: &str, line: u32, text: &str, message: String) {
+        self.diagnostics.push(validate::line_diagnostic(asset_id, line, text, message));
+    }
+}
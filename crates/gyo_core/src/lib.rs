@@ -1,10 +1,34 @@
 use binrw::{binrw, BinRead, BinWrite};
 use serde::{Serialize, Deserialize};
-use std::io::Write;
+use std::io::{Read, Write};
+
+pub mod background;
+pub mod export;
+pub mod graph;
+pub mod import;
+pub mod pack;
+pub mod particles;
+pub mod pipeline;
+pub mod post;
+pub mod preprocess;
+pub mod reflect;
+pub mod shader;
+pub mod texture;
+pub mod validate;
+pub mod vector;
+
+pub use pack::{ProjectDeclarations, ProjectPacker};
+pub use pipeline::PipelineState;
 
 /// The Magic Signature: "GYO1"
 const MAGIC: &[u8; 4] = b"GYO1";
 
+/// Schema Version written by `write_new`. Manifests of older versions are
+/// still read: 1 has no `variants`, 1 and 2 no `pipelines`, 1 to 3 no `passes`,
+/// 1 to 4 no `effects`, 1 to 5 no `background` (They get the sky), 1 to 6
+/// no `particles`.
+const VERSION: u32 = 7;
+
 #[binrw]
 #[brw(big)] // Network Endian (Big Endian) for portability
 #[derive(Debug, Clone)]
@@ -39,6 +63,73 @@ pub struct Manifest {
     pub assets: Vec<AssetEntry>,
     // Reserved for Phase 2: Compute Kernels
     pub compute_kernels: Vec<String>, 
+    /// Named sets of defines the `SumiSource` assets can be compiled with.
+    /// The first one is the default.
+    pub variants: Vec<ShaderVariant>,
+    /// Pipeline state of shader assets, at most one per asset
+    pub pipelines: Vec<PipelineState>,
+    /// The render graph (See `graph`). Empty draws the main shader alone.
+    pub passes: Vec<graph::Pass>,
+    /// Post-processing effects, in order (See `post`). Empty draws straight
+    /// into the screen.
+    pub effects: Vec<post::Effect>,
+    /// Drawn behind sprites
+    pub background: background::Background,
+    /// Particle emitters, drawn over the sprites (See `particles`)
+    pub particles: Vec<particles::Emitter>,
+}
+
+/// Fields of a schema version 1 manifest, in order
+type ManifestV1 = (String, String, u64, Vec<AssetEntry>, Vec<String>);
+/// Fields of a schema version 2 manifest, in order
+type ManifestV2 = (String, String, u64, Vec<AssetEntry>, Vec<String>, Vec<ShaderVariant>);
+/// Fields of a schema version 3 manifest, in order
+type ManifestV3 = (String, String, u64, Vec<AssetEntry>, Vec<String>, Vec<ShaderVariant>, Vec<PipelineState>);
+/// Fields of a schema version 4 manifest, in order
+type ManifestV4 = (String, String, u64, Vec<AssetEntry>, Vec<String>, Vec<ShaderVariant>, Vec<PipelineState>, Vec<graph::Pass>);
+/// Fields of a schema version 5 manifest, in order
+type ManifestV5 = (String, String, u64, Vec<AssetEntry>, Vec<String>, Vec<ShaderVariant>, Vec<PipelineState>, Vec<graph::Pass>, Vec<post::Effect>);
+/// Fields of a schema version 6 manifest, in order
+type ManifestV6 = (String, String, u64, Vec<AssetEntry>, Vec<String>, Vec<ShaderVariant>, Vec<PipelineState>, Vec<graph::Pass>, Vec<post::Effect>, background::Background);
+
+impl Manifest {
+    /// The declared pipeline state of a shader asset
+    pub fn pipeline(&self, asset_id: &str) -> Option<&PipelineState> {
+        self.pipelines.iter().find(|p| p.asset_id == asset_id)
+    }
+
+    /// The variant called `name`, or the default one for `None`.
+    /// `Ok(None)` if the project declares no variants.
+    pub fn variant(&self, name: Option<&str>) -> anyhow::Result<Option<&ShaderVariant>> {
+        let Some(name) = name else {
+            return Ok(self.variants.first());
+        };
+        match self.variants.iter().find(|v| v.name == name) {
+            Some(variant) => Ok(Some(variant)),
+            None => anyhow::bail!(
+                "No variant `{}` in the manifest (Declared: {})",
+                name,
+                self.variants.iter().map(|v| v.name.as_str()).collect::<Vec<_>>().join(", "),
+            ),
+        }
+    }
+}
+
+/// A named set of `#define`s, like a quality preset (See `preprocess`).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ShaderVariant {
+    pub name: String,
+    /// Name and value (Empty for flags)
+    pub defines: Vec<(String, String)>,
+}
+
+impl ShaderVariant {
+    pub fn new<N: Into<String>, V: Into<String>>(name: impl Into<String>, defines: impl IntoIterator<Item = (N, V)>) -> Self {
+        Self {
+            name: name.into(),
+            defines: defines.into_iter().map(|(n, v)| (n.into(), v.into())).collect(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -50,11 +141,51 @@ pub struct AssetEntry {
     pub size: u64,
 }
 
+impl AssetEntry {
+    /// The bytes of this asset in the decompressed `payload`
+    pub fn bytes<'a>(&self, payload: &'a [u8]) -> anyhow::Result<&'a [u8]> {
+        let start = usize::try_from(self.offset).ok();
+        let end = self.offset.checked_add(self.size).and_then(|end| usize::try_from(end).ok());
+        start.zip(end)
+            .and_then(|(start, end)| payload.get(start..end))
+            .ok_or_else(|| anyhow::anyhow!("Corrupt file: Asset defined outside payload bounds"))
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum AssetKind {
     SumiSource, // The user's S2L/WGSL code
     TexturePng,
     MeshGltf,   // Reserved for Phase 3
+    GlslSource, // Shadertoy-style GLSL fragment code (`mainImage`)
+    /// A precompiled SPIR-V module (Little-endian words).
+    /// Only a fragment entry point: Drawn as an image shader.
+    /// Vertex and fragment entry points: Drawn as sprites.
+    SpirV { entry_points: Vec<EntryPoint> },
+    /// SVG path data (The `d` attribute of a `<path>`), as UTF-8 text.
+    /// See `vector`.
+    SvgPath,
+}
+
+/// Pipeline stage of a shader entry point.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+    Compute,
+}
+
+/// A named entry point of a shader module.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EntryPoint {
+    pub name: String,
+    pub stage: ShaderStage,
+}
+
+impl EntryPoint {
+    pub fn new(name: impl Into<String>, stage: ShaderStage) -> Self {
+        Self { name: name.into(), stage }
+    }
 }
 
 impl GyoshoFile {
@@ -75,7 +206,7 @@ impl GyoshoFile {
         // 3. Construct and Write File
         let file = GyoshoFile {
             magic: *MAGIC,
-            version: 1,
+            version: VERSION,
             manifest_len: manifest_bytes.len() as u32,
             manifest_bytes,
             compressed_payload,
@@ -90,7 +221,52 @@ impl GyoshoFile {
         reader: &mut R
     ) -> anyhow::Result<Manifest> {
         let file = GyoshoFile::read(reader)?;
-        let manifest: Manifest = bincode::deserialize(&file.manifest_bytes)?;
-        Ok(manifest)
+        file.manifest()
+    }
+
+    /// Deserializes the Manifest of a parsed file
+    pub fn manifest(&self) -> anyhow::Result<Manifest> {
+        match self.version {
+            1 => {
+                let (title, author, timestamp, assets, compute_kernels): ManifestV1 =
+                    bincode::deserialize(&self.manifest_bytes)?;
+                Ok(Manifest { title, author, timestamp, assets, compute_kernels, variants: Vec::new(), pipelines: Vec::new(), passes: Vec::new(), effects: Vec::new(), background: Default::default(), particles: Vec::new() })
+            }
+            2 => {
+                let (title, author, timestamp, assets, compute_kernels, variants): ManifestV2 =
+                    bincode::deserialize(&self.manifest_bytes)?;
+                Ok(Manifest { title, author, timestamp, assets, compute_kernels, variants, pipelines: Vec::new(), passes: Vec::new(), effects: Vec::new(), background: Default::default(), particles: Vec::new() })
+            }
+            3 => {
+                let (title, author, timestamp, assets, compute_kernels, variants, pipelines): ManifestV3 =
+                    bincode::deserialize(&self.manifest_bytes)?;
+                Ok(Manifest { title, author, timestamp, assets, compute_kernels, variants, pipelines, passes: Vec::new(), effects: Vec::new(), background: Default::default(), particles: Vec::new() })
+            }
+            4 => {
+                let (title, author, timestamp, assets, compute_kernels, variants, pipelines, passes): ManifestV4 =
+                    bincode::deserialize(&self.manifest_bytes)?;
+                Ok(Manifest { title, author, timestamp, assets, compute_kernels, variants, pipelines, passes, effects: Vec::new(), background: Default::default(), particles: Vec::new() })
+            }
+            5 => {
+                let (title, author, timestamp, assets, compute_kernels, variants, pipelines, passes, effects): ManifestV5 =
+                    bincode::deserialize(&self.manifest_bytes)?;
+                Ok(Manifest { title, author, timestamp, assets, compute_kernels, variants, pipelines, passes, effects, background: Default::default(), particles: Vec::new() })
+            }
+            6 => {
+                let (title, author, timestamp, assets, compute_kernels, variants, pipelines, passes, effects, background): ManifestV6 =
+                    bincode::deserialize(&self.manifest_bytes)?;
+                Ok(Manifest { title, author, timestamp, assets, compute_kernels, variants, pipelines, passes, effects, background, particles: Vec::new() })
+            }
+            VERSION => Ok(bincode::deserialize(&self.manifest_bytes)?),
+            version => anyhow::bail!("Unsupported GYO schema version {} (Expected at most {})", version, VERSION),
+        }
+    }
+
+    /// Decompresses the payload the Manifest's asset offsets point into
+    pub fn decompress_payload(&self) -> anyhow::Result<Vec<u8>> {
+        let mut decoder = zstd::Decoder::new(self.compressed_payload.as_slice())?;
+        let mut payload = Vec::new();
+        decoder.read_to_end(&mut payload)?;
+        Ok(payload)
     }
 }
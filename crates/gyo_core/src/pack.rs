@@ -0,0 +1,308 @@
+use crate::background::Background;
+use crate::graph::{self, Pass};
+use crate::particles::{self, Emitter};
+use crate::post::{self, Effect};
+use crate::texture::Texture;
+use crate::validate;
+use crate::vector;
+use crate::{AssetEntry, AssetKind, GyoshoFile, Manifest, PipelineState, ShaderVariant};
+use anyhow::Context;
+use serde::Deserialize;
+use std::io::Cursor;
+use std::path::Path;
+
+/// The file of a project directory declaring what its assets cannot say
+/// themselves (See [`ProjectDeclarations`])
+pub const DECLARATIONS_FILE: &str = "gyo.ron";
+
+/// What a project directory declares in [`DECLARATIONS_FILE`], in RON.
+/// Fields can be left out; the declarations in them are written in full,
+/// as in the manifest:
+///
+/// ```ron
+/// (
+///     background: Some(Gradient(bottom: (0.0, 0.0, 0.1, 1.0), top: (0.9, 0.4, 0.1, 1.0))),
+///     variants: [(name: "fast", defines: [("FAST", "")])],
+///     effects: [Fxaa],
+/// )
+/// ```
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectDeclarations {
+    /// The directory name unless set
+    pub title: Option<String>,
+    pub author: Option<String>,
+    /// The sky unless set
+    pub background: Option<Background>,
+    pub variants: Vec<ShaderVariant>,
+    pub pipelines: Vec<PipelineState>,
+    pub passes: Vec<Pass>,
+    pub effects: Vec<Effect>,
+    pub particles: Vec<Emitter>,
+}
+
+/// Collects assets into a single payload and writes them as a `.gyo` file.
+#[derive(Debug, Clone)]
+pub struct ProjectPacker {
+    pub title: String,
+    pub author: String,
+    pub timestamp: u64,
+    /// Drawn behind sprites (The sky unless set)
+    pub background: Background,
+    assets: Vec<AssetEntry>,
+    variants: Vec<ShaderVariant>,
+    pipelines: Vec<PipelineState>,
+    passes: Vec<Pass>,
+    effects: Vec<Effect>,
+    emitters: Vec<Emitter>,
+    payload: Vec<u8>,
+}
+
+impl ProjectPacker {
+    pub fn new(title: impl Into<String>, author: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            author: author.into(),
+            timestamp: 0,
+            background: Background::default(),
+            assets: Vec::new(),
+            variants: Vec::new(),
+            pipelines: Vec::new(),
+            passes: Vec::new(),
+            effects: Vec::new(),
+            emitters: Vec::new(),
+            payload: Vec::new(),
+        }
+    }
+
+    /// Packs an unpacked project directory.
+    ///
+    /// - `*.wgsl` files become `SumiSource` assets
+    /// - `*.glsl` and `*.frag` files become `GlslSource` assets
+    /// - `*.spv` files become `SpirV` assets declaring all their entry points
+    /// - `*.png` files become `TexturePng` assets
+    /// - `*.svgpath` files (SVG path data) become `SvgPath` assets
+    /// - `gyo.ron` declares the variants, pipeline state, render graph,
+    ///   effects, background and particle emitters (See
+    ///   [`ProjectDeclarations`])
+    ///
+    /// Other files are ignored. Assets are added in file name order so the
+    /// output is reproducible. The title is the directory name unless
+    /// `gyo.ron` sets one.
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> anyhow::Result<Self> {
+        let dir = dir.as_ref();
+        let title = dir.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut packer = Self::new(title, "");
+
+        let mut paths = std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read project directory {}", dir.display()))?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.sort();
+
+        for path in paths {
+            let extension = path.extension().and_then(|ext| ext.to_str());
+            if !matches!(extension, Some("wgsl" | "glsl" | "frag" | "spv" | "png" | "svgpath")) {
+                continue;
+            }
+            let id = path.file_name().unwrap().to_string_lossy().into_owned();
+            let bytes = std::fs::read(&path)
+                .with_context(|| format!("Failed to read asset {}", path.display()))?;
+            let kind = match extension {
+                Some("wgsl") => AssetKind::SumiSource,
+                Some("glsl" | "frag") => AssetKind::GlslSource,
+                Some("spv") => AssetKind::SpirV {
+                    entry_points: validate::spirv_entry_points(&bytes)
+                        .with_context(|| format!("Failed to read entry points of {}", path.display()))?,
+                },
+                Some("svgpath") => AssetKind::SvgPath,
+                _ => AssetKind::TexturePng,
+            };
+            packer.add_asset(id, kind, &bytes);
+        }
+
+        let declarations = dir.join(DECLARATIONS_FILE);
+        if declarations.is_file() {
+            let text = std::fs::read_to_string(&declarations)
+                .with_context(|| format!("Failed to read {}", declarations.display()))?;
+            let declarations: ProjectDeclarations = ron::from_str(&text)
+                .with_context(|| format!("Failed to read {}", declarations.display()))?;
+            packer.declare(declarations);
+        }
+
+        Ok(packer)
+    }
+
+    /// Adds what `declarations` declares (See [`ProjectPacker::from_dir`])
+    pub fn declare(&mut self, declarations: ProjectDeclarations) -> &mut Self {
+        let ProjectDeclarations { title, author, background, variants, pipelines, passes, effects, particles } = declarations;
+        if let Some(title) = title {
+            self.title = title;
+        }
+        if let Some(author) = author {
+            self.author = author;
+        }
+        if let Some(background) = background {
+            self.background = background;
+        }
+        for variant in variants {
+            self.add_variant(variant);
+        }
+        for pipeline in pipelines {
+            self.add_pipeline(pipeline);
+        }
+        for pass in passes {
+            self.add_pass(pass);
+        }
+        for effect in effects {
+            self.add_effect(effect);
+        }
+        for emitter in particles {
+            self.add_emitter(emitter);
+        }
+        self
+    }
+
+    /// Appends an asset to the payload.
+    pub fn add_asset(&mut self, id: impl Into<String>, kind: AssetKind, bytes: &[u8]) -> &mut Self {
+        self.assets.push(AssetEntry {
+            id: id.into(),
+            kind,
+            offset: self.payload.len() as u64,
+            size: bytes.len() as u64,
+        });
+        self.payload.extend_from_slice(bytes);
+        self
+    }
+
+    /// Declares a variant (The first one is the default).
+    /// Replaces an earlier variant of the same name.
+    pub fn add_variant(&mut self, variant: ShaderVariant) -> &mut Self {
+        match self.variants.iter_mut().find(|v| v.name == variant.name) {
+            Some(existing) => *existing = variant,
+            None => self.variants.push(variant),
+        }
+        self
+    }
+
+    /// Declares how a shader asset is drawn.
+    /// Replaces an earlier declaration for the same asset.
+    pub fn add_pipeline(&mut self, pipeline: PipelineState) -> &mut Self {
+        match self.pipelines.iter_mut().find(|p| p.asset_id == pipeline.asset_id) {
+            Some(existing) => *existing = pipeline,
+            None => self.pipelines.push(pipeline),
+        }
+        self
+    }
+
+    /// Appends a pass to the render graph (See `graph`).
+    pub fn add_pass(&mut self, pass: Pass) -> &mut Self {
+        self.passes.push(pass);
+        self
+    }
+
+    /// Appends a post-processing effect (See `post`).
+    pub fn add_effect(&mut self, effect: Effect) -> &mut Self {
+        self.effects.push(effect);
+        self
+    }
+
+    /// Declares a particle emitter (See `particles`).
+    /// Replaces an earlier emitter of the same name.
+    pub fn add_emitter(&mut self, emitter: Emitter) -> &mut Self {
+        match self.emitters.iter_mut().find(|e| e.name == emitter.name) {
+            Some(existing) => *existing = emitter,
+            None => self.emitters.push(emitter),
+        }
+        self
+    }
+
+    pub fn manifest(&self) -> Manifest {
+        Manifest {
+            title: self.title.clone(),
+            author: self.author.clone(),
+            timestamp: self.timestamp,
+            assets: self.assets.clone(),
+            compute_kernels: vec![],
+            variants: self.variants.clone(),
+            pipelines: self.pipelines.clone(),
+            passes: self.passes.clone(),
+            effects: self.effects.clone(),
+            background: self.background.clone(),
+            particles: self.emitters.clone(),
+        }
+    }
+
+    /// Parses and validates every shader asset with naga, in every variant,
+    /// the entry points of the pipeline declarations, the render graph, the
+    /// post-processing effects, the background and the particle emitters.
+    /// The error is a `validate::ShaderError` when a shader is invalid.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !self.passes.is_empty() {
+            graph::schedule(&self.passes, &self.assets)?;
+        }
+        post::check(&self.effects, &self.assets)?;
+        self.background.check(&self.assets)?;
+        particles::check(&self.emitters, &self.assets)?;
+        vector::read_paths(&self.assets, |asset| Ok(self.asset_bytes(asset)))?;
+        for asset in self.assets.iter().filter(|a| post::is_lut(&self.effects, &a.id)) {
+            let lut = Texture::read_png(self.asset_bytes(asset))
+                .with_context(|| format!("Failed to decode texture {}", asset.id))?;
+            post::lut_size(&asset.id, &lut)?;
+        }
+        let validate_variant = |variant: Option<&ShaderVariant>| -> anyhow::Result<()> {
+            let assets = self.assets.iter().map(|a| (a, self.asset_bytes(a)));
+            let mut shaders = validate::validate_all(assets, variant)?;
+            validate::apply_pipelines(&mut shaders, &self.pipelines)?;
+            graph::check_shaders(&self.passes, &shaders)?;
+            post::check_shaders(&self.effects, &shaders)?;
+            self.background.check_shader(&shaders)
+        };
+        if self.variants.is_empty() {
+            validate_variant(None)?;
+            return Ok(());
+        }
+
+        let mut diagnostics = Vec::new();
+        for variant in &self.variants {
+            match validate_variant(Some(variant)).map_err(|e| e.downcast::<validate::ShaderError>()) {
+                Ok(()) => {}
+                Err(Ok(e)) => diagnostics.extend(e.diagnostics.into_iter().map(|mut diagnostic| {
+                    diagnostic.notes.push(format!("In variant `{}`", variant.name));
+                    diagnostic
+                })),
+                Err(Err(e)) => return Err(e.context(format!("In variant `{}`", variant.name))),
+            }
+        }
+        if diagnostics.is_empty() {
+            Ok(())
+        } else {
+            Err(validate::ShaderError { diagnostics }.into())
+        }
+    }
+
+    fn asset_bytes(&self, asset: &AssetEntry) -> &[u8] {
+        &self.payload[asset.offset as usize..(asset.offset + asset.size) as usize]
+    }
+
+    /// Validates the shaders and writes the `.gyo` file.
+    pub fn write<W: std::io::Write + std::io::Seek>(&self, writer: &mut W) -> anyhow::Result<()> {
+        self.validate()?;
+        self.write_unchecked(writer)
+    }
+
+    /// Writes the `.gyo` file without validating the shaders.
+    /// For tools that validate later anyway (`ProjectLoader` does).
+    pub fn write_unchecked<W: std::io::Write + std::io::Seek>(&self, writer: &mut W) -> anyhow::Result<()> {
+        GyoshoFile::write_new(writer, &self.manifest(), &self.payload)
+    }
+
+    /// Encodes the project into an in-memory `.gyo` file
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let mut buffer = Cursor::new(Vec::new());
+        self.write(&mut buffer)?;
+        Ok(buffer.into_inner())
+    }
+}
@@ -0,0 +1,201 @@
+//! Particle emitters, simulated on the GPU and drawn with the sprites.
+//!
+//! Each emitter owns a ring of `capacity` particles. New ones overwrite the
+//! oldest slots, so an emitter shows at most `rate * lifetime` particles,
+//! and at most `capacity`. Positions are in world units, like sprites.
+
+use crate::{AssetEntry, AssetKind};
+use serde::{Deserialize, Serialize};
+
+/// Particles an emitter can hold at most
+pub const MAX_PARTICLES: u32 = 1 << 20;
+
+/// A source of particles.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Emitter {
+    /// Unique among the project's emitters
+    pub name: String,
+    /// Particles alive at once at most
+    pub capacity: u32,
+    /// Particles spawned per second
+    pub rate: f32,
+    /// Where particles spawn: `[x, y, width, height]`, uniformly
+    pub area: [f32; 4],
+    /// Smallest and largest initial velocity, per axis (World units per second)
+    pub velocity: [[f32; 2]; 2],
+    /// Shortest and longest life in seconds
+    pub lifetime: [f32; 2],
+    /// Width and height of the particle sprites, scaled by a random factor
+    /// between `scale[0]` and `scale[1]`
+    pub size: [f32; 2],
+    pub scale: [f32; 2],
+    /// Linear RGBA at birth and at death, blended over the particle's life
+    pub color: [f32; 4],
+    pub color_end: [f32; 4],
+    /// Turns the height of particles along their velocity (Streaks of rain)
+    pub align: bool,
+    /// Sprite layer, like `InstanceRaw::pos.z` (0 in front, 1 at the back)
+    pub layer: f32,
+    /// A `TexturePng` asset the particles sample whole, or flat colour
+    pub atlas: Option<String>,
+    pub forces: Forces,
+}
+
+/// What moves particles after they spawn.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Forces {
+    /// Acceleration (World units per second squared, `y` points down)
+    pub gravity: [f32; 2],
+    /// Velocity of the air, which particles pick up at the `drag` rate
+    pub wind: [f32; 2],
+    /// Fraction of the difference to the wind's velocity lost per second
+    pub drag: f32,
+    /// Strength of the turbulence (An acceleration)
+    pub noise: f32,
+    /// Size of the turbulence's swirls in world units
+    pub noise_scale: f32,
+}
+
+impl Default for Forces {
+    /// No forces (Swirls of 100 units, once turbulence is added)
+    fn default() -> Self {
+        Self { gravity: [0.0; 2], wind: [0.0; 2], drag: 0.0, noise: 0.0, noise_scale: 100.0 }
+    }
+}
+
+impl Forces {
+    pub fn with_gravity(mut self, gravity: [f32; 2]) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    pub fn with_wind(mut self, wind: [f32; 2], drag: f32) -> Self {
+        self.wind = wind;
+        self.drag = drag;
+        self
+    }
+
+    pub fn with_noise(mut self, noise: f32, noise_scale: f32) -> Self {
+        self.noise = noise;
+        self.noise_scale = noise_scale;
+        self
+    }
+}
+
+impl Emitter {
+    /// White 8x8 particles spawned at the origin, standing still for a
+    /// second, `capacity` per second
+    pub fn new(name: impl Into<String>, capacity: u32) -> Self {
+        Self {
+            name: name.into(),
+            capacity,
+            rate: capacity as f32,
+            area: [0.0; 4],
+            velocity: [[0.0; 2]; 2],
+            lifetime: [1.0, 1.0],
+            size: [8.0, 8.0],
+            scale: [1.0, 1.0],
+            color: [1.0; 4],
+            color_end: [1.0; 4],
+            align: false,
+            layer: 0.0,
+            atlas: None,
+            forces: Forces::default(),
+        }
+    }
+
+    pub fn with_rate(mut self, rate: f32) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    pub fn with_area(mut self, area: [f32; 4]) -> Self {
+        self.area = area;
+        self
+    }
+
+    pub fn with_velocity(mut self, min: [f32; 2], max: [f32; 2]) -> Self {
+        self.velocity = [min, max];
+        self
+    }
+
+    pub fn with_lifetime(mut self, min: f32, max: f32) -> Self {
+        self.lifetime = [min, max];
+        self
+    }
+
+    pub fn with_size(mut self, size: [f32; 2]) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_scale(mut self, min: f32, max: f32) -> Self {
+        self.scale = [min, max];
+        self
+    }
+
+    /// Fades from `color` to `color_end` over the particle's life
+    pub fn with_color(mut self, color: [f32; 4], color_end: [f32; 4]) -> Self {
+        self.color = color;
+        self.color_end = color_end;
+        self
+    }
+
+    pub fn with_align(mut self, align: bool) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn with_layer(mut self, layer: f32) -> Self {
+        self.layer = layer;
+        self
+    }
+
+    pub fn with_atlas(mut self, atlas: impl Into<String>) -> Self {
+        self.atlas = Some(atlas.into());
+        self
+    }
+
+    pub fn with_forces(mut self, forces: Forces) -> Self {
+        self.forces = forces;
+        self
+    }
+
+    /// Checks the settings, and that the atlas is a `TexturePng` asset
+    pub fn check(&self, assets: &[AssetEntry]) -> anyhow::Result<()> {
+        let name = &self.name;
+        anyhow::ensure!(
+            (1..=MAX_PARTICLES).contains(&self.capacity),
+            "Emitter `{}` holds {} particles (1 to {})", name, self.capacity, MAX_PARTICLES,
+        );
+        anyhow::ensure!(self.rate.is_finite() && self.rate >= 0.0, "Emitter `{}` has a rate of {}", name, self.rate);
+        let [shortest, longest] = self.lifetime;
+        anyhow::ensure!(
+            shortest > 0.0 && shortest <= longest,
+            "Emitter `{}` lives {} to {} seconds (Above 0, shortest first)", name, shortest, longest,
+        );
+        anyhow::ensure!(
+            self.forces.noise_scale > 0.0,
+            "Emitter `{}` has turbulence swirls of size {}", name, self.forces.noise_scale,
+        );
+        if let Some(atlas) = &self.atlas {
+            anyhow::ensure!(
+                assets.iter().any(|a| &a.id == atlas && matches!(a.kind, AssetKind::TexturePng)),
+                "Emitter `{}` samples `{}`, which is not a TexturePng asset", name, atlas,
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Checks every emitter, and that their names are unique
+pub fn check(emitters: &[Emitter], assets: &[AssetEntry]) -> anyhow::Result<()> {
+    for (i, emitter) in emitters.iter().enumerate() {
+        anyhow::ensure!(
+            emitters[..i].iter().all(|e| e.name != emitter.name),
+            "Emitter `{}` is declared twice", emitter.name,
+        );
+        emitter.check(assets)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,125 @@
+//! Fixed-function state of the pipeline a shader asset is drawn with.
+//!
+//! Declared per shader asset in the `Manifest`. The runtime maps these onto
+//! its graphics API; assets without a declaration get [`PipelineState::new`].
+
+use crate::EntryPoint;
+use serde::{Deserialize, Serialize};
+
+/// How a shader asset is drawn.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PipelineState {
+    /// The shader asset this state applies to
+    pub asset_id: String,
+    /// Entry points to use instead of the conventional names
+    /// (`main` for image shaders, `vs_main`/`fs_main` for sprites)
+    pub entry_points: Vec<EntryPoint>,
+    /// `None`: Replace for image shaders, alpha blending for sprites
+    pub blend: Option<BlendMode>,
+    pub topology: Topology,
+    pub cull: CullMode,
+    /// Depth test and writes against a depth buffer cleared to 1.0 each
+    /// frame. `None` draws without a depth buffer.
+    pub depth: Option<DepthState>,
+}
+
+impl PipelineState {
+    /// The defaults: Conventional entry points, triangles, no culling, no depth
+    pub fn new(asset_id: impl Into<String>) -> Self {
+        Self {
+            asset_id: asset_id.into(),
+            entry_points: Vec::new(),
+            blend: None,
+            topology: Topology::TriangleList,
+            cull: CullMode::None,
+            depth: None,
+        }
+    }
+
+    pub fn with_entry_point(mut self, entry_point: EntryPoint) -> Self {
+        self.entry_points.retain(|e| e.stage != entry_point.stage);
+        self.entry_points.push(entry_point);
+        self
+    }
+
+    pub fn with_blend(mut self, blend: BlendMode) -> Self {
+        self.blend = Some(blend);
+        self
+    }
+
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    pub fn with_cull(mut self, cull: CullMode) -> Self {
+        self.cull = cull;
+        self
+    }
+
+    pub fn with_depth(mut self, depth: DepthState) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+}
+
+/// Color blending with what is already in the target.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    /// Overwrite the target
+    Replace,
+    /// `src * src.a + dst * (1 - src.a)`
+    Alpha,
+    /// `src + dst * (1 - src.a)`, for premultiplied colors
+    Premultiplied,
+    /// `src * src.a + dst`, for glows and particles
+    Additive,
+    /// `src * dst`
+    Multiply,
+}
+
+/// How vertices are assembled into primitives.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Topology {
+    PointList,
+    LineList,
+    LineStrip,
+    TriangleList,
+    TriangleStrip,
+}
+
+/// Which triangles are discarded. Counter-clockwise triangles face front.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CullMode {
+    None,
+    Front,
+    Back,
+}
+
+/// Depth test and writes.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DepthState {
+    /// Store the depth of fragments that pass the test
+    pub write: bool,
+    pub compare: CompareFunction,
+}
+
+impl Default for DepthState {
+    /// Nearer fragments win
+    fn default() -> Self {
+        Self { write: true, compare: CompareFunction::Less }
+    }
+}
+
+/// Passes when `new <op> stored`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompareFunction {
+    Never,
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
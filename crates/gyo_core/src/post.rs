@@ -0,0 +1,165 @@
+//! Post-processing: Effects applied in order to the finished frame.
+//!
+//! With effects declared, the scene draws into an HDR target instead of the
+//! screen. Each effect reads what the one before it wrote and the last one
+//! writes the screen, so values above 1 survive until an effect (Usually
+//! [`Effect::ToneMap`]) brings them into range.
+
+use crate::texture::Texture;
+use crate::validate::ValidatedShader;
+use crate::{AssetEntry, AssetKind};
+use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+
+/// A post-processing effect.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Hash)]
+pub enum Effect {
+    /// Blurred bright parts added back
+    Bloom(Bloom),
+    /// HDR colours mapped into display range
+    ToneMap(ToneMap),
+    /// Fast approximate anti-aliasing (Best after `ToneMap`)
+    Fxaa,
+    /// Colours looked up in a `TexturePng` asset (See [`lut_size`])
+    ColorGrade { lut: String },
+    /// An image shader (`SumiSource`) reading the frame as channel 0
+    Custom { asset_id: String },
+}
+
+/// Settings of [`Effect::Bloom`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Bloom {
+    /// Brightness above which pixels bloom
+    pub threshold: f32,
+    /// How much of the blurred light is added back
+    pub intensity: f32,
+    /// Spread of the blur, in half-size texels per tap
+    pub radius: f32,
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Self { threshold: 1.0, intensity: 0.6, radius: 1.0 }
+    }
+}
+
+impl Bloom {
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+}
+
+impl Hash for Bloom {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.threshold.to_bits(), self.intensity.to_bits(), self.radius.to_bits()).hash(state);
+    }
+}
+
+/// Settings of [`Effect::ToneMap`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ToneMap {
+    pub operator: ToneMapper,
+    /// Colours are multiplied by it first
+    pub exposure: f32,
+}
+
+impl ToneMap {
+    /// Exposure 1
+    pub fn new(operator: ToneMapper) -> Self {
+        Self { operator, exposure: 1.0 }
+    }
+
+    pub fn with_exposure(mut self, exposure: f32) -> Self {
+        self.exposure = exposure;
+        self
+    }
+}
+
+impl Hash for ToneMap {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.operator, self.exposure.to_bits()).hash(state);
+    }
+}
+
+/// Curve of [`Effect::ToneMap`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ToneMapper {
+    /// `c / (1 + c)`
+    Reinhard,
+    /// Narkowicz's fit of the ACES filmic curve
+    Aces,
+}
+
+/// True if `asset_id` is the shader of a custom effect (Not drawn on its own)
+pub fn is_effect_shader(effects: &[Effect], asset_id: &str) -> bool {
+    effects.iter().any(|e| matches!(e, Effect::Custom { asset_id: id } if id == asset_id))
+}
+
+/// True if `asset_id` is the LUT of a colour grade (Not a channel)
+pub fn is_lut(effects: &[Effect], asset_id: &str) -> bool {
+    effects.iter().any(|e| matches!(e, Effect::ColorGrade { lut } if lut == asset_id))
+}
+
+/// Checks that the assets effects refer to exist and have the right kind.
+pub fn check(effects: &[Effect], assets: &[AssetEntry]) -> anyhow::Result<()> {
+    let has = |id: &str, kind: fn(&AssetKind) -> bool| assets.iter().any(|a| a.id == id && kind(&a.kind));
+    for (i, effect) in effects.iter().enumerate() {
+        match effect {
+            Effect::Bloom(bloom) => anyhow::ensure!(
+                bloom.radius > 0.0 && bloom.intensity >= 0.0,
+                "Effect {} (Bloom) needs a positive radius and intensity", i,
+            ),
+            Effect::ToneMap(tone_map) => anyhow::ensure!(
+                tone_map.exposure > 0.0,
+                "Effect {} (ToneMap) has an exposure of {}", i, tone_map.exposure,
+            ),
+            Effect::Fxaa => {}
+            Effect::ColorGrade { lut } => anyhow::ensure!(
+                has(lut, |kind| matches!(kind, AssetKind::TexturePng)),
+                "Effect {} (ColorGrade) reads `{}`, which is not a TexturePng asset", i, lut,
+            ),
+            Effect::Custom { asset_id } => anyhow::ensure!(
+                has(asset_id, |kind| matches!(kind, AssetKind::SumiSource)),
+                "Effect {} (Custom) draws `{}`, which is not a SumiSource asset", i, asset_id,
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the shaders of custom effects are image shaders.
+/// `shaders` are the validated shaders of the project.
+pub fn check_shaders(effects: &[Effect], shaders: &[(&AssetEntry, ValidatedShader)]) -> anyhow::Result<()> {
+    for effect in effects {
+        let Effect::Custom { asset_id } = effect else { continue };
+        let shader = shaders.iter().find(|(asset, _)| asset.id == *asset_id);
+        anyhow::ensure!(
+            shader.is_some_and(|(_, shader)| shader.is_image()),
+            "Effect shader `{}` is not an image shader (`main_image`)", asset_id,
+        );
+    }
+    Ok(())
+}
+
+/// Size of a colour grading LUT: A strip of `size` squares of `size` x
+/// `size` texels, so `size * size` wide and `size` high. Red grows across
+/// each square, green down it and blue from square to square, in sRGB (An
+/// unmodified strip leaves colours as they are).
+pub fn lut_size(id: &str, lut: &Texture) -> anyhow::Result<u32> {
+    anyhow::ensure!(
+        lut.height >= 2 && lut.width == lut.height * lut.height,
+        "LUT `{}` is {}x{}, expected a strip of N squares of NxN (N*N x N)", id, lut.width, lut.height,
+    );
+    Ok(lut.height)
+}
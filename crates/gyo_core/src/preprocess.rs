@@ -0,0 +1,124 @@
+//! `#define` / `#ifdef` preprocessing of `SumiSource` assets.
+//!
+//! Supported directives, at the start of a line:
+//!
+//! - `#define NAME` and `#define NAME value`: Later occurrences of `NAME` (as
+//!   a whole identifier) are replaced by `value`
+//! - `#ifdef NAME`, `#ifndef NAME`, `#else` and `#endif`, which nest
+//!
+//! Directive lines and lines of inactive branches are blanked rather than
+//! removed, so line numbers stay those of the asset. Other `#` lines (like
+//! `#import`) are left alone.
+//!
+//! A `#define` in an asset is a default for that asset: The defines of the
+//! selected [`crate::ShaderVariant`] win over it and apply to every asset.
+
+use crate::validate::{self, ShaderError};
+use std::collections::HashMap;
+
+/// Values of defined names (Empty for flags)
+pub type Defines = HashMap<String, String>;
+
+/// Preprocesses one asset with `defines` already set.
+pub fn preprocess(asset_id: &str, source: &str, defines: &Defines) -> Result<String, ShaderError> {
+    let mut local = defines.clone();
+    // Open conditionals: Line, whether the branch is taken, whether the
+    // enclosing block is active
+    let mut stack: Vec<(u32, bool, bool)> = Vec::new();
+    let mut active = true;
+    let mut diagnostics = Vec::new();
+    let mut output = Vec::new();
+
+    for (i, text) in source.lines().enumerate() {
+        let line = i as u32 + 1;
+        let Some((directive, argument)) = directive(text) else {
+            output.push(if active { substitute(text, &local) } else { String::new() });
+            continue;
+        };
+
+        let mut error = |message: String| diagnostics.push(validate::line_diagnostic(asset_id, line, text, message));
+        match directive {
+            "define" if active => match argument.split_once(char::is_whitespace) {
+                _ if argument.is_empty() => error("Expected a name, like `#define STEPS 32`".to_string()),
+                Some((name, value)) => {
+                    let value = substitute(value.trim(), &local);
+                    local.entry(name.to_string()).or_insert(value);
+                }
+                None => {
+                    local.entry(argument.to_string()).or_default();
+                }
+            },
+            "ifdef" | "ifndef" => {
+                if argument.is_empty() || argument.contains(char::is_whitespace) {
+                    error(format!("Expected a single name after `#{}`", directive));
+                }
+                let taken = local.contains_key(argument) == (directive == "ifdef");
+                stack.push((line, taken, active));
+                active = active && taken;
+            }
+            "else" => match stack.last_mut() {
+                Some((_, taken, outer)) => {
+                    *taken = !*taken;
+                    active = *outer && *taken;
+                }
+                None => error("`#else` without `#ifdef`".to_string()),
+            },
+            "endif" => match stack.pop() {
+                Some((_, _, outer)) => active = outer,
+                None => error("`#endif` without `#ifdef`".to_string()),
+            },
+            "define" => {}
+            // Not ours
+            _ => {
+                output.push(if active { text.to_string() } else { String::new() });
+                continue;
+            }
+        }
+        output.push(String::new());
+    }
+
+    for (line, _, _) in stack {
+        let text = source.lines().nth(line as usize - 1).unwrap_or_default();
+        diagnostics.push(validate::line_diagnostic(asset_id, line, text, "Unterminated conditional: Missing `#endif`".to_string()));
+    }
+
+    if diagnostics.is_empty() {
+        Ok(output.join("\n"))
+    } else {
+        Err(ShaderError { diagnostics })
+    }
+}
+
+/// Name and argument of a `#name argument` line
+fn directive(text: &str) -> Option<(&str, &str)> {
+    let rest = text.trim_start().strip_prefix('#')?;
+    let end = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(rest.len());
+    // Trailing `//` comments are not part of the argument
+    let argument = rest[end..].split("//").next().unwrap_or_default().trim();
+    Some((&rest[..end], argument))
+}
+
+/// Replaces every identifier of `text` that is defined by its value
+fn substitute(text: &str, defines: &Defines) -> String {
+    if defines.is_empty() {
+        return text.to_string();
+    }
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(|c: char| c.is_alphabetic() || c == '_') {
+        // Neither number suffixes (`1e3`) nor members (`v.x`) are replaced
+        let prefix = &rest[..start];
+        let in_number = prefix.ends_with(|c: char| c.is_ascii_digit() || c == '.');
+        let end = rest[start..].find(|c: char| !c.is_alphanumeric() && c != '_')
+            .map_or(rest.len(), |len| start + len);
+        let word = &rest[start..end];
+        output.push_str(prefix);
+        match defines.get(word).filter(|_| !in_number) {
+            Some(value) => output.push_str(value),
+            None => output.push_str(word),
+        }
+        rest = &rest[end..];
+    }
+    output.push_str(rest);
+    output
+}
@@ -0,0 +1,483 @@
+//! Resource bindings of shaders, reflected from their naga modules.
+//!
+//! The runtime builds explicit bind group layouts from [`resources`] instead of
+//! letting the device guess them, and [`check_bindings`] compares what the
+//! shaders expect with what a project provides before anything draws:
+//!
+//! - `@group(0) @binding(0)` is the `Uniforms` buffer of the prelude, and
+//!   `@group(0) @binding(1)` the 2D camera (See `shader::CAMERA_BINDING`)
+//! - `@group(1)` holds the channels: The Nth `TexturePng` asset at binding
+//!   2N, a sampler at 2N + 1 (See `shader::CHANNEL_GROUP`)
+//! - `@group(2)` holds the sprite atlas: A `TexturePng` asset picked per
+//!   batch at binding 0, a sampler at 1 (See `shader::ATLAS_GROUP`)
+//!
+//! Bindings outside of that, or of the wrong type, fail the check. Channels
+//! without a texture (Bound to black) and textures no shader samples are
+//! reported as warnings (Any texture may be an atlas once a shader samples one).
+//!
+//! [`check_vertex_inputs`] does the same for the vertex buffer of sprite
+//! shaders, so the WGSL cannot drift from the runtime's instance layout.
+
+use crate::shader::{ATLAS_GROUP, CAMERA_BINDING, CAMERA_SIZE, CHANNEL_COUNT, CHANNEL_GROUP, UNIFORMS_SIZE, UNIFORM_GROUP};
+use crate::validate::{self, ShaderDiagnostic, ShaderError, ValidatedShader};
+use crate::{AssetEntry, AssetKind, ShaderStage};
+use naga::{AddressSpace, GlobalVariable, Handle, ImageClass, ImageDimension, Module, ScalarKind, Span, TypeInner};
+use std::num::{NonZeroU32, NonZeroU64};
+use wgpu_types as wgt;
+
+/// What a binding holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceKind {
+    UniformBuffer { size: u32 },
+    StorageBuffer { read_only: bool },
+    Texture,
+    StorageTexture,
+    Sampler,
+    Other,
+}
+
+impl std::fmt::Display for ResourceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UniformBuffer { size } => write!(f, "uniform buffer ({} bytes)", size),
+            Self::StorageBuffer { read_only: true } => write!(f, "storage buffer (read-only)"),
+            Self::StorageBuffer { read_only: false } => write!(f, "storage buffer"),
+            Self::Texture => write!(f, "texture"),
+            Self::StorageTexture => write!(f, "storage texture"),
+            Self::Sampler => write!(f, "sampler"),
+            Self::Other => write!(f, "resource"),
+        }
+    }
+}
+
+/// One resource binding used by a shader's entry points.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShaderResource {
+    pub group: u32,
+    pub binding: u32,
+    /// Variable name in the shader (Empty if it has none)
+    pub name: String,
+    pub kind: ResourceKind,
+    /// Stages whose entry points use it
+    pub visibility: wgt::ShaderStages,
+    /// Binding type for an explicit bind group layout
+    pub ty: wgt::BindingType,
+    /// Array length of binding arrays
+    pub count: Option<NonZeroU32>,
+    handle: Handle<GlobalVariable>,
+}
+
+impl ShaderResource {
+    /// The bind group layout entry of this resource
+    pub fn layout_entry(&self) -> wgt::BindGroupLayoutEntry {
+        wgt::BindGroupLayoutEntry {
+            binding: self.binding,
+            visibility: self.visibility,
+            ty: self.ty,
+            count: self.count,
+        }
+    }
+}
+
+/// Every bound resource the entry points of `shader` use, ordered by group
+/// and binding.
+pub fn resources(shader: &ValidatedShader) -> Vec<ShaderResource> {
+    let module = &shader.module;
+    let mut resources: Vec<ShaderResource> = module.global_variables.iter()
+        .filter_map(|(handle, var)| {
+            let binding = var.binding.as_ref()?;
+            let visibility = module.entry_points.iter().enumerate()
+                .filter(|(i, _)| !shader.info.get_entry_point(*i)[handle].is_empty())
+                .fold(wgt::ShaderStages::NONE, |stages, (_, ep)| stages | shader_stages(ep.stage));
+            if visibility.is_empty() {
+                return None;
+            }
+
+            let (ty, count) = match module.types[var.ty].inner {
+                TypeInner::BindingArray { base, size } => (base, match size {
+                    naga::ArraySize::Constant(size) => Some(size),
+                    naga::ArraySize::Dynamic => None,
+                }),
+                _ => (var.ty, None),
+            };
+            Some(ShaderResource {
+                group: binding.group,
+                binding: binding.binding,
+                name: var.name.clone().unwrap_or_default(),
+                kind: kind_of(module, var),
+                visibility,
+                ty: binding_type(module, var.space, &module.types[ty].inner),
+                count,
+                handle,
+            })
+        })
+        .collect();
+    resources.sort_by_key(|r| (r.group, r.binding));
+    resources
+}
+
+/// Layout entries of one bind group, by group index: Empty groups in between
+/// are kept so the index matches `@group`.
+pub fn bind_group_entries(resources: &[ShaderResource]) -> Vec<Vec<wgt::BindGroupLayoutEntry>> {
+    let group_count = resources.iter().map(|r| r.group + 1).max().unwrap_or(0);
+    (0..group_count)
+        .map(|group| resources.iter()
+            .filter(|r| r.group == group)
+            .map(ShaderResource::layout_entry)
+            .collect())
+        .collect()
+}
+
+/// Problems that do not stop a project from drawing.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BindingCheck {
+    /// `(shader asset, channel)` of channels sampled without a texture asset.
+    /// They are bound to black.
+    pub missing_channels: Vec<(String, u32)>,
+    /// `TexturePng` assets no shader samples
+    pub unused_textures: Vec<String>,
+}
+
+impl BindingCheck {
+    pub fn warnings(&self) -> Vec<String> {
+        let missing = self.missing_channels.iter().map(|(asset_id, channel)| format!(
+            "{} samples channel {} but the project has no texture for it (Bound to black)", asset_id, channel,
+        ));
+        let unused = self.unused_textures.iter().map(|asset_id| format!(
+            "Texture {} is not sampled by any shader", asset_id,
+        ));
+        missing.chain(unused).collect()
+    }
+}
+
+/// Checks the bindings of validated shaders against a project's assets.
+///
+/// `shaders` pairs each shader with its asset. Bindings the runtime cannot
+/// provide are errors pointing at their declaration.
+pub fn check_bindings(
+    shaders: &[(&AssetEntry, &ValidatedShader)],
+    assets: &[AssetEntry],
+) -> Result<BindingCheck, ShaderError> {
+    let textures: Vec<&str> = assets.iter()
+        .filter(|a| matches!(a.kind, AssetKind::TexturePng))
+        .map(|a| a.id.as_str())
+        .collect();
+
+    let mut check = BindingCheck::default();
+    let mut sampled = vec![false; textures.len()];
+    let mut samples_atlas = false;
+    let mut diagnostics = Vec::new();
+    for (asset, shader) in shaders {
+        for resource in resources(shader) {
+            samples_atlas |= resource.group == ATLAS_GROUP;
+            match expected(&resource) {
+                Err(message) => diagnostics.push(declaration_diagnostic(asset, shader, &resource, message)),
+                Ok(Some(channel)) => match sampled.get_mut(channel as usize) {
+                    Some(sampled) => *sampled = true,
+                    None => check.missing_channels.push((asset.id.clone(), channel)),
+                },
+                Ok(None) => {}
+            }
+        }
+    }
+
+    if !diagnostics.is_empty() {
+        return Err(ShaderError { diagnostics });
+    }
+    check.unused_textures = textures.iter().zip(&sampled)
+        .filter(|(_, sampled)| !**sampled && !samples_atlas)
+        .map(|(id, _)| id.to_string())
+        .collect();
+    Ok(check)
+}
+
+/// Checks a binding against what the runtime provides. `Ok(Some(n))` for the
+/// texture of channel n.
+fn expected(resource: &ShaderResource) -> Result<Option<u32>, String> {
+    let what = format!("`{}` (@group({}) @binding({}))", resource.name, resource.group, resource.binding);
+    match (resource.group, resource.binding) {
+        (UNIFORM_GROUP, 0) => match resource.kind {
+            ResourceKind::UniformBuffer { size } if size <= UNIFORMS_SIZE => Ok(None),
+            ResourceKind::UniformBuffer { size } => Err(format!(
+                "{} is {} bytes, larger than the {} bytes of `Uniforms`", what, size, UNIFORMS_SIZE,
+            )),
+            kind => Err(format!("{} must be the `Uniforms` buffer, not a {}", what, kind)),
+        },
+        (UNIFORM_GROUP, CAMERA_BINDING) => match resource.kind {
+            ResourceKind::UniformBuffer { size } if size <= CAMERA_SIZE => Ok(None),
+            ResourceKind::UniformBuffer { size } => Err(format!(
+                "{} is {} bytes, larger than the {} bytes of the camera (A `mat4x4<f32>`)", what, size, CAMERA_SIZE,
+            )),
+            kind => Err(format!("{} must be the camera buffer, not a {}", what, kind)),
+        },
+        (CHANNEL_GROUP, binding) if binding < 2 * CHANNEL_COUNT && resource.count.is_none() => {
+            let channel = binding / 2;
+            if binding % 2 == 1 {
+                match resource.ty {
+                    wgt::BindingType::Sampler(wgt::SamplerBindingType::Filtering) => Ok(None),
+                    _ => Err(format!("{} must be a (filtering) sampler for channel {}", what, channel)),
+                }
+            } else {
+                match resource.ty {
+                    wgt::BindingType::Texture {
+                        sample_type: wgt::TextureSampleType::Float { .. },
+                        view_dimension: wgt::TextureViewDimension::D2,
+                        multisampled: false,
+                    } => Ok(Some(channel)),
+                    _ => Err(format!("{} must be a `texture_2d<f32>` for channel {}", what, channel)),
+                }
+            }
+        }
+        (ATLAS_GROUP, 0) if resource.count.is_none() => match resource.ty {
+            wgt::BindingType::Texture {
+                sample_type: wgt::TextureSampleType::Float { .. },
+                view_dimension: wgt::TextureViewDimension::D2,
+                multisampled: false,
+            } => Ok(None),
+            _ => Err(format!("{} must be a `texture_2d<f32>` for the sprite atlas", what)),
+        },
+        (ATLAS_GROUP, 1) => match resource.ty {
+            wgt::BindingType::Sampler(wgt::SamplerBindingType::Filtering) => Ok(None),
+            _ => Err(format!("{} must be a (filtering) sampler for the sprite atlas", what)),
+        },
+        _ => Err(format!(
+            "{} is not provided by the runtime: Use @group({}) @binding(0) for the uniforms, \
+             @group({}) @binding({}) for the camera, @group({}) @binding(0..{}) for channels and \
+             @group({}) @binding(0..2) for the sprite atlas",
+            what, UNIFORM_GROUP, UNIFORM_GROUP, CAMERA_BINDING, CHANNEL_GROUP, 2 * CHANNEL_COUNT, ATLAS_GROUP,
+        )),
+    }
+}
+
+fn declaration_diagnostic(
+    asset: &AssetEntry,
+    shader: &ValidatedShader,
+    resource: &ShaderResource,
+    message: String,
+) -> ShaderDiagnostic {
+    let span = shader.module.global_variables.get_span(resource.handle);
+    let mut diagnostic = validate::diagnostic(&shader.composed, &message, Some((span, "declared here")), Vec::new());
+    // Binaries have no source to point into
+    if diagnostic.asset_id.is_empty() {
+        diagnostic.asset_id = asset.id.clone();
+    }
+    diagnostic
+}
+
+/// An input of a vertex entry point read from a vertex buffer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VertexInput {
+    pub location: u32,
+    /// Argument or struct member name (Empty if it has none)
+    pub name: String,
+    /// WGSL spelling of its type, like `vec2<f32>`
+    pub ty: String,
+    /// Attribute format it reads, `None` for types no format provides
+    pub format: Option<wgt::VertexFormat>,
+    /// Where it is declared (Its struct, for struct members)
+    span: Span,
+}
+
+/// The `@location` inputs of the vertex entry point `entry_point`, by location.
+/// Empty if the shader has no such entry point.
+pub fn vertex_inputs(shader: &ValidatedShader, entry_point: &str) -> Vec<VertexInput> {
+    let module = &shader.module;
+    let Some(ep) = module.entry_points.iter().find(|ep| ep.stage == naga::ShaderStage::Vertex && ep.name == entry_point) else {
+        return Vec::new();
+    };
+    let input = |name: &Option<String>, ty: Handle<naga::Type>, binding: &Option<naga::Binding>, span| {
+        let Some(naga::Binding::Location { location, .. }) = binding else { return None };
+        let inner = &module.types[ty].inner;
+        Some(VertexInput {
+            location: *location,
+            name: name.clone().unwrap_or_default(),
+            ty: type_name(inner),
+            format: vertex_format(inner),
+            span,
+        })
+    };
+
+    let mut inputs: Vec<VertexInput> = ep.function.arguments.iter()
+        .flat_map(|arg| match &module.types[arg.ty].inner {
+            TypeInner::Struct { members, .. } => members.iter()
+                .filter_map(|m| input(&m.name, m.ty, &m.binding, module.types.get_span(arg.ty)))
+                .collect(),
+            _ => input(&arg.name, arg.ty, &arg.binding, Span::default()).into_iter().collect::<Vec<_>>(),
+        })
+        .collect();
+    inputs.sort_by_key(|input| input.location);
+    inputs
+}
+
+/// Checks the inputs of the vertex entry point `entry_point` against the
+/// attributes of a vertex buffer: Every input needs an attribute of the same
+/// format at its location. Attributes the shader ignores are fine.
+pub fn check_vertex_inputs(
+    asset: &AssetEntry,
+    shader: &ValidatedShader,
+    entry_point: &str,
+    attributes: &[wgt::VertexAttribute],
+) -> Result<(), ShaderError> {
+    let diagnostics: Vec<ShaderDiagnostic> = vertex_inputs(shader, entry_point).iter()
+        .filter_map(|input| {
+            let what = format!("Vertex input `{}` (@location({}))", input.name, input.location);
+            let message = match attributes.iter().find(|a| a.shader_location == input.location) {
+                None => format!(
+                    "{} is not in the instance layout, which has locations {:?}",
+                    what, attributes.iter().map(|a| a.shader_location).collect::<Vec<_>>(),
+                ),
+                Some(attribute) if Some(attribute.format) != input.format => format!(
+                    "{} is a `{}`, but the instance layout has a {:?} there", what, input.ty, attribute.format,
+                ),
+                Some(_) => return None,
+            };
+            let mut diagnostic = validate::diagnostic(&shader.composed, &message, Some((input.span, "declared here")), Vec::new());
+            if diagnostic.asset_id.is_empty() {
+                diagnostic.asset_id = asset.id.clone();
+            }
+            Some(diagnostic)
+        })
+        .collect();
+
+    if diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err(ShaderError { diagnostics })
+    }
+}
+
+fn vertex_format(inner: &TypeInner) -> Option<wgt::VertexFormat> {
+    use wgt::VertexFormat as F;
+    let (kind, width, size) = match *inner {
+        TypeInner::Scalar(scalar) => (scalar.kind, scalar.width, 1),
+        TypeInner::Vector { size, scalar } => (scalar.kind, scalar.width, size as u8),
+        _ => return None,
+    };
+    let formats = match (kind, width) {
+        (ScalarKind::Float, 4) => [F::Float32, F::Float32x2, F::Float32x3, F::Float32x4],
+        (ScalarKind::Uint, 4) => [F::Uint32, F::Uint32x2, F::Uint32x3, F::Uint32x4],
+        (ScalarKind::Sint, 4) => [F::Sint32, F::Sint32x2, F::Sint32x3, F::Sint32x4],
+        _ => return None,
+    };
+    Some(formats[size as usize - 1])
+}
+
+fn type_name(inner: &TypeInner) -> String {
+    let scalar = |scalar: naga::Scalar| match (scalar.kind, scalar.width) {
+        (ScalarKind::Float, 4) => "f32".to_string(),
+        (ScalarKind::Uint, 4) => "u32".to_string(),
+        (ScalarKind::Sint, 4) => "i32".to_string(),
+        (kind, width) => format!("{:?}{}", kind, width * 8),
+    };
+    match *inner {
+        TypeInner::Scalar(s) => scalar(s),
+        TypeInner::Vector { size, scalar: s } => format!("vec{}<{}>", size as u8, scalar(s)),
+        ref other => format!("{:?}", other),
+    }
+}
+
+pub(crate) fn kind_of(module: &Module, var: &GlobalVariable) -> ResourceKind {
+    let inner = match module.types[var.ty].inner {
+        TypeInner::BindingArray { base, .. } => &module.types[base].inner,
+        ref inner => inner,
+    };
+    match (var.space, inner) {
+        (AddressSpace::Uniform, _) => ResourceKind::UniformBuffer { size: inner.size(module.to_ctx()) },
+        (AddressSpace::Storage { access }, _) => ResourceKind::StorageBuffer {
+            read_only: !access.contains(naga::StorageAccess::STORE),
+        },
+        (_, TypeInner::Image { class: ImageClass::Storage { .. }, .. }) => ResourceKind::StorageTexture,
+        (_, TypeInner::Image { .. }) => ResourceKind::Texture,
+        (_, TypeInner::Sampler { .. }) => ResourceKind::Sampler,
+        _ => ResourceKind::Other,
+    }
+}
+
+fn binding_type(module: &Module, space: AddressSpace, inner: &TypeInner) -> wgt::BindingType {
+    let buffer = |ty| wgt::BindingType::Buffer {
+        ty,
+        has_dynamic_offset: false,
+        min_binding_size: NonZeroU64::new(inner.size(module.to_ctx()) as u64),
+    };
+    match (space, inner) {
+        (AddressSpace::Uniform, _) => buffer(wgt::BufferBindingType::Uniform),
+        (AddressSpace::Storage { access }, _) => buffer(wgt::BufferBindingType::Storage {
+            read_only: !access.contains(naga::StorageAccess::STORE),
+        }),
+        (_, TypeInner::Sampler { comparison: true }) => {
+            wgt::BindingType::Sampler(wgt::SamplerBindingType::Comparison)
+        }
+        (_, TypeInner::Sampler { comparison: false }) => {
+            wgt::BindingType::Sampler(wgt::SamplerBindingType::Filtering)
+        }
+        (_, TypeInner::Image { dim, arrayed, class }) => {
+            let view_dimension = view_dimension(*dim, *arrayed);
+            match *class {
+                ImageClass::Sampled { kind, multi } => wgt::BindingType::Texture {
+                    sample_type: match kind {
+                        ScalarKind::Sint => wgt::TextureSampleType::Sint,
+                        ScalarKind::Uint => wgt::TextureSampleType::Uint,
+                        _ => wgt::TextureSampleType::Float { filterable: !multi },
+                    },
+                    view_dimension,
+                    multisampled: multi,
+                },
+                ImageClass::Depth { multi } => wgt::BindingType::Texture {
+                    sample_type: wgt::TextureSampleType::Depth,
+                    view_dimension,
+                    multisampled: multi,
+                },
+                ImageClass::Storage { format, access } => wgt::BindingType::StorageTexture {
+                    access: match (access.contains(naga::StorageAccess::LOAD), access.contains(naga::StorageAccess::STORE)) {
+                        (true, true) => wgt::StorageTextureAccess::ReadWrite,
+                        (true, false) => wgt::StorageTextureAccess::ReadOnly,
+                        _ => wgt::StorageTextureAccess::WriteOnly,
+                    },
+                    format: storage_format(format),
+                    view_dimension,
+                },
+            }
+        }
+        _ => wgt::BindingType::AccelerationStructure,
+    }
+}
+
+fn view_dimension(dim: ImageDimension, arrayed: bool) -> wgt::TextureViewDimension {
+    match (dim, arrayed) {
+        (ImageDimension::D1, _) => wgt::TextureViewDimension::D1,
+        (ImageDimension::D2, false) => wgt::TextureViewDimension::D2,
+        (ImageDimension::D2, true) => wgt::TextureViewDimension::D2Array,
+        (ImageDimension::D3, _) => wgt::TextureViewDimension::D3,
+        (ImageDimension::Cube, false) => wgt::TextureViewDimension::Cube,
+        (ImageDimension::Cube, true) => wgt::TextureViewDimension::CubeArray,
+    }
+}
+
+/// naga and wgpu share the storage format names
+fn storage_format(format: naga::StorageFormat) -> wgt::TextureFormat {
+    macro_rules! same_names {
+        ($($name:ident),* $(,)?) => {
+            match format {
+                $(naga::StorageFormat::$name => wgt::TextureFormat::$name,)*
+            }
+        };
+    }
+    same_names!(
+        R8Unorm, R8Snorm, R8Uint, R8Sint,
+        R16Uint, R16Sint, R16Float, Rg8Unorm, Rg8Snorm, Rg8Uint, Rg8Sint,
+        R32Uint, R32Sint, R32Float, Rg16Uint, Rg16Sint, Rg16Float,
+        Rgba8Unorm, Rgba8Snorm, Rgba8Uint, Rgba8Sint, Bgra8Unorm,
+        Rgb10a2Uint, Rgb10a2Unorm, Rg11b10Float,
+        Rg32Uint, Rg32Sint, Rg32Float, Rgba16Uint, Rgba16Sint, Rgba16Float,
+        Rgba32Uint, Rgba32Sint, Rgba32Float,
+        R16Unorm, R16Snorm, Rg16Unorm, Rg16Snorm, Rgba16Unorm, Rgba16Snorm,
+    )
+}
+
+fn shader_stages(stage: naga::ShaderStage) -> wgt::ShaderStages {
+    match validate::stage_of(stage) {
+        ShaderStage::Vertex => wgt::ShaderStages::VERTEX,
+        ShaderStage::Fragment => wgt::ShaderStages::FRAGMENT,
+        ShaderStage::Compute => wgt::ShaderStages::COMPUTE,
+    }
+}
@@ -0,0 +1,191 @@
+//! The runtime prelude wrapped around Gyosho "image" shaders.
+//!
+//! User code only provides `main_image(frag_color, frag_coord) -> vec4<f32>`
+//! (Shadertoy style). The prefix declares the `Uniforms` block bound at
+//! `@group(0) @binding(0)`, the suffix provides the `main` fragment entry point.
+//!
+//! GLSL sources get an equivalent prelude declaring the Shadertoy names
+//! (`iTime`, `iResolution`, `iMouse`, `iChannel0..3`) around `mainImage`.
+
+/// Full-screen triangle vertex shader (Entry point: `main`)
+pub const VERTEX_SHADER: &str = include_str!("assets/vert.wgsl");
+
+/// Declarations available to every image shader
+pub const FRAGMENT_PREFIX: &str = include_str!("assets/frag.prefix.wgsl");
+
+/// Fragment entry point calling the user's `main_image` (Entry point: `main`)
+pub const FRAGMENT_SUFFIX: &str = include_str!("assets/frag.suffix.wgsl");
+
+/// `FRAGMENT_SUFFIX` for OpenGL targets, where the fragment position
+/// already has the bottom-left origin `main_image` expects
+pub const GL_FRAGMENT_SUFFIX: &str = include_str!("assets/frag.suffix.gl.wgsl");
+
+/// The shader new projects start with
+pub const DEFAULT_FRAGMENT: &str = include_str!("assets/frag.default.wgsl");
+
+/// Declarations and Shadertoy names available to GLSL shaders
+pub const GLSL_FRAGMENT_PREFIX: &str = include_str!("assets/frag.prefix.glsl");
+
+/// GLSL entry point calling the user's `mainImage` (Entry point: `main`)
+pub const GLSL_FRAGMENT_SUFFIX: &str = include_str!("assets/frag.suffix.glsl");
+
+/// Bind group of the `Uniforms` buffer (Always at binding 0)
+pub const UNIFORM_GROUP: u32 = 0;
+
+/// Size of the prelude's `Uniforms` block in bytes
+pub const UNIFORMS_SIZE: u32 = 48;
+
+/// Binding of the 2D camera in the `Uniforms` group: A `mat4x4<f32>` taking
+/// sprite positions (World space) to clip space
+pub const CAMERA_BINDING: u32 = 1;
+
+/// Size of the camera block in bytes
+pub const CAMERA_SIZE: u32 = 64;
+
+/// Bind group of the `iChannel` textures. Channel N uses binding 2N for its
+/// texture and 2N + 1 for its sampler.
+pub const CHANNEL_GROUP: u32 = 1;
+
+/// Number of `iChannel` slots
+pub const CHANNEL_COUNT: u32 = 4;
+
+/// Bind group of the atlas sprite shaders sample: Its texture at binding 0,
+/// its sampler at binding 1. Each sprite batch binds its own atlas.
+pub const ATLAS_GROUP: u32 = 2;
+
+/// Entry point name of both prelude stages
+pub const ENTRY_POINT: &str = "main";
+
+/// Origin reported for lines that belong to the runtime prelude
+pub const PRELUDE_ORIGIN: &str = "<prelude>";
+
+/// Returns true if `source` is an image shader (Defines `main_image`)
+/// rather than a raw vertex/fragment module.
+pub fn is_image_shader(source: &str) -> bool {
+    source.contains("fn main_image")
+}
+
+/// A contiguous run of lines copied into a composed shader.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    /// Asset id the lines were copied from (Or `PRELUDE_ORIGIN`)
+    pub origin: String,
+    /// First line in the composed code (1-based)
+    pub first_line: u32,
+    pub line_count: u32,
+    /// Line of `first_line` in the origin (1-based)
+    pub origin_first_line: u32,
+}
+
+/// Maps lines of a composed shader back to the assets they came from.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceMap {
+    segments: Vec<Segment>,
+}
+
+impl SourceMap {
+    /// Returns the origin and its line for a composed `line` (1-based)
+    pub fn resolve(&self, line: u32) -> Option<(&str, u32)> {
+        self.segments.iter()
+            .find(|s| line >= s.first_line && line < s.first_line + s.line_count)
+            .map(|s| (s.origin.as_str(), s.origin_first_line + (line - s.first_line)))
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+}
+
+/// Shader code assembled from several sources, with the map back to them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ComposedShader {
+    pub code: String,
+    pub map: SourceMap,
+}
+
+impl ComposedShader {
+    /// Appends `text` (Always ending in a new line) attributed to `origin`.
+    pub fn append(&mut self, origin: &str, text: &str) -> &mut Self {
+        self.append_from(origin, 1, text)
+    }
+
+    /// Like `append`, for text that starts at `origin_first_line` of its origin.
+    pub fn append_from(&mut self, origin: &str, origin_first_line: u32, text: &str) -> &mut Self {
+        let first_line = self.code.matches('\n').count() as u32 + 1;
+        self.code.push_str(text);
+        if !text.ends_with('\n') {
+            self.code.push('\n');
+        }
+        self.map.segments.push(Segment {
+            origin: origin.to_string(),
+            first_line,
+            line_count: text.lines().count().max(1) as u32,
+            origin_first_line,
+        });
+        self
+    }
+
+    /// Appends another composed shader, keeping its origins.
+    pub fn append_shader(&mut self, other: &ComposedShader) -> &mut Self {
+        let offset = self.code.matches('\n').count() as u32;
+        self.code.push_str(&other.code);
+        self.map.segments.extend(other.map.segments.iter().map(|s| Segment {
+            first_line: s.first_line + offset,
+            ..s.clone()
+        }));
+        self
+    }
+}
+
+/// Wraps user code with the runtime prelude, producing a complete fragment module.
+pub fn compose_image_shader(asset_id: &str, source: &str) -> ComposedShader {
+    wrap_image_shader(&user_code(asset_id, source))
+}
+
+/// Wraps composed user code (E.g. an asset and its imports) with the runtime prelude.
+pub fn wrap_image_shader(user: &ComposedShader) -> ComposedShader {
+    let mut shader = ComposedShader::default();
+    shader.append(PRELUDE_ORIGIN, FRAGMENT_PREFIX)
+        .append_shader(user)
+        .append(PRELUDE_ORIGIN, FRAGMENT_SUFFIX);
+    shader
+}
+
+/// `wrap_image_shader` for code translated to OpenGL (See `GL_FRAGMENT_SUFFIX`).
+pub fn wrap_gl_image_shader(user: &ComposedShader) -> ComposedShader {
+    let mut shader = ComposedShader::default();
+    shader.append(PRELUDE_ORIGIN, FRAGMENT_PREFIX)
+        .append_shader(user)
+        .append(PRELUDE_ORIGIN, GL_FRAGMENT_SUFFIX);
+    shader
+}
+
+/// Wraps Shadertoy GLSL (`mainImage`) with the GLSL prelude.
+pub fn compose_glsl_shader(asset_id: &str, source: &str) -> ComposedShader {
+    let mut shader = ComposedShader::default();
+    shader.append(PRELUDE_ORIGIN, GLSL_FRAGMENT_PREFIX)
+        .append(asset_id, source)
+        .append(PRELUDE_ORIGIN, GLSL_FRAGMENT_SUFFIX);
+    shader
+}
+
+/// Returns the module the runtime compiles for a `SumiSource` asset:
+/// image shaders get the prelude, raw modules are used as they are.
+pub fn prepare(asset_id: &str, source: &str) -> ComposedShader {
+    prepare_composed(user_code(asset_id, source))
+}
+
+/// `prepare` for composed user code (E.g. an asset and its imports).
+pub fn prepare_composed(user: ComposedShader) -> ComposedShader {
+    if is_image_shader(&user.code) {
+        wrap_image_shader(&user)
+    } else {
+        user
+    }
+}
+
+fn user_code(asset_id: &str, source: &str) -> ComposedShader {
+    let mut shader = ComposedShader::default();
+    shader.append(asset_id, source);
+    shader
+}
@@ -4,6 +4,7 @@ use std::fmt;
 /// The structure of textures.
 #[binrw]
 #[brw(little)]
+#[derive(Clone)]
 pub struct Texture {
     /// The width of the texture.
     pub width: u32,
@@ -39,4 +40,49 @@ impl Texture {
             data,
         }
     }
+
+    /// Decodes a PNG image. Grayscale, RGB, palette and 16bit images are converted to 8bit RGBA.
+    pub fn read_png<R: std::io::Read>(reader: R) -> anyhow::Result<Self> {
+        let mut decoder = png::Decoder::new(reader);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        buffer.truncate(info.buffer_size());
+
+        let data = match info.color_type {
+            png::ColorType::Rgba => buffer,
+            png::ColorType::Rgb => buffer.chunks_exact(3)
+                .flat_map(|p| [p[0], p[1], p[2], 255])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => buffer.chunks_exact(2)
+                .flat_map(|p| [p[0], p[0], p[0], p[1]])
+                .collect(),
+            png::ColorType::Grayscale => buffer.iter()
+                .flat_map(|&g| [g, g, g, 255])
+                .collect(),
+            png::ColorType::Indexed => anyhow::bail!("Indexed PNG was not expanded"),
+        };
+
+        Ok(Self::new(info.width, info.height, data))
+    }
+
+    /// Encodes the texture as an 8bit RGBA PNG.
+    pub fn write_png<W: std::io::Write>(&self, writer: W) -> anyhow::Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.data)?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Encodes the texture as a PNG file at `path`.
+    pub fn save_png<P: AsRef<std::path::Path>>(&self, path: P) -> anyhow::Result<()> {
+        let file = std::fs::File::create(path)?;
+        self.write_png(std::io::BufWriter::new(file))
+    }
 }
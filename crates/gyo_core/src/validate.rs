@@ -0,0 +1,471 @@
+//! Up-front naga validation of shader assets (`SumiSource`, `GlslSource`).
+//!
+//! Shaders are parsed and validated on the CPU before they ever reach a
+//! device, so broken projects fail with structured diagnostics pointing at the
+//! asset, line and column instead of an uncaptured device error.
+
+use crate::import::{self, Library};
+use crate::shader::{self, ComposedShader};
+use crate::{AssetEntry, AssetKind, EntryPoint, PipelineState, ShaderStage, ShaderVariant};
+use std::hash::{Hash, Hasher};
+use naga::valid::{Capabilities, ModuleInfo, ValidationFlags, Validator};
+use naga::{Module, Span};
+use std::fmt;
+
+/// One problem found in a shader, located in the asset it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShaderDiagnostic {
+    /// Asset the location belongs to (`shader::PRELUDE_ORIGIN` for the prelude)
+    pub asset_id: String,
+    /// 1-based line in the asset (0 if naga reported no location)
+    pub line: u32,
+    /// 1-based column in bytes (0 if naga reported no location)
+    pub column: u32,
+    pub message: String,
+    /// Secondary labels and the error's cause chain
+    pub notes: Vec<String>,
+    /// The offending line with the span underlined (Empty without a location)
+    pub snippet: String,
+}
+
+impl fmt::Display for ShaderDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "error: {}", self.message)?;
+        if self.line > 0 {
+            writeln!(f, "  --> {}:{}:{}", self.asset_id, self.line, self.column)?;
+        } else {
+            writeln!(f, "  --> {}", self.asset_id)?;
+        }
+        write!(f, "{}", self.snippet)?;
+        for note in &self.notes {
+            writeln!(f, "   = note: {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+/// All diagnostics of a failed validation.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub struct ShaderError {
+    pub diagnostics: Vec<ShaderDiagnostic>,
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Shader validation failed ({} error(s))", self.diagnostics.len())?;
+        for diagnostic in &self.diagnostics {
+            write!(f, "\n{}", diagnostic)?;
+        }
+        Ok(())
+    }
+}
+
+/// Language a shader asset is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderLanguage {
+    Wgsl,
+    /// Shadertoy-style fragment code, always an image shader
+    Glsl,
+    /// Precompiled binary module
+    SpirV,
+}
+
+impl ShaderLanguage {
+    /// The language of a shader asset, `None` for other kinds
+    pub fn of(kind: &AssetKind) -> Option<Self> {
+        match kind {
+            AssetKind::SumiSource => Some(Self::Wgsl),
+            AssetKind::GlslSource => Some(Self::Glsl),
+            AssetKind::SpirV { .. } => Some(Self::SpirV),
+            _ => None,
+        }
+    }
+}
+
+/// A shader that passed validation, ready to hand to the device.
+#[derive(Debug, Clone)]
+pub struct ValidatedShader {
+    pub language: ShaderLanguage,
+    /// Source code the device compiles, including the prelude (Empty for SPIR-V)
+    pub composed: ComposedShader,
+    /// SPIR-V words the device compiles (Empty for text sources)
+    pub binary: Vec<u32>,
+    /// Declared entry points. Empty means the conventional names
+    /// (`main` for image shaders, `vs_main`/`fs_main` for sprites)
+    pub entry_points: Vec<EntryPoint>,
+    pub module: Module,
+    pub info: ModuleInfo,
+}
+
+impl ValidatedShader {
+    /// True if the shader is drawn as a full-screen image rather than sprites:
+    /// Text wrapped in the runtime prelude, or SPIR-V without a vertex stage.
+    pub fn is_image(&self) -> bool {
+        match self.language {
+            ShaderLanguage::SpirV => !self.entry_points.iter().any(|e| e.stage == ShaderStage::Vertex),
+            _ => self.composed.map.segments().iter().any(|s| s.origin == shader::PRELUDE_ORIGIN),
+        }
+    }
+
+    /// The declared entry point for `stage`, if any
+    pub fn entry_point(&self, stage: ShaderStage) -> Option<&str> {
+        self.entry_points.iter()
+            .find(|e| e.stage == stage)
+            .map(|e| e.name.as_str())
+    }
+
+    /// Hash of everything the device compiles, to detect changed shaders.
+    /// The same from one build to the next (Pipeline caches are keyed by it).
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = siphasher::sip::SipHasher13::new();
+        (&self.composed.code, &self.binary, &self.entry_points).hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Prepares a `SumiSource` (See `shader::prepare`) and validates it.
+pub fn validate_asset(asset_id: &str, source: &str) -> Result<ValidatedShader, ShaderError> {
+    validate(shader::prepare(asset_id, source))
+}
+
+/// Wraps a `GlslSource` in the GLSL prelude and validates it.
+pub fn validate_glsl_asset(asset_id: &str, source: &str) -> Result<ValidatedShader, ShaderError> {
+    validate_glsl(shader::compose_glsl_shader(asset_id, source))
+}
+
+/// Validates a text asset (WGSL or GLSL).
+pub fn validate_source(language: ShaderLanguage, asset_id: &str, source: &str) -> Result<ValidatedShader, ShaderError> {
+    match language {
+        ShaderLanguage::Wgsl => validate_asset(asset_id, source),
+        ShaderLanguage::Glsl => validate_glsl_asset(asset_id, source),
+        ShaderLanguage::SpirV => Err(error_without_location(asset_id, "SPIR-V is not a text format".to_string())),
+    }
+}
+
+/// Parses a SPIR-V binary the way the device will and validates it. Every
+/// declared entry point must exist in the module with the declared stage.
+pub fn validate_spirv(asset_id: &str, bytes: &[u8], entry_points: &[EntryPoint]) -> Result<ValidatedShader, ShaderError> {
+    let module = naga::front::spv::parse_u8_slice(bytes, &spirv_options())
+        .map_err(|e| error_without_location(asset_id, e.to_string()))?;
+
+    check_entry_points(asset_id, &module, entry_points)?;
+
+    // SPIR-V carries no source spans: Blame the asset itself
+    let mut shader = finish(ShaderLanguage::SpirV, ComposedShader::default(), module)
+        .map_err(|mut e| {
+            for diagnostic in &mut e.diagnostics {
+                diagnostic.asset_id = asset_id.to_string();
+            }
+            e
+        })?;
+    shader.binary = bytes.chunks_exact(4)
+        .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect();
+    shader.entry_points = entry_points.to_vec();
+    Ok(shader)
+}
+
+/// Every declared entry point must exist in the module with the declared stage
+fn check_entry_points(asset_id: &str, module: &Module, entry_points: &[EntryPoint]) -> Result<(), ShaderError> {
+    let missing: Vec<ShaderDiagnostic> = entry_points.iter()
+        .filter(|declared| !module.entry_points.iter()
+            .any(|ep| ep.name == declared.name && stage_of(ep.stage) == declared.stage))
+        .map(|declared| error_without_location(asset_id, format!(
+            "Declared {:?} entry point `{}` is not in the module", declared.stage, declared.name,
+        )))
+        .flat_map(|e| e.diagnostics)
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(ShaderError { diagnostics: missing })
+    }
+}
+
+/// Applies the entry points of pipeline declarations to validated shaders,
+/// checking that each declaration names a shader and that its entry points
+/// exist.
+pub fn apply_pipelines(shaders: &mut [(&AssetEntry, ValidatedShader)], pipelines: &[PipelineState]) -> Result<(), ShaderError> {
+    let mut diagnostics = Vec::new();
+    for pipeline in pipelines {
+        let Some((_, shader)) = shaders.iter_mut().find(|(asset, _)| asset.id == pipeline.asset_id) else {
+            diagnostics.extend(error_without_location(&pipeline.asset_id, format!(
+                "Pipeline declared for `{}`, which is not a shader drawn on its own", pipeline.asset_id,
+            )).diagnostics);
+            continue;
+        };
+        match check_entry_points(&pipeline.asset_id, &shader.module, &pipeline.entry_points) {
+            Ok(()) => {
+                for entry_point in &pipeline.entry_points {
+                    shader.entry_points.retain(|e| e.stage != entry_point.stage);
+                    shader.entry_points.push(entry_point.clone());
+                }
+            }
+            Err(e) => diagnostics.extend(e.diagnostics),
+        }
+    }
+
+    if diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err(ShaderError { diagnostics })
+    }
+}
+
+/// Lists the entry points of a SPIR-V binary, for packing `SpirV` assets.
+pub fn spirv_entry_points(bytes: &[u8]) -> Result<Vec<EntryPoint>, ShaderError> {
+    let module = naga::front::spv::parse_u8_slice(bytes, &spirv_options())
+        .map_err(|e| error_without_location("<spirv>", e.to_string()))?;
+    Ok(module.entry_points.iter()
+        .map(|ep| EntryPoint::new(ep.name.clone(), stage_of(ep.stage)))
+        .collect())
+}
+
+/// Validates any shader asset, `None` if `asset` is not a shader.
+/// `SumiSource` imports are resolved from `library`.
+pub fn validate_entry(asset: &AssetEntry, bytes: &[u8], library: &Library) -> Option<Result<ValidatedShader, ShaderError>> {
+    let language = ShaderLanguage::of(&asset.kind)?;
+    Some(match &asset.kind {
+        AssetKind::SpirV { entry_points } => validate_spirv(&asset.id, bytes, entry_points),
+        _ => std::str::from_utf8(bytes)
+            .map_err(|e| error_without_location(&asset.id, format!("Source is not valid UTF-8: {}", e)))
+            .and_then(|source| match language {
+                ShaderLanguage::Wgsl => import::prepare(&asset.id, source, library).and_then(validate),
+                _ => validate_source(language, &asset.id, source),
+            }),
+    })
+}
+
+/// Same options wgpu uses for `ShaderSource::SpirV`
+fn spirv_options() -> naga::front::spv::Options {
+    naga::front::spv::Options {
+        adjust_coordinate_space: false,
+        strict_capabilities: true,
+        block_ctx_dump_prefix: None,
+    }
+}
+
+pub(crate) fn stage_of(stage: naga::ShaderStage) -> ShaderStage {
+    match stage {
+        naga::ShaderStage::Vertex => ShaderStage::Vertex,
+        naga::ShaderStage::Fragment => ShaderStage::Fragment,
+        naga::ShaderStage::Compute => ShaderStage::Compute,
+    }
+}
+
+fn error_without_location(asset_id: &str, message: String) -> ShaderError {
+    ShaderError {
+        diagnostics: vec![ShaderDiagnostic {
+            asset_id: asset_id.to_string(),
+            line: 0,
+            column: 0,
+            message,
+            notes: Vec::new(),
+            snippet: String::new(),
+        }],
+    }
+}
+
+/// Parses and validates a composed GLSL fragment shader.
+pub fn validate_glsl(composed: ComposedShader) -> Result<ValidatedShader, ShaderError> {
+    let options = naga::front::glsl::Options::from(naga::ShaderStage::Fragment);
+    let module = naga::front::glsl::Frontend::default()
+        .parse(&options, &composed.code)
+        .map_err(|e| ShaderError {
+            diagnostics: e.errors.iter()
+                .map(|error| diagnostic(&composed, &error.kind.to_string(), Some((error.meta, "")), causes(error)))
+                .collect(),
+        })?;
+
+    finish(ShaderLanguage::Glsl, composed, module)
+}
+
+/// Parses and validates a composed WGSL shader with every naga check enabled.
+pub fn validate(composed: ComposedShader) -> Result<ValidatedShader, ShaderError> {
+    let module = naga::front::wgsl::parse_str(&composed.code).map_err(|e| {
+        let mut labels = e.labels();
+        let primary = labels.next();
+        let mut notes: Vec<String> = labels
+            .map(|(span, label)| format!("{} (at {})", label, locate(&composed, span)))
+            .collect();
+        notes.extend(causes(&e));
+        ShaderError {
+            diagnostics: vec![diagnostic(&composed, e.message(), primary, notes)],
+        }
+    })?;
+
+    finish(ShaderLanguage::Wgsl, composed, module)
+}
+
+fn finish(language: ShaderLanguage, composed: ComposedShader, module: Module) -> Result<ValidatedShader, ShaderError> {
+    let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+        .validate(&module)
+        .map_err(|e| {
+            let mut spans = e.spans();
+            let primary = spans.next().map(|(span, label)| (*span, label.as_str()));
+            let mut notes: Vec<String> = spans
+                .map(|(span, label)| format!("{} (at {})", label, locate(&composed, *span)))
+                .collect();
+            notes.extend(causes(&e));
+            ShaderError {
+                diagnostics: vec![diagnostic(&composed, &e.to_string(), primary, notes)],
+            }
+        })?;
+
+    Ok(ValidatedShader {
+        language,
+        composed,
+        binary: Vec::new(),
+        entry_points: Vec::new(),
+        module,
+        info,
+    })
+}
+
+pub(crate) fn diagnostic(
+    composed: &ComposedShader,
+    message: &str,
+    primary: Option<(Span, &str)>,
+    notes: Vec<String>,
+) -> ShaderDiagnostic {
+    // Diagnostics without a span are blamed on the first asset line
+    let fallback = composed.map.segments().iter()
+        .find(|s| s.origin != shader::PRELUDE_ORIGIN)
+        .or(composed.map.segments().first())
+        .map(|s| s.origin.clone())
+        .unwrap_or_default();
+
+    let Some((span, label)) = primary.filter(|(span, _)| span.is_defined()) else {
+        return ShaderDiagnostic {
+            asset_id: fallback,
+            line: 0,
+            column: 0,
+            message: message.to_string(),
+            notes,
+            snippet: String::new(),
+        };
+    };
+
+    let location = span.location(&composed.code);
+    let (asset_id, line) = composed.map.resolve(location.line_number)
+        .map(|(origin, line)| (origin.to_string(), line))
+        .unwrap_or((fallback, location.line_number));
+
+    let text = composed.code.lines()
+        .nth(location.line_number as usize - 1)
+        .unwrap_or_default();
+    let snippet = render_snippet(line, text, location.line_position, location.length, label);
+
+    ShaderDiagnostic {
+        asset_id,
+        line,
+        column: location.line_position,
+        message: message.to_string(),
+        notes,
+        snippet,
+    }
+}
+
+/// A diagnostic covering a whole line of an asset, for directives
+pub(crate) fn line_diagnostic(asset_id: &str, line: u32, text: &str, message: String) -> ShaderDiagnostic {
+    let column = (text.len() - text.trim_start().len()) as u32 + 1;
+    ShaderDiagnostic {
+        asset_id: asset_id.to_string(),
+        line,
+        column,
+        message,
+        notes: Vec::new(),
+        snippet: render_snippet(line, text, column, text.trim().len() as u32, ""),
+    }
+}
+
+/// Renders a line of code with `length` bytes from `column` underlined:
+///
+/// ```text
+///    |
+/// 12 |     let x = foo;
+///    |             ^^^ label
+/// ```
+pub(crate) fn render_snippet(line: u32, text: &str, column: u32, length: u32, label: &str) -> String {
+    let number = line.to_string();
+    let gutter = " ".repeat(number.len());
+    let start = (column.max(1) - 1) as usize;
+    // Multi-line spans are only underlined up to the end of the first line
+    let width = (length as usize).min(text.len().saturating_sub(start)).max(1);
+    // Keep tabs so the carets line up with the code above them
+    let indent: String = text.get(..start).unwrap_or(text).chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    let mut snippet = format!("{} |\n{} | {}\n{} | {}{}", gutter, number, text, gutter, indent, "^".repeat(width));
+    if !label.is_empty() {
+        snippet.push(' ');
+        snippet.push_str(label);
+    }
+    snippet.push('\n');
+    snippet
+}
+
+/// `asset:line:column` of a span, for secondary labels
+fn locate(composed: &ComposedShader, span: Span) -> String {
+    if !span.is_defined() {
+        return "unknown location".to_string();
+    }
+    let location = span.location(&composed.code);
+    match composed.map.resolve(location.line_number) {
+        Some((origin, line)) => format!("{}:{}:{}", origin, line, location.line_position),
+        None => format!("{}:{}", location.line_number, location.line_position),
+    }
+}
+
+fn causes(error: &dyn std::error::Error) -> Vec<String> {
+    let mut notes = Vec::new();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        notes.push(cause.to_string());
+        source = cause.source();
+    }
+    notes
+}
+
+/// Validates every shader among `(asset, bytes)` pairs, collecting the
+/// diagnostics of every failing asset into one error.
+///
+/// Other kinds are skipped, and so are `SumiSource` assets another one
+/// imports: They are validated as part of it. `SumiSource` assets are
+/// preprocessed with the defines of `variant`.
+pub fn validate_all<'a, I>(assets: I, variant: Option<&ShaderVariant>) -> Result<Vec<(&'a AssetEntry, ValidatedShader)>, ShaderError>
+where
+    I: IntoIterator<Item = (&'a AssetEntry, &'a [u8])>,
+{
+    let assets: Vec<_> = assets.into_iter().collect();
+    let mut library = Library::new();
+    if let Some(variant) = variant {
+        library.define_variant(variant);
+    }
+    for (asset, bytes) in &assets {
+        if let (AssetKind::SumiSource, Ok(source)) = (&asset.kind, std::str::from_utf8(bytes)) {
+            library.insert(&asset.id, source);
+        }
+    }
+    let imported = library.imported();
+
+    let mut shaders = Vec::new();
+    let mut diagnostics = Vec::new();
+    for (asset, bytes) in assets {
+        if matches!(asset.kind, AssetKind::SumiSource) && imported.contains(asset.id.as_str()) {
+            continue;
+        }
+        match validate_entry(asset, bytes, &library) {
+            Some(Ok(shader)) => shaders.push((asset, shader)),
+            Some(Err(e)) => diagnostics.extend(e.diagnostics),
+            None => {}
+        }
+    }
+
+    if diagnostics.is_empty() {
+        Ok(shaders)
+    } else {
+        Err(ShaderError { diagnostics })
+    }
+}
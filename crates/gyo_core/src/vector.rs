@@ -0,0 +1,216 @@
+//! Vector paths: Outlines of lines and Bézier curves, filled or stroked by
+//! the runtime (`hanga::vector` tessellates them into triangles).
+//!
+//! Paths are built in code, or read from `SvgPath` assets: The `d`
+//! attribute of an SVG `<path>`, as UTF-8 text. Relative commands, the
+//! shorthand curves and arcs are turned into the commands below.
+//! Coordinates are world units, like sprite positions (`y` points down).
+
+use crate::{AssetEntry, AssetKind};
+use anyhow::Context;
+
+/// A step of a path.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathCommand {
+    /// Starts a subpath
+    MoveTo([f32; 2]),
+    LineTo([f32; 2]),
+    /// A quadratic Bézier curve through one control point
+    QuadTo { ctrl: [f32; 2], to: [f32; 2] },
+    /// A cubic Bézier curve through two control points
+    CubicTo { ctrl1: [f32; 2], ctrl2: [f32; 2], to: [f32; 2] },
+    /// Back to the start of the subpath
+    Close,
+}
+
+/// Subpaths of lines and curves.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Path {
+    pub commands: Vec<PathCommand>,
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn move_to(mut self, to: [f32; 2]) -> Self {
+        self.commands.push(PathCommand::MoveTo(to));
+        self
+    }
+
+    pub fn line_to(mut self, to: [f32; 2]) -> Self {
+        self.commands.push(PathCommand::LineTo(to));
+        self
+    }
+
+    pub fn quad_to(mut self, ctrl: [f32; 2], to: [f32; 2]) -> Self {
+        self.commands.push(PathCommand::QuadTo { ctrl, to });
+        self
+    }
+
+    pub fn cubic_to(mut self, ctrl1: [f32; 2], ctrl2: [f32; 2], to: [f32; 2]) -> Self {
+        self.commands.push(PathCommand::CubicTo { ctrl1, ctrl2, to });
+        self
+    }
+
+    pub fn close(mut self) -> Self {
+        self.commands.push(PathCommand::Close);
+        self
+    }
+
+    /// The path moved by `offset`
+    pub fn translated(&self, [dx, dy]: [f32; 2]) -> Self {
+        let p = |[x, y]: [f32; 2]| [x + dx, y + dy];
+        let commands = self.commands.iter().map(|command| match *command {
+            PathCommand::MoveTo(to) => PathCommand::MoveTo(p(to)),
+            PathCommand::LineTo(to) => PathCommand::LineTo(p(to)),
+            PathCommand::QuadTo { ctrl, to } => PathCommand::QuadTo { ctrl: p(ctrl), to: p(to) },
+            PathCommand::CubicTo { ctrl1, ctrl2, to } => PathCommand::CubicTo { ctrl1: p(ctrl1), ctrl2: p(ctrl2), to: p(to) },
+            PathCommand::Close => PathCommand::Close,
+        });
+        Self { commands: commands.collect() }
+    }
+
+    /// Reads SVG path data (`M 10 10 h 80 v 80 Z`, ...)
+    pub fn parse_svg(data: &str) -> anyhow::Result<Self> {
+        use svgtypes::SimplePathSegment as Segment;
+        let point = |x: f64, y: f64| [x as f32, y as f32];
+        let mut path = Self::new();
+        for segment in svgtypes::SimplifyingPathParser::from(data) {
+            let segment = segment.map_err(|e| anyhow::anyhow!("Invalid SVG path data: {}", e))?;
+            path.commands.push(match segment {
+                Segment::MoveTo { x, y } => PathCommand::MoveTo(point(x, y)),
+                Segment::LineTo { x, y } => PathCommand::LineTo(point(x, y)),
+                Segment::Quadratic { x1, y1, x, y } => PathCommand::QuadTo { ctrl: point(x1, y1), to: point(x, y) },
+                Segment::CurveTo { x1, y1, x2, y2, x, y } => PathCommand::CubicTo {
+                    ctrl1: point(x1, y1),
+                    ctrl2: point(x2, y2),
+                    to: point(x, y),
+                },
+                Segment::ClosePath => PathCommand::Close,
+            });
+        }
+        path.check()?;
+        Ok(path)
+    }
+
+    /// Checks that subpaths start with a `MoveTo`, and that every point is
+    /// finite
+    pub fn check(&self) -> anyhow::Result<()> {
+        let mut open = false;
+        for (i, command) in self.commands.iter().enumerate() {
+            let points: &[[f32; 2]] = match command {
+                PathCommand::MoveTo(to) | PathCommand::LineTo(to) => std::slice::from_ref(to),
+                PathCommand::QuadTo { ctrl, to } => &[*ctrl, *to],
+                PathCommand::CubicTo { ctrl1, ctrl2, to } => &[*ctrl1, *ctrl2, *to],
+                PathCommand::Close => &[],
+            };
+            anyhow::ensure!(
+                points.iter().flatten().all(|c| c.is_finite()),
+                "Command {} of the path is not at a finite point", i,
+            );
+            match command {
+                PathCommand::MoveTo(_) => open = true,
+                PathCommand::Close => open = false,
+                _ => anyhow::ensure!(open, "Command {} of the path does not follow a MoveTo", i),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Which parts of a path a fill covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FillRule {
+    /// Points the outline winds around a number of times other than 0
+    #[default]
+    NonZero,
+    /// Points inside an odd number of outlines (Holes where they overlap)
+    EvenOdd,
+}
+
+/// How corners of a stroke are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineJoin {
+    /// Sharp, cut off past the `miter_limit`
+    #[default]
+    Miter,
+    Round,
+    Bevel,
+}
+
+/// How the ends of open subpaths are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineCap {
+    /// At the end point
+    #[default]
+    Butt,
+    /// Half the width past the end point
+    Square,
+    Round,
+}
+
+/// How a path is stroked.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StrokeStyle {
+    /// In world units
+    pub width: f32,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    /// Longest miter, in multiples of half the width
+    pub miter_limit: f32,
+}
+
+impl Default for StrokeStyle {
+    /// 1 unit wide, mitered, with butt caps (The SVG defaults)
+    fn default() -> Self {
+        Self { width: 1.0, join: LineJoin::Miter, cap: LineCap::Butt, miter_limit: 4.0 }
+    }
+}
+
+impl StrokeStyle {
+    pub fn new(width: f32) -> Self {
+        Self { width, ..Self::default() }
+    }
+
+    pub fn with_join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    pub fn with_cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    pub fn with_miter_limit(mut self, miter_limit: f32) -> Self {
+        self.miter_limit = miter_limit;
+        self
+    }
+
+    /// Checks that the width is positive and the miter limit at least 1
+    pub fn check(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(self.width.is_finite() && self.width > 0.0, "A stroke is {} units wide", self.width);
+        anyhow::ensure!(self.miter_limit >= 1.0, "A stroke has a miter limit of {} (At least 1)", self.miter_limit);
+        Ok(())
+    }
+}
+
+/// Reads the `SvgPath` assets, by id in manifest order. `bytes` gives the
+/// payload of an asset.
+pub fn read_paths<'a>(
+    assets: &[AssetEntry],
+    bytes: impl Fn(&AssetEntry) -> anyhow::Result<&'a [u8]>,
+) -> anyhow::Result<Vec<(String, Path)>> {
+    assets.iter()
+        .filter(|a| matches!(a.kind, AssetKind::SvgPath))
+        .map(|asset| {
+            let path = std::str::from_utf8(bytes(asset)?)
+                .map_err(anyhow::Error::from)
+                .and_then(Path::parse_svg)
+                .with_context(|| format!("Failed to read path {}", asset.id))?;
+            Ok((asset.id.clone(), path))
+        })
+        .collect()
+}
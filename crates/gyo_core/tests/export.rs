@@ -0,0 +1,88 @@
+//! Exported shaders and their binding reports.
+
+use gyo_core::export::{self, ExportTarget, FULLSCREEN_VERTEX_ID};
+use gyo_core::import::Library;
+use gyo_core::reflect::ResourceKind;
+use gyo_core::shader::DEFAULT_FRAGMENT;
+use gyo_core::{AssetEntry, AssetKind, GyoshoFile, ProjectPacker, ShaderStage};
+
+const CHANNEL_IMAGE: &str = "\
+@group(1) @binding(0) var channel0: texture_2d<f32>;
+@group(1) @binding(1) var channel0_sampler: sampler;
+
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    return image(channel0, channel0_sampler, frag_coord / u.resolution);
+}
+";
+
+#[test]
+fn image_shaders_export_with_their_vertex_shader() {
+    for target in ExportTarget::ALL {
+        let exports = export::export_source("default.wgsl", DEFAULT_FRAGMENT, &Library::new(), target).unwrap();
+        let ids: Vec<_> = exports.iter().map(|e| e.asset_id.as_str()).collect();
+        assert_eq!(ids, [FULLSCREEN_VERTEX_ID, "default.wgsl"], "{:?}", target);
+
+        let fragment = &exports[1];
+        assert_eq!(fragment.entry_points.len(), 1);
+        assert_eq!(fragment.entry_points[0].0, ShaderStage::Fragment);
+        assert!(fragment.code.contains(&fragment.entry_points[0].1), "{:?}", target);
+
+        let uniform = &fragment.bindings[0];
+        assert_eq!((uniform.group, uniform.binding, uniform.name.as_str()), (0, 0, "u"));
+        assert_eq!(uniform.kind, ResourceKind::UniformBuffer { size: 48 });
+        let time = uniform.fields.iter().find(|f| f.name == "time").unwrap();
+        assert_eq!((time.offset, time.size), (40, 4));
+    }
+}
+
+#[test]
+fn binding_locations_per_target() {
+    let locations = |target| {
+        let exports = export::export_source("channels.wgsl", CHANNEL_IMAGE, &Library::new(), target).unwrap();
+        exports[1].bindings.iter()
+            .map(|b| (b.name.clone(), b.location.clone()))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(locations(ExportTarget::Hlsl), [
+        ("u".to_string(), "register(b0, space0)".to_string()),
+        ("channel0".to_string(), "register(t0, space1)".to_string()),
+        ("channel0_sampler".to_string(), "register(s1, space1)".to_string()),
+    ]);
+    assert_eq!(locations(ExportTarget::Msl), [
+        ("u".to_string(), "[[buffer(0)]]".to_string()),
+        ("channel0".to_string(), "[[texture(0)]]".to_string()),
+        ("channel0_sampler".to_string(), "[[sampler(0)]]".to_string()),
+    ]);
+
+    // GLSL ES 3.0 combines the texture and its sampler into one `sampler2D`
+    let glsl = locations(ExportTarget::GlslEs300);
+    assert!(glsl[0].1.starts_with("uniform block "), "{:?}", glsl);
+    assert!(glsl[1].1.starts_with("sampler2D "), "{:?}", glsl);
+    assert_eq!(glsl[1].1, glsl[2].1);
+}
+
+#[test]
+fn projects_export_every_sumi_source_once() {
+    let mut packer = ProjectPacker::new("Export", "tests");
+    packer
+        .add_asset("a.wgsl", AssetKind::SumiSource, DEFAULT_FRAGMENT.as_bytes())
+        .add_asset("b.wgsl", AssetKind::SumiSource, CHANNEL_IMAGE.as_bytes());
+    let bytes = packer.to_bytes().unwrap();
+
+    let exports = export::export_project(&bytes, None, ExportTarget::GlslEs300).unwrap();
+    let files: Vec<_> = exports.iter().map(|e| e.file_name()).collect();
+    assert_eq!(files, ["fullscreen_triangle.vert.glsl", "a.frag.glsl", "b.frag.glsl"]);
+    assert!(exports[1].code.starts_with("#version 300 es"), "{}", exports[1].code);
+}
+
+#[test]
+fn assets_outside_the_payload_are_reported() {
+    let mut manifest = ProjectPacker::new("Corrupt", "tests").manifest();
+    manifest.assets.push(AssetEntry { id: "a.wgsl".to_string(), kind: AssetKind::SumiSource, offset: u64::MAX, size: 2 });
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    GyoshoFile::write_new(&mut bytes, &manifest, b"").unwrap();
+
+    let error = export::export_project(&bytes.into_inner(), None, ExportTarget::GlslEs300).unwrap_err();
+    assert!(error.to_string().contains("outside payload bounds"), "{:#}", error);
+}
@@ -0,0 +1,93 @@
+//! Render graphs declared in the manifest.
+
+use gyo_core::graph::{self, Pass, PassInput, PassTarget, Persistence, TargetFormat};
+use gyo_core::{AssetKind, GyoshoFile, ProjectPacker};
+use std::io::Cursor;
+
+const IMAGE: &str = "\
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    return vec4<f32>(frag_coord / u.resolution, 0.0, 1.0);
+}
+";
+
+fn buffer(name: &str) -> Pass {
+    Pass::new(name, "image.wgsl").with_target(PassTarget::new(TargetFormat::Rgba16Float))
+}
+
+fn read(pass: &str) -> PassInput {
+    PassInput::Pass(pass.to_string())
+}
+
+fn packer(passes: Vec<Pass>) -> ProjectPacker {
+    let mut packer = ProjectPacker::new("Graph", "gyo_core tests");
+    packer.add_asset("image.wgsl", AssetKind::SumiSource, IMAGE.as_bytes());
+    for pass in passes {
+        packer.add_pass(pass);
+    }
+    packer
+}
+
+fn error(passes: Vec<Pass>) -> String {
+    format!("{:#}", packer(passes).to_bytes().unwrap_err())
+}
+
+#[test]
+fn passes_run_after_what_they_read() {
+    let passes = vec![
+        Pass::new("Image", "image.wgsl").with_input(read("Buffer B")),
+        buffer("Buffer B").with_input(read("Buffer A")).with_input(read("Buffer C")),
+        buffer("Buffer A"),
+        buffer("Buffer C"),
+    ];
+    let bytes = packer(passes.clone()).to_bytes().unwrap();
+    let manifest = GyoshoFile::read_manifest(&mut Cursor::new(bytes)).unwrap();
+    assert_eq!(manifest.passes, passes);
+
+    let order = graph::schedule(&manifest.passes, &manifest.assets).unwrap();
+    let names: Vec<&str> = order.iter().map(|&i| passes[i].name.as_str()).collect();
+    assert_eq!(names, ["Buffer A", "Buffer C", "Buffer B", "Image"]);
+}
+
+#[test]
+fn feedback_goes_through_previous_frames() {
+    let cycle = error(vec![
+        Pass::new("Image", "image.wgsl").with_input(read("Buffer A")),
+        buffer("Buffer A").with_input(read("Buffer B")),
+        buffer("Buffer B").with_input(read("Buffer A")),
+    ]);
+    assert!(cycle.contains("Buffer A -> Buffer B -> Buffer A (Read a `PreviousFrame`"), "{}", cycle);
+
+    let not_ping_pong = error(vec![
+        Pass::new("Image", "image.wgsl").with_input(read("Buffer A")),
+        buffer("Buffer A").with_input(PassInput::PreviousFrame("Buffer A".to_string())),
+    ]);
+    assert!(not_ping_pong.contains("reads the previous frame of `Buffer A`, which is not `PingPong`"), "{}", not_ping_pong);
+
+    // A pass reading its own previous frame
+    let ping_pong = buffer("Buffer A")
+        .with_target(PassTarget::new(TargetFormat::Rgba16Float).with_persistence(Persistence::PingPong))
+        .with_input(PassInput::PreviousFrame("Buffer A".to_string()));
+    packer(vec![Pass::new("Image", "image.wgsl").with_input(read("Buffer A")), ping_pong])
+        .to_bytes()
+        .unwrap();
+}
+
+#[test]
+fn graphs_are_checked_against_the_project() {
+    let cases = [
+        (vec![Pass::new("Image", "image.wgsl"), Pass::new("Other", "image.wgsl")], "exactly one pass without a target (Found 2)"),
+        (vec![Pass::new("Image", "image.wgsl"), buffer("Image")], "Pass `Image` is declared twice"),
+        (vec![Pass::new("Image", "missing.wgsl")], "draws `missing.wgsl`, which is not a SumiSource asset"),
+        (vec![Pass::new("Image", "image.wgsl").with_input(read("Buffer A"))], "reads `Buffer A`, which is not a pass"),
+        (vec![Pass::new("Image", "image.wgsl"), buffer("Buffer A").with_input(read("Image"))], "reads `Image`, which draws into the screen"),
+        (vec![Pass::new("Image", "image.wgsl").with_input(PassInput::Texture("image.wgsl".to_string()))], "which is not a TexturePng asset"),
+        (
+            vec![Pass::new("Image", "image.wgsl"), buffer("Buffer A").with_target(PassTarget::new(TargetFormat::Rgba8Unorm).with_scale(0.0))],
+            "Pass `Buffer A` has a target scale of 0",
+        ),
+    ];
+    for (passes, message) in cases {
+        let error = error(passes);
+        assert!(error.contains(message), "{:?} not in {:?}", message, error);
+    }
+}
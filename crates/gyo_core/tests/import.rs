@@ -0,0 +1,93 @@
+//! `#import` resolution across the assets of a project.
+
+use gyo_core::import::{self, Library};
+use gyo_core::validate;
+use gyo_core::{AssetKind, ProjectPacker};
+
+const HASH: &str = "\
+fn hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(12.9898, 78.233))) * 43758.5453);
+}
+";
+
+const NOISE: &str = "\
+#import \"hash.wgsl\"
+
+fn noise(p: vec2<f32>) -> f32 {
+    return hash(floor(p));
+}
+";
+
+const IMAGE: &str = "\
+#import \"noise.wgsl\"
+#import \"hash.wgsl\"
+
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    return vec4<f32>(vec3<f32>(noise(frag_coord) + hash(frag_coord)), 1.0);
+}
+";
+
+fn library<'a>(sources: &[(&'a str, &'a str)]) -> Library<'a> {
+    let mut library = Library::new();
+    for (id, source) in sources {
+        library.insert(id, source);
+    }
+    library
+}
+
+#[test]
+fn imports_are_included_once() {
+    let library = library(&[("hash.wgsl", HASH), ("noise.wgsl", NOISE)]);
+    let composed = import::resolve("image.wgsl", IMAGE, &library).unwrap();
+
+    assert_eq!(composed.code.matches("fn hash").count(), 1);
+    assert!(!composed.code.contains("#import"), "{}", composed.code);
+    // Dependencies come before the code using them
+    let origins: Vec<_> = composed.map.segments().iter().map(|s| s.origin.as_str()).collect();
+    assert_eq!(origins, ["hash.wgsl", "noise.wgsl", "image.wgsl"]);
+
+    let shader = validate::validate(gyo_core::shader::prepare_composed(composed)).unwrap();
+    assert!(shader.is_image());
+}
+
+#[test]
+fn errors_in_imports_point_at_the_library() {
+    let broken_noise = NOISE.replace("floor(p)", "floor(q)");
+    let library = library(&[("hash.wgsl", HASH), ("noise.wgsl", &broken_noise)]);
+    let composed = import::prepare("image.wgsl", IMAGE, &library).unwrap();
+    let error = validate::validate(composed).unwrap_err();
+
+    let diagnostic = &error.diagnostics[0];
+    assert_eq!((diagnostic.asset_id.as_str(), diagnostic.line), ("noise.wgsl", 4));
+    assert!(diagnostic.snippet.contains("4 |     return hash(floor(q));"), "{}", diagnostic.snippet);
+}
+
+#[test]
+fn cycles_and_missing_assets_are_reported() {
+    let library = library(&[
+        ("a.wgsl", "#import \"b.wgsl\"\nfn a() {}\n"),
+        ("b.wgsl", "fn b() {}\n#import \"a.wgsl\"\n"),
+    ]);
+    let error = import::resolve("a.wgsl", "#import \"b.wgsl\"\nfn a() {}\n", &library).unwrap_err();
+    let diagnostic = &error.diagnostics[0];
+    assert_eq!((diagnostic.asset_id.as_str(), diagnostic.line), ("b.wgsl", 2));
+    assert_eq!(diagnostic.message, "Import cycle: a.wgsl -> b.wgsl -> a.wgsl");
+
+    let error = import::resolve("c.wgsl", "\n#import \"missing.wgsl\"\n", &library).unwrap_err();
+    let diagnostic = &error.diagnostics[0];
+    assert_eq!((diagnostic.asset_id.as_str(), diagnostic.line), ("c.wgsl", 2));
+    assert!(diagnostic.message.contains("missing.wgsl"), "{}", diagnostic.message);
+}
+
+#[test]
+fn libraries_are_validated_where_they_are_imported() {
+    // Uses the prelude's `u`: Only valid inside an image shader
+    let timed = "fn wobble() -> f32 { return sin(u.time); }\n";
+    let image = "#import \"timed.wgsl\"\nfn main_image(c: vec4<f32>, p: vec2<f32>) -> vec4<f32> { return vec4<f32>(wobble()); }\n";
+
+    let mut packer = ProjectPacker::new("Imports", "tests");
+    packer
+        .add_asset("image.wgsl", AssetKind::SumiSource, image.as_bytes())
+        .add_asset("timed.wgsl", AssetKind::SumiSource, timed.as_bytes());
+    packer.to_bytes().unwrap();
+}
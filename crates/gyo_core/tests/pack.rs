@@ -0,0 +1,59 @@
+//! Packing unpacked project directories.
+
+use gyo_core::background::Background;
+use gyo_core::pipeline::{BlendMode, CullMode};
+use gyo_core::post::Effect;
+use gyo_core::{pack, ProjectPacker};
+use std::path::PathBuf;
+
+fn project_dir(name: &str, declarations: Option<&str>) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("pack").join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("frag.wgsl"), gyo_core::shader::DEFAULT_FRAGMENT).unwrap();
+    if let Some(declarations) = declarations {
+        std::fs::write(dir.join(pack::DECLARATIONS_FILE), declarations).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn directories_declare_what_their_files_cannot() {
+    let dir = project_dir("declared", Some(r#"(
+        title: Some("Declared"),
+        background: Some(Gradient(bottom: (0.0, 0.0, 0.1, 1.0), top: (0.9, 0.4, 0.1, 1.0))),
+        variants: [(name: "fast", defines: [("FAST", "")])],
+        pipelines: [(
+            asset_id: "frag.wgsl",
+            entry_points: [],
+            blend: Some(Additive),
+            topology: TriangleList,
+            cull: Back,
+            depth: None,
+        )],
+        effects: [Fxaa],
+    )"#));
+
+    let manifest = ProjectPacker::from_dir(&dir).unwrap().manifest();
+    assert_eq!(manifest.title, "Declared");
+    assert_eq!(manifest.assets.len(), 1, "The declarations are not an asset");
+    assert_eq!(manifest.background, Background::Gradient { bottom: [0.0, 0.0, 0.1, 1.0], top: [0.9, 0.4, 0.1, 1.0] });
+    assert_eq!(manifest.variants[0].name, "fast");
+    assert_eq!(manifest.pipelines[0].blend, Some(BlendMode::Additive));
+    assert_eq!(manifest.pipelines[0].cull, CullMode::Back);
+    assert_eq!(manifest.effects, vec![Effect::Fxaa]);
+
+    // Without declarations: The directory name and the defaults
+    let manifest = ProjectPacker::from_dir(project_dir("undeclared", None)).unwrap().manifest();
+    assert_eq!(manifest.title, "undeclared");
+    assert_eq!(manifest.background, Background::Sky);
+    assert!(manifest.pipelines.is_empty() && manifest.effects.is_empty());
+}
+
+#[test]
+fn bad_declarations_are_reported() {
+    let dir = project_dir("misspelled", Some("(efects: [Fxaa])"));
+    let error = ProjectPacker::from_dir(&dir).expect_err("Expected an unknown field");
+    let message = format!("{:#}", error);
+    assert!(message.contains(pack::DECLARATIONS_FILE) && message.contains("efects"), "{}", message);
+}
@@ -0,0 +1,57 @@
+//! Particle emitters declared in the manifest.
+
+use gyo_core::particles::{Emitter, Forces};
+use gyo_core::{AssetKind, GyoshoFile, ProjectPacker};
+use std::io::Cursor;
+
+const SPRITES: &str = "\
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+    return vec4<f32>(f32(i), 0.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0);
+}
+";
+
+fn packer() -> ProjectPacker {
+    let mut packer = ProjectPacker::new("Particles", "gyo_core tests");
+    packer.add_asset("sprites.wgsl", AssetKind::SumiSource, SPRITES.as_bytes());
+    packer
+}
+
+#[test]
+fn emitters_round_trip() {
+    let rain = Emitter::new("rain", 50_000)
+        .with_rate(20_000.0)
+        .with_area([-200.0, -50.0, 1680.0, 10.0])
+        .with_velocity([0.0, 900.0], [0.0, 1500.0])
+        .with_align(true)
+        .with_forces(Forces::default().with_wind([300.0, 1200.0], 1.5).with_noise(40.0, 200.0));
+    let mut packer = packer();
+    packer.add_emitter(Emitter::new("rain", 10)).add_emitter(rain.clone());
+
+    let bytes = packer.to_bytes().unwrap();
+    let manifest = GyoshoFile::read_manifest(&mut Cursor::new(bytes)).unwrap();
+    // The later declaration replaced the first
+    assert_eq!(manifest.particles, [rain]);
+}
+
+#[test]
+fn emitters_are_checked() {
+    let cases = [
+        (Emitter::new("empty", 0), "Emitter `empty` holds 0 particles"),
+        (Emitter::new("rate", 10).with_rate(-1.0), "Emitter `rate` has a rate of -1"),
+        (Emitter::new("life", 10).with_lifetime(2.0, 1.0), "Emitter `life` lives 2 to 1 seconds"),
+        (Emitter::new("swirls", 10).with_forces(Forces::default().with_noise(1.0, 0.0)), "turbulence swirls of size 0"),
+        (Emitter::new("atlas", 10).with_atlas("sprites.wgsl"), "samples `sprites.wgsl`, which is not a TexturePng asset"),
+    ];
+    for (emitter, message) in cases {
+        let mut packer = packer();
+        packer.add_emitter(emitter);
+        let error = format!("{:#}", packer.to_bytes().unwrap_err());
+        assert!(error.contains(message), "{:?} not in {:?}", message, error);
+    }
+}
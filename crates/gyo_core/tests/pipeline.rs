@@ -0,0 +1,59 @@
+//! Pipeline state declared in the manifest.
+
+use gyo_core::pipeline::{BlendMode, CullMode, DepthState, Topology};
+use gyo_core::validate::ShaderError;
+use gyo_core::{AssetKind, EntryPoint, GyoshoFile, PipelineState, ProjectPacker, ShaderStage};
+use std::io::Cursor;
+
+const LINES: &str = "\
+@vertex
+fn vs_lines(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+    return vec4<f32>(f32(i & 1u), f32(i >> 1u), 0.0, 1.0);
+}
+
+@fragment
+fn fs_lines() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0);
+}
+";
+
+fn lines_pipeline() -> PipelineState {
+    PipelineState::new("lines.wgsl")
+        .with_entry_point(EntryPoint::new("vs_lines", ShaderStage::Vertex))
+        .with_entry_point(EntryPoint::new("fs_lines", ShaderStage::Fragment))
+        .with_blend(BlendMode::Additive)
+        .with_topology(Topology::LineStrip)
+        .with_cull(CullMode::Back)
+        .with_depth(DepthState::default())
+}
+
+#[test]
+fn pipelines_are_stored_in_the_manifest() {
+    let mut packer = ProjectPacker::new("Lines", "gyo_core tests");
+    packer.add_asset("lines.wgsl", AssetKind::SumiSource, LINES.as_bytes())
+        .add_pipeline(PipelineState::new("lines.wgsl"))
+        .add_pipeline(lines_pipeline());
+
+    let bytes = packer.to_bytes().unwrap();
+    let manifest = GyoshoFile::read_manifest(&mut Cursor::new(bytes)).unwrap();
+    assert_eq!(manifest.pipelines, [lines_pipeline()]);
+    assert_eq!(manifest.pipeline("lines.wgsl"), Some(&lines_pipeline()));
+}
+
+#[test]
+fn declared_entry_points_must_exist() {
+    let mut packer = ProjectPacker::new("Lines", "gyo_core tests");
+    packer.add_asset("lines.wgsl", AssetKind::SumiSource, LINES.as_bytes())
+        .add_pipeline(lines_pipeline().with_entry_point(EntryPoint::new("fs_lines", ShaderStage::Vertex)))
+        .add_pipeline(PipelineState::new("missing.wgsl"));
+
+    let error = packer.to_bytes().unwrap_err();
+    let error = error.downcast_ref::<ShaderError>().unwrap();
+    let messages: Vec<_> = error.diagnostics.iter()
+        .map(|d| (d.asset_id.as_str(), d.message.as_str()))
+        .collect();
+    assert_eq!(messages, [
+        ("lines.wgsl", "Declared Vertex entry point `fs_lines` is not in the module"),
+        ("missing.wgsl", "Pipeline declared for `missing.wgsl`, which is not a shader drawn on its own"),
+    ]);
+}
@@ -0,0 +1,93 @@
+//! Post-processing effects and backgrounds declared in the manifest.
+
+use gyo_core::background::Background;
+use gyo_core::post::{Bloom, Effect, ToneMap, ToneMapper};
+use gyo_core::texture::Texture;
+use gyo_core::{AssetKind, GyoshoFile, ProjectPacker};
+use std::io::Cursor;
+
+const IMAGE: &str = "\
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    return vec4<f32>(frag_coord / u.resolution, 0.0, 1.0);
+}
+";
+
+const SPRITES: &str = "\
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+    return vec4<f32>(f32(i), 0.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0);
+}
+";
+
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut png = Vec::new();
+    Texture::new(width, height, vec![255; (width * height * 4) as usize]).write_png(&mut png).unwrap();
+    png
+}
+
+fn packer(effects: Vec<Effect>) -> ProjectPacker {
+    let mut packer = ProjectPacker::new("Post", "gyo_core tests");
+    packer.add_asset("image.wgsl", AssetKind::SumiSource, IMAGE.as_bytes())
+        .add_asset("sprites.wgsl", AssetKind::SumiSource, SPRITES.as_bytes())
+        .add_asset("lut.png", AssetKind::TexturePng, &png(16, 4))
+        .add_asset("wide.png", AssetKind::TexturePng, &png(16, 2));
+    for effect in effects {
+        packer.add_effect(effect);
+    }
+    packer
+}
+
+fn error(effects: Vec<Effect>) -> String {
+    format!("{:#}", packer(effects).to_bytes().unwrap_err())
+}
+
+#[test]
+fn effects_are_stored_in_order() {
+    let effects = vec![
+        Effect::Bloom(Bloom::default().with_threshold(0.8)),
+        Effect::ToneMap(ToneMap::new(ToneMapper::Reinhard).with_exposure(1.5)),
+        Effect::ColorGrade { lut: "lut.png".to_string() },
+        Effect::Fxaa,
+        Effect::Custom { asset_id: "image.wgsl".to_string() },
+    ];
+    let bytes = packer(effects.clone()).to_bytes().unwrap();
+    let manifest = GyoshoFile::read_manifest(&mut Cursor::new(bytes)).unwrap();
+    assert_eq!(manifest.effects, effects);
+}
+
+#[test]
+fn effects_are_checked_against_the_project() {
+    let cases = [
+        (Effect::ColorGrade { lut: "image.wgsl".to_string() }, "Effect 0 (ColorGrade) reads `image.wgsl`, which is not a TexturePng asset"),
+        (Effect::ColorGrade { lut: "wide.png".to_string() }, "LUT `wide.png` is 16x2, expected a strip of N squares of NxN (N*N x N)"),
+        (Effect::Custom { asset_id: "missing.wgsl".to_string() }, "Effect 0 (Custom) draws `missing.wgsl`, which is not a SumiSource asset"),
+        (Effect::Custom { asset_id: "sprites.wgsl".to_string() }, "Effect shader `sprites.wgsl` is not an image shader (`main_image`)"),
+        (Effect::ToneMap(ToneMap::new(ToneMapper::Aces).with_exposure(0.0)), "Effect 0 (ToneMap) has an exposure of 0"),
+        (Effect::Bloom(Bloom::default().with_radius(-1.0)), "Effect 0 (Bloom) needs a positive radius and intensity"),
+    ];
+    for (effect, message) in cases {
+        let error = error(vec![effect]);
+        assert!(error.contains(message), "{:?} not in {:?}", message, error);
+    }
+}
+
+#[test]
+fn background_shaders_are_image_shaders() {
+    let mut packer = packer(vec![]);
+    packer.background = Background::Shader { asset_id: "image.wgsl".to_string() };
+    let bytes = packer.to_bytes().unwrap();
+    let manifest = GyoshoFile::read_manifest(&mut Cursor::new(bytes)).unwrap();
+    assert_eq!(manifest.background, packer.background);
+
+    packer.background = Background::Shader { asset_id: "sprites.wgsl".to_string() };
+    let error = format!("{:#}", packer.to_bytes().unwrap_err());
+    assert!(error.contains("Background shader `sprites.wgsl` is not an image shader"), "{}", error);
+    packer.background = Background::Shader { asset_id: "lut.png".to_string() };
+    let error = format!("{:#}", packer.to_bytes().unwrap_err());
+    assert!(error.contains("The background draws `lut.png`, which is not a SumiSource asset"), "{}", error);
+}
@@ -0,0 +1,157 @@
+//! `#define` / `#ifdef` preprocessing and manifest variants.
+
+use gyo_core::import::{self, Library};
+use gyo_core::preprocess::{self, Defines};
+use gyo_core::validate::{self, ShaderError};
+use gyo_core::{AssetKind, GyoshoFile, ProjectPacker, ShaderVariant};
+use std::io::Cursor;
+
+const IMAGE: &str = "\
+#define STEPS 4
+
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    var total = 0.0;
+    for (var i = 0; i < STEPS; i++) {
+        total += 0.1;
+    }
+#ifdef SHADOWS
+    total *= 0.5;
+#else
+    total *= 2.0;
+#endif
+    return vec4<f32>(vec3<f32>(total), 1.0);
+}
+";
+
+fn defines(pairs: &[(&str, &str)]) -> Defines {
+    pairs.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect()
+}
+
+#[test]
+fn directives_keep_line_numbers() {
+    let output = preprocess::preprocess("image.wgsl", IMAGE, &Defines::new()).unwrap();
+
+    assert_eq!(output.lines().count(), IMAGE.lines().count());
+    assert_eq!(output.lines().nth(4), Some("    for (var i = 0; i < 4; i++) {"));
+    assert!(output.contains("total *= 2.0;") && !output.contains("0.5"), "{}", output);
+    assert!(!output.contains('#'), "{}", output);
+}
+
+#[test]
+fn variant_defines_win_over_the_source() {
+    let output = preprocess::preprocess("image.wgsl", IMAGE, &defines(&[("STEPS", "64"), ("SHADOWS", "")])).unwrap();
+
+    assert!(output.contains("i < 64;"), "{}", output);
+    assert!(output.contains("total *= 0.5;") && !output.contains("2.0"), "{}", output);
+}
+
+#[test]
+fn only_whole_identifiers_are_replaced() {
+    let source = "#define x 2.0\nlet a = v.x + x + x2 + 1e3 + max_x;";
+    let output = preprocess::preprocess("a.wgsl", source, &Defines::new()).unwrap();
+
+    assert_eq!(output.lines().nth(1), Some("let a = v.x + 2.0 + x2 + 1e3 + max_x;"));
+}
+
+#[test]
+fn unbalanced_conditionals_are_reported() {
+    let error = preprocess::preprocess("a.wgsl", "fn a() {}\n#ifdef A\n#ifdef B\n#endif\n", &Defines::new()).unwrap_err();
+    let diagnostic = &error.diagnostics[0];
+    assert_eq!((diagnostic.asset_id.as_str(), diagnostic.line), ("a.wgsl", 2));
+    assert!(diagnostic.message.contains("#endif"), "{}", diagnostic.message);
+
+    let error = preprocess::preprocess("a.wgsl", "#else\n", &Defines::new()).unwrap_err();
+    assert_eq!(error.diagnostics[0].line, 1);
+}
+
+#[test]
+fn imports_can_be_conditional() {
+    let mut library = Library::new();
+    library.insert("fast.wgsl", "fn shade() -> f32 { return 0.0; }\n");
+    library.insert("slow.wgsl", "fn shade() -> f32 { return 1.0; }\n");
+    let image = "#ifdef FAST\n#import \"fast.wgsl\"\n#else\n#import \"slow.wgsl\"\n#endif\n";
+
+    let composed = import::resolve("image.wgsl", image, &library).unwrap();
+    assert!(composed.code.contains("return 1.0;") && !composed.code.contains("return 0.0;"), "{}", composed.code);
+
+    library.define("FAST", "");
+    let composed = import::resolve("image.wgsl", image, &library).unwrap();
+    assert!(composed.code.contains("return 0.0;") && !composed.code.contains("return 1.0;"), "{}", composed.code);
+}
+
+#[test]
+fn imports_left_out_by_a_variant_are_shaders_of_their_own() {
+    let debug = "fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> { return vec4<f32>(1.0, 0.0, 1.0, 1.0); }\n";
+    let main = "\
+#ifdef DEBUG
+#import \"debug.wgsl\"
+#else
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> { return frag_color; }
+#endif
+";
+    let mut library = Library::new();
+    library.insert("main.wgsl", main).insert("debug.wgsl", debug);
+    assert!(library.imported().is_empty());
+    library.define("DEBUG", "");
+    assert_eq!(library.imported().into_iter().collect::<Vec<_>>(), ["debug.wgsl"]);
+
+    // Only the variant importing it validates it as part of `main.wgsl`
+    let mut packer = ProjectPacker::new("Debug", "gyo_core tests");
+    packer.add_asset("main.wgsl", AssetKind::SumiSource, main.as_bytes())
+        .add_asset("debug.wgsl", AssetKind::SumiSource, debug.as_bytes());
+    let manifest = packer.manifest();
+    let payload = [main.as_bytes(), debug.as_bytes()].concat();
+    let assets = || manifest.assets.iter().map(|a| (a, a.bytes(&payload).unwrap()));
+    let ids = |shaders: Vec<(&gyo_core::AssetEntry, _)>| shaders.into_iter().map(|(a, _)| a.id.clone()).collect::<Vec<_>>();
+    assert_eq!(ids(validate::validate_all(assets(), None).unwrap()), ["main.wgsl", "debug.wgsl"]);
+    let variant = ShaderVariant::new("debug", [("DEBUG", "")]);
+    assert_eq!(ids(validate::validate_all(assets(), Some(&variant)).unwrap()), ["main.wgsl"]);
+}
+
+#[test]
+fn variants_are_stored_and_all_validated() {
+    let mut packer = ProjectPacker::new("Variants", "gyo_core tests");
+    packer.add_asset("image.wgsl", AssetKind::SumiSource, IMAGE.as_bytes())
+        .add_variant(ShaderVariant::new("low", [("STEPS", "2")]))
+        .add_variant(ShaderVariant::new("high", [("STEPS", "64"), ("SHADOWS", "")]));
+
+    let bytes = packer.to_bytes().unwrap();
+    let manifest = GyoshoFile::read_manifest(&mut Cursor::new(&bytes)).unwrap();
+    assert_eq!(manifest.variants, packer.manifest().variants);
+    assert_eq!(manifest.variant(None).unwrap().unwrap().name, "low");
+    assert!(manifest.variant(Some("ultra")).is_err());
+
+    // A variant that breaks the shader fails packing, naming the variant
+    packer.add_variant(ShaderVariant::new("broken", [("STEPS", "missing")]));
+    let error = packer.to_bytes().unwrap_err();
+    let error = error.downcast_ref::<ShaderError>().unwrap();
+    assert_eq!(error.diagnostics.len(), 1);
+    assert!(error.diagnostics[0].notes.iter().any(|n| n == "In variant `broken`"), "{:?}", error.diagnostics[0]);
+}
+
+#[test]
+fn version_1_files_have_no_variants() {
+    use binrw::BinWrite;
+
+    let packer = ProjectPacker::new("Old", "gyo_core tests");
+    let manifest = packer.manifest();
+    // Version 1 manifests end after `compute_kernels`
+    let manifest_bytes = bincode::serialize(&(
+        &manifest.title, &manifest.author, manifest.timestamp, &manifest.assets, &manifest.compute_kernels,
+    )).unwrap();
+    let file = GyoshoFile {
+        magic: *b"GYO1",
+        version: 1,
+        manifest_len: manifest_bytes.len() as u32,
+        manifest_bytes,
+        compressed_payload: Vec::new(),
+    };
+    let mut bytes = Cursor::new(Vec::new());
+    file.write(&mut bytes).unwrap();
+
+    let manifest = GyoshoFile::read_manifest(&mut Cursor::new(bytes.into_inner())).unwrap();
+    assert_eq!(manifest.title, "Old");
+    assert!(manifest.variants.is_empty());
+    assert_eq!(manifest.background, gyo_core::background::Background::Sky);
+    assert!(manifest.particles.is_empty());
+}
@@ -0,0 +1,185 @@
+//! Reflected bindings and the check against a project's assets.
+
+use gyo_core::reflect::{self, ResourceKind};
+use gyo_core::validate::{self, ValidatedShader};
+use gyo_core::{AssetEntry, AssetKind};
+
+const CHANNEL_IMAGE: &str = "\
+@group(1) @binding(0) var channel0: texture_2d<f32>;
+@group(1) @binding(1) var channel0_sampler: sampler;
+@group(1) @binding(2) var channel1: texture_2d<f32>;
+
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    let uv = frag_coord / u.resolution;
+    return image(channel0, channel0_sampler, uv) + image(channel1, channel0_sampler, uv);
+}
+";
+
+fn asset(id: &str, kind: AssetKind) -> AssetEntry {
+    AssetEntry { id: id.to_string(), kind, offset: 0, size: 0 }
+}
+
+fn check(shader: &ValidatedShader, assets: &[AssetEntry]) -> Result<reflect::BindingCheck, validate::ShaderError> {
+    reflect::check_bindings(&[(&assets[0], shader)], assets)
+}
+
+#[test]
+fn resources_of_an_image_shader() {
+    let shader = validate::validate_asset("channels.wgsl", CHANNEL_IMAGE).unwrap();
+    let resources = reflect::resources(&shader);
+
+    let bindings: Vec<_> = resources.iter().map(|r| (r.group, r.binding, r.name.as_str(), r.kind)).collect();
+    assert_eq!(bindings, [
+        (0, 0, "u", ResourceKind::UniformBuffer { size: 48 }),
+        (1, 0, "channel0", ResourceKind::Texture),
+        (1, 1, "channel0_sampler", ResourceKind::Sampler),
+        (1, 2, "channel1", ResourceKind::Texture),
+    ]);
+    assert!(resources.iter().all(|r| r.visibility == wgpu_types::ShaderStages::FRAGMENT));
+
+    let groups = reflect::bind_group_entries(&resources);
+    assert_eq!(groups.iter().map(Vec::len).collect::<Vec<_>>(), [1, 3]);
+}
+
+#[test]
+fn missing_and_unused_textures_are_warnings() {
+    let shader = validate::validate_asset("channels.wgsl", CHANNEL_IMAGE).unwrap();
+
+    // Channel 1 has no texture
+    let one_texture = [asset("channels.wgsl", AssetKind::SumiSource), asset("a.png", AssetKind::TexturePng)];
+    let result = check(&shader, &one_texture).unwrap();
+    assert_eq!(result.missing_channels, [("channels.wgsl".to_string(), 1)]);
+    assert!(result.unused_textures.is_empty());
+
+    // The default shader samples nothing
+    let default = validate::validate_asset("default.wgsl", gyo_core::shader::DEFAULT_FRAGMENT).unwrap();
+    let result = check(&default, &one_texture).unwrap();
+    assert_eq!(result.unused_textures, ["a.png"]);
+    assert_eq!(result.warnings().len(), 1);
+}
+
+#[test]
+fn unknown_bindings_point_at_their_declaration() {
+    let source = "\
+@group(2) @binding(0) var<storage, read> particles: array<vec4<f32>>;
+
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    return particles[0];
+}
+";
+    let shader = validate::validate_asset("storage.wgsl", source).unwrap();
+    let error = check(&shader, &[asset("storage.wgsl", AssetKind::SumiSource)]).unwrap_err();
+    let diagnostic = &error.diagnostics[0];
+
+    assert_eq!((diagnostic.asset_id.as_str(), diagnostic.line), ("storage.wgsl", 1));
+    assert!(diagnostic.message.contains("`particles` (@group(2) @binding(0))"), "{}", diagnostic.message);
+}
+
+#[test]
+fn channel_bindings_must_match_their_type() {
+    let source = "\
+@group(1) @binding(1) var not_a_sampler: texture_2d<f32>;
+
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    return textureLoad(not_a_sampler, vec2<i32>(0), 0);
+}
+";
+    let shader = validate::validate_asset("typed.wgsl", source).unwrap();
+    let error = check(&shader, &[asset("typed.wgsl", AssetKind::SumiSource)]).unwrap_err();
+    assert!(error.diagnostics[0].message.contains("sampler for channel 0"), "{}", error.diagnostics[0].message);
+}
+
+#[test]
+fn sprite_shaders_sample_an_atlas() {
+    let source = "\
+@group(2) @binding(0) var atlas: texture_2d<f32>;
+@group(2) @binding(1) var atlas_sampler: sampler;
+
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+    return vec4<f32>(f32(i), 0.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    return textureSample(atlas, atlas_sampler, position.xy);
+}
+";
+    let shader = validate::validate_asset("sprites.wgsl", source).unwrap();
+    let assets = [asset("sprites.wgsl", AssetKind::SumiSource), asset("tiles.png", AssetKind::TexturePng)];
+    // Any texture may be the atlas of a batch
+    let result = check(&shader, &assets).unwrap();
+    assert!(result.warnings().is_empty(), "{:?}", result.warnings());
+
+    let source = source.replace("var atlas_sampler: sampler", "var atlas_sampler: texture_2d<f32>")
+        .replace("textureSample(atlas, atlas_sampler, position.xy)", "textureLoad(atlas_sampler, vec2<i32>(0), 0)");
+    let shader = validate::validate_asset("sprites.wgsl", &source).unwrap();
+    let error = check(&shader, &assets).unwrap_err();
+    assert!(error.diagnostics[0].message.contains("sampler for the sprite atlas"), "{}", error.diagnostics[0].message);
+}
+
+#[test]
+fn sprite_shaders_see_through_the_camera() {
+    let source = "\
+struct Camera {
+    view_proj: mat4x4<f32>,
+};
+
+@group(0) @binding(1) var<uniform> camera: Camera;
+
+@vertex
+fn vs_main(@location(0) pos: vec2<f32>) -> @builtin(position) vec4<f32> {
+    return camera.view_proj * vec4<f32>(pos, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0);
+}
+";
+    let shader = validate::validate_asset("sprites.wgsl", source).unwrap();
+    let assets = [asset("sprites.wgsl", AssetKind::SumiSource)];
+    check(&shader, &assets).unwrap();
+
+    // The camera is a single matrix
+    let source = source.replace("view_proj: mat4x4<f32>,", "view_proj: mat4x4<f32>,\n    extra: vec4<f32>,");
+    let shader = validate::validate_asset("sprites.wgsl", &source).unwrap();
+    let error = check(&shader, &assets).unwrap_err();
+    assert!(error.diagnostics[0].message.contains("larger than the 64 bytes of the camera"), "{}", error.diagnostics[0].message);
+}
+
+#[test]
+fn vertex_inputs_are_checked_against_a_layout() {
+    let source = "\
+struct Instance {
+    @location(0) pos: vec3<f32>,
+    @location(2) tint: vec4<f32>,
+};
+
+@vertex
+fn vs_main(instance: Instance, @location(1) size: vec3<f32>) -> @builtin(position) vec4<f32> {
+    return vec4<f32>(instance.pos + size, instance.tint.a);
+}
+";
+    let shader = validate::validate_asset("sprites.wgsl", source).unwrap();
+    let inputs: Vec<_> = reflect::vertex_inputs(&shader, "vs_main").into_iter()
+        .map(|input| (input.location, input.name, input.ty, input.format))
+        .collect();
+    assert_eq!(inputs, [
+        (0, "pos".to_string(), "vec3<f32>".to_string(), Some(wgpu_types::VertexFormat::Float32x3)),
+        (1, "size".to_string(), "vec3<f32>".to_string(), Some(wgpu_types::VertexFormat::Float32x3)),
+        (2, "tint".to_string(), "vec4<f32>".to_string(), Some(wgpu_types::VertexFormat::Float32x4)),
+    ]);
+
+    // Location 1 is a vec2 in the layout, location 2 is missing
+    let attribute = |shader_location, format| wgpu_types::VertexAttribute { format, offset: 0, shader_location };
+    let layout = [attribute(0, wgpu_types::VertexFormat::Float32x3), attribute(1, wgpu_types::VertexFormat::Float32x2)];
+    let asset = asset("sprites.wgsl", AssetKind::SumiSource);
+    let error = reflect::check_vertex_inputs(&asset, &shader, "vs_main", &layout).unwrap_err();
+    let messages: Vec<_> = error.diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(messages, [
+        "Vertex input `size` (@location(1)) is a `vec3<f32>`, but the instance layout has a Float32x2 there",
+        "Vertex input `tint` (@location(2)) is not in the instance layout, which has locations [0, 1]",
+    ]);
+    assert_eq!(error.diagnostics[1].line, 1); // The struct declaring it
+}
@@ -0,0 +1,117 @@
+//! Shader diagnostics must point into the asset, not the composed module.
+
+use gyo_core::shader::PRELUDE_ORIGIN;
+use gyo_core::validate::{self, ShaderError};
+use gyo_core::{AssetKind, EntryPoint, ProjectPacker, ShaderStage};
+
+const BROKEN_IMAGE: &str = "\
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    let uv = frag_coord / u.resolution;
+    return vec4<f32>(uv, missing, 1.0);
+}
+";
+
+const BAD_TYPES: &str = "\
+@fragment
+fn main() -> @location(0) vec4<f32> {
+    let x: f32 = 1u;
+    return vec4<f32>(x);
+}
+";
+
+#[test]
+fn parse_error_maps_to_asset_line() {
+    let error = validate::validate_asset("broken.wgsl", BROKEN_IMAGE).unwrap_err();
+    let diagnostic = &error.diagnostics[0];
+
+    assert_eq!(diagnostic.asset_id, "broken.wgsl");
+    assert_eq!((diagnostic.line, diagnostic.column), (3, 26));
+    assert!(diagnostic.message.contains("missing"), "{}", diagnostic.message);
+    assert!(diagnostic.snippet.contains("3 |     return vec4<f32>(uv, missing, 1.0);"), "{}", diagnostic.snippet);
+    assert!(diagnostic.snippet.contains("^^^^^^^"), "{}", diagnostic.snippet);
+}
+
+#[test]
+fn raw_module_errors_keep_their_lines() {
+    let error = validate::validate_asset("raw.wgsl", BAD_TYPES).unwrap_err();
+    let diagnostic = &error.diagnostics[0];
+
+    assert_eq!(diagnostic.asset_id, "raw.wgsl");
+    assert_eq!(diagnostic.line, 3);
+}
+
+#[test]
+fn prelude_errors_are_attributed_to_the_prelude() {
+    // Wrong `main_image` signature: the call in the prelude suffix fails
+    let source = "fn main_image(frag_coord: vec2<f32>) -> vec4<f32> { return vec4<f32>(1.0); }";
+    let error = validate::validate_asset("sig.wgsl", source).unwrap_err();
+
+    assert_eq!(error.diagnostics[0].asset_id, PRELUDE_ORIGIN);
+}
+
+#[test]
+fn packer_reports_every_invalid_asset() {
+    let mut packer = ProjectPacker::new("Broken", "tests");
+    packer
+        .add_asset("a.wgsl", AssetKind::SumiSource, BROKEN_IMAGE.as_bytes())
+        .add_asset("ok.wgsl", AssetKind::SumiSource, gyo_core::shader::DEFAULT_FRAGMENT.as_bytes())
+        .add_asset("b.wgsl", AssetKind::SumiSource, BAD_TYPES.as_bytes());
+
+    let error = packer.to_bytes().unwrap_err();
+    let error = error.downcast_ref::<ShaderError>().expect("Expected a ShaderError");
+    let assets: Vec<_> = error.diagnostics.iter().map(|d| d.asset_id.as_str()).collect();
+    assert_eq!(assets, ["a.wgsl", "b.wgsl"]);
+}
+
+#[test]
+fn glsl_errors_map_to_asset_line() {
+    let source = "\
+void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+    vec2 uv = fragCoord / iResolution.xy;
+    fragColor = vec4(uv, missing, 1.0);
+}
+";
+    let error = validate::validate_glsl_asset("toy.glsl", source).unwrap_err();
+    let diagnostic = &error.diagnostics[0];
+
+    assert_eq!(diagnostic.asset_id, "toy.glsl");
+    assert_eq!(diagnostic.line, 3);
+    assert!(diagnostic.snippet.contains("missing"), "{}", diagnostic.snippet);
+}
+
+/// The default image shader compiled to a SPIR-V binary
+fn default_spirv() -> Vec<u8> {
+    let shader = validate::validate_asset("default.wgsl", gyo_core::shader::DEFAULT_FRAGMENT).unwrap();
+    let options = naga::back::spv::Options::default();
+    naga::back::spv::write_vec(&shader.module, &shader.info, &options, None)
+        .unwrap()
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .collect()
+}
+
+#[test]
+fn spirv_entry_points_are_checked() {
+    let bytes = default_spirv();
+    let entry_points = validate::spirv_entry_points(&bytes).unwrap();
+    assert_eq!(entry_points, [EntryPoint::new("main", ShaderStage::Fragment)]);
+
+    let shader = validate::validate_spirv("default.spv", &bytes, &entry_points).unwrap();
+    assert!(shader.is_image());
+    assert_eq!(shader.entry_point(ShaderStage::Fragment), Some("main"));
+
+    let missing = [EntryPoint::new("vs_main", ShaderStage::Vertex)];
+    let error = validate::validate_spirv("default.spv", &bytes, &missing).unwrap_err();
+    assert_eq!(error.diagnostics[0].asset_id, "default.spv");
+    assert!(error.diagnostics[0].message.contains("vs_main"), "{}", error.diagnostics[0].message);
+}
+
+#[test]
+fn spirv_garbage_is_reported() {
+    let mut packer = ProjectPacker::new("Garbage", "tests");
+    packer.add_asset("junk.spv", AssetKind::SpirV { entry_points: vec![] }, &[1, 2, 3, 4, 5, 6, 7, 8]);
+
+    let error = packer.to_bytes().unwrap_err();
+    let error = error.downcast_ref::<ShaderError>().expect("Expected a ShaderError");
+    assert_eq!(error.diagnostics[0].asset_id, "junk.spv");
+}
@@ -0,0 +1,45 @@
+//! Vector paths, built in code or read from SVG path data.
+
+use gyo_core::vector::{Path, PathCommand, StrokeStyle};
+use gyo_core::{AssetKind, ProjectPacker};
+
+#[test]
+fn svg_path_data_becomes_absolute_commands() {
+    // Relative lines, shorthand curves and an arc
+    let path = Path::parse_svg("M10 20 h30 v10 l-5 5 Q 0 0 10 10 T 30 30 a 5 5 0 0 1 10 0 z").unwrap();
+    let commands = &path.commands;
+    assert_eq!(commands[..4], [
+        PathCommand::MoveTo([10.0, 20.0]),
+        PathCommand::LineTo([40.0, 20.0]),
+        PathCommand::LineTo([40.0, 30.0]),
+        PathCommand::LineTo([35.0, 35.0]),
+    ]);
+    assert_eq!(commands[4], PathCommand::QuadTo { ctrl: [0.0, 0.0], to: [10.0, 10.0] });
+    // The smooth curve mirrors the last control point
+    assert_eq!(commands[5], PathCommand::QuadTo { ctrl: [20.0, 20.0], to: [30.0, 30.0] });
+    // Arcs are approximated by cubic curves
+    assert!(matches!(commands[6], PathCommand::CubicTo { .. }));
+    let Some(PathCommand::CubicTo { to, .. }) = commands.iter().rev().nth(1) else { panic!("{:?}", commands) };
+    assert!((to[0] - 40.0).abs() < 1e-4 && (to[1] - 30.0).abs() < 1e-4, "{:?}", to);
+    assert_eq!(commands.last(), Some(&PathCommand::Close));
+
+    // The same path built in code
+    let built = Path::new().move_to([10.0, 20.0]).line_to([40.0, 20.0]).close();
+    assert_eq!(built, Path::parse_svg("M 10,20 L 40,20 Z").unwrap());
+    assert_eq!(built.translated([1.0, -1.0]), Path::parse_svg("M 11,19 L 41,19 Z").unwrap());
+}
+
+#[test]
+fn bad_paths_are_rejected() {
+    assert!(Path::parse_svg("M 10 10 L 20").is_err());
+    assert!(Path::new().line_to([1.0, 1.0]).check().unwrap_err().to_string().contains("does not follow a MoveTo"));
+    assert!(Path::new().move_to([f32::NAN, 0.0]).check().is_err());
+    assert!(StrokeStyle::new(0.0).check().is_err());
+    assert!(StrokeStyle::new(2.0).with_miter_limit(0.5).check().is_err());
+
+    // Packing checks `SvgPath` assets
+    let mut packer = ProjectPacker::new("Paths", "gyo_core tests");
+    packer.add_asset("logo.svgpath", AssetKind::SvgPath, b"M 0 0 L 10 banana");
+    let error = packer.validate().unwrap_err();
+    assert!(format!("{:#}", error).contains("Failed to read path logo.svgpath"), "{:#}", error);
+}
@@ -1,13 +1,15 @@
 use std::sync::Arc;
 use std::time::Instant; // 🌟 NEW: Time tracking
 use winit::application::ApplicationHandler;
-use winit::event::WindowEvent;
+use winit::event::{ElementState, KeyEvent, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
 use winit::window::{Window, WindowId};
 use hanga::HangaEngine;
+use hanga::watch::{ProjectSource, ProjectWatcher};
 use hanga_traits::Runtime;
-use rand::Rng;
 use gyo_core::{GyoshoFile, Manifest, AssetEntry, AssetKind};
+use gyo_core::particles::{Emitter, Forces};
 use std::io::Cursor;
 
 // --- DUMMY FILE GENERATOR (UNCHANGED) ---
@@ -23,7 +25,13 @@ fn create_dummy_gyo() -> anyhow::Result<Vec<u8>> {
         @location(1) size: vec2<f32>,
         @location(2) uv_rect: vec4<f32>,
         @location(3) color: vec4<f32>,
+        @location(4) pivot: vec2<f32>,
+        @location(5) transform: vec4<f32>,
     };
+    struct Camera { view_proj: mat4x4<f32> };
+    @group(0) @binding(1) var<uniform> camera: Camera;
+    @group(2) @binding(0) var atlas: texture_2d<f32>;
+    @group(2) @binding(1) var atlas_sampler: sampler;
     @vertex
     fn vs_main(@builtin(vertex_index) in_vertex_index: u32, instance: InstanceInput) -> VertexOutput {
         var corners = array<vec2<f32>, 6>(
@@ -31,22 +39,21 @@ fn create_dummy_gyo() -> anyhow::Result<Vec<u8>> {
             vec2(0.0, 0.0), vec2(1.0, 1.0), vec2(1.0, 0.0)
         );
         let local_pos = corners[in_vertex_index];
-        let world_x = instance.pos.x + (local_pos.x * instance.size.x);
-        let world_y = instance.pos.y + (local_pos.y * instance.size.y);
+        let transform = mat2x2<f32>(instance.transform.xy, instance.transform.zw);
+        let world = instance.pos.xy + transform * ((local_pos - instance.pivot) * instance.size);
         
-        // Normalize to NDC
-        let ndc_x = (world_x / 1280.0) * 2.0 - 1.0;
-        let ndc_y = (world_y / 720.0) * -2.0 + 1.0;
+        // Normalize to NDC through the camera
+        let ndc = camera.view_proj * vec4<f32>(world, 0.0, 1.0);
         
         var out: VertexOutput;
-        out.clip_position = vec4<f32>(ndc_x, ndc_y, 0.0, 1.0);
-        out.uv = local_pos; 
+        out.clip_position = vec4<f32>(ndc.xy, clamp(instance.pos.z, 0.0, 1.0), 1.0);
+        out.uv = instance.uv_rect.xy + local_pos * instance.uv_rect.zw;
         out.color = instance.color;
         return out;
     }
     @fragment
     fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-        return in.color;
+        return textureSample(atlas, atlas_sampler, in.uv) * in.color;
     }
     "#;
 
@@ -63,6 +70,13 @@ fn create_dummy_gyo() -> anyhow::Result<Vec<u8>> {
         timestamp: 0,
         assets,
         compute_kernels: vec![],
+        variants: vec![],
+        pipelines: vec![],
+        passes: vec![],
+        effects: vec![],
+        background: Default::default(),
+        // 50,000 drops simulated on the GPU (The wind is set every frame)
+        particles: vec![rain()],
     };
 
     let mut buffer = Cursor::new(Vec::new());
@@ -72,20 +86,28 @@ fn create_dummy_gyo() -> anyhow::Result<Vec<u8>> {
 
 // --- SIMULATION ---
 
-struct RainDrop {
-    x: f32, 
-    y: f32, 
-    speed: f32, 
-    scale: f32,
-    // Store original X to allow wrapping without visible seams
-    epoch: u32, 
+fn rain() -> Emitter {
+    Emitter::new("rain", 50_000)
+        .with_rate(50_000.0 / 0.8)
+        .with_area([-200.0, -50.0, 1680.0, 0.0]) // Wider spawn area for wind
+        .with_velocity([0.0, 900.0], [0.0, 1500.0]) // Faster!
+        .with_lifetime(0.8, 0.8)
+        .with_size([2.0, 25.0])
+        .with_scale(0.5, 1.2)
+        .with_color([0.6, 0.8, 1.0, 0.4], [0.6, 0.8, 1.0, 0.4])
+        .with_align(true) // Slant the rain along its velocity
+        .with_forces(Forces::default().with_wind([0.0, 1200.0], 1.0))
 }
 
 struct HokusaiApp {
     window: Option<Arc<Window>>,
     engine: Option<HangaEngine>,
-    drops: Vec<RainDrop>,
     start_time: Instant, // 🌟 NEW: The Clock
+    // Screenshots being written to disk (Press P)
+    pending_screenshots: Vec<std::thread::JoinHandle<anyhow::Result<()>>>,
+    // Optional project to hot reload (First argument: A .gyo file or project directory)
+    source: Option<ProjectSource>,
+    watcher: Option<ProjectWatcher>,
 }
 
 impl ApplicationHandler for HokusaiApp {
@@ -98,22 +120,17 @@ impl ApplicationHandler for HokusaiApp {
             let window = Arc::new(event_loop.create_window(window_attributes).expect("Failed to create window"));
             self.window = Some(window.clone());
 
-            let gyo_bytes = create_dummy_gyo().expect("Failed to create GYO");
+            let gyo_bytes = match &self.source {
+                Some(source) => {
+                    self.watcher = ProjectWatcher::new(source.clone())
+                        .inspect_err(|e| eprintln!("Hot reload disabled: {:?}", e))
+                        .ok();
+                    source.read().expect("Failed to read project")
+                }
+                None => create_dummy_gyo().expect("Failed to create GYO"),
+            };
             let engine = pollster::block_on(HangaEngine::new(window.clone(), &gyo_bytes))
                 .expect("Failed to initialize engine");
-            
-            // Generate Drops
-            let mut rng = rand::rng(); 
-            self.drops.reserve(50_000);
-            for _ in 0..50_000 {
-                self.drops.push(RainDrop {
-                    x: rng.random_range(-200.0..1480.0), // Wider spawn area for wind
-                    y: rng.random_range(0.0..720.0),
-                    speed: rng.random_range(15.0..25.0), // Faster!
-                    scale: rng.random_range(0.5..1.2),
-                    epoch: 0,
-                });
-            }
             self.engine = Some(engine);
         }
     }
@@ -127,42 +144,42 @@ impl ApplicationHandler for HokusaiApp {
                     if let Some(window) = self.window.as_ref() { window.request_redraw(); }
                 }
             }
+            WindowEvent::KeyboardInput {
+                event: KeyEvent { physical_key: PhysicalKey::Code(KeyCode::KeyP), state: ElementState::Pressed, repeat: false, .. },
+                ..
+            } => {
+                if let Some(engine) = self.engine.as_mut() {
+                    let path = format!("hokusai-{}.png", self.start_time.elapsed().as_millis());
+                    println!("📸 Saving screenshot to {}", path);
+                    self.pending_screenshots.push(engine.request_screenshot().save_png_in_background(path));
+                }
+            }
             WindowEvent::RedrawRequested => {
+                // Report screenshots that finished writing
+                for handle in extract_finished(&mut self.pending_screenshots) {
+                    match handle.join() {
+                        Ok(Ok(())) => println!("📸 Screenshot saved"),
+                        Ok(Err(e)) => eprintln!("Screenshot failed: {:?}", e),
+                        Err(_) => eprintln!("Screenshot thread panicked"),
+                    }
+                }
+
                 if let Some(engine) = self.engine.as_mut() {
+                    // 🌟 0. HOT RELOAD (Errors are shown in the window)
+                    match self.watcher.as_ref().and_then(|watcher| watcher.poll()) {
+                        Some(Ok(bytes)) => { let _ = engine.reload_project(&bytes); }
+                        Some(Err(e)) => { engine.show_error(e); }
+                        None => {}
+                    }
+
                     // 🌟 1. CALCULATE WIND
                     let elapsed = self.start_time.elapsed().as_secs_f32();
-                    // Sine wave: oscillating left and right over 5 seconds
-                    let wind_force = (elapsed * 2.0).sin() * 5.0; 
-                    
-                    // 🌟 2. UPDATE PHYSICS
-                    for drop in &mut self.drops {
-                        drop.y += drop.speed;
-                        drop.x += wind_force * drop.scale; // Parallax wind!
-
-                        // Wrapping Logic
-                        if drop.y > 720.0 {
-                            drop.y = -50.0;
-                            // Randomize X slightly on respawn to break patterns
-                            drop.x = rand::rng().random_range(-200.0..1480.0);
-                        }
-                    }
+                    // Sine wave: oscillating left and right
+                    let wind_force = (elapsed * 2.0).sin() * 300.0;
 
-                    // 🌟 3. BUILD BATCH
-                    let batch = engine.get_batch();
-                    batch.clear();
-                    
-                    for drop in &self.drops {
-                        // Slant the rain based on wind (simple skew effect)
-                        let slant = wind_force * 2.0;
-                        
-                        batch.push(hanga::pipeline_2d::InstanceRaw {
-                            pos: [drop.x, drop.y, 0.0],
-                            // We stretch the width slightly when it moves fast
-                            size: [2.0 * drop.scale, 25.0 * drop.scale], 
-                            uv_rect: [0.0, 0.0, 1.0, 1.0],
-                            // Color varies slightly by speed (faster = lighter)
-                            color: [0.6, 0.7 + (drop.speed/100.0), 1.0, 0.4], 
-                        });
+                    // 🌟 2. UPDATE PHYSICS (On the GPU: Only the wind changes)
+                    if let Some(rain) = engine.emitter_mut("rain") {
+                        rain.forces.wind = [wind_force, 1200.0];
                     }
 
                     engine.prepare_frame();
@@ -175,6 +192,12 @@ impl ApplicationHandler for HokusaiApp {
     }
 }
 
+fn extract_finished<T>(handles: &mut Vec<std::thread::JoinHandle<T>>) -> Vec<std::thread::JoinHandle<T>> {
+    let (finished, pending) = handles.drain(..).partition(|h| h.is_finished());
+    *handles = pending;
+    finished
+}
+
 fn main() -> anyhow::Result<()> {
     env_logger::init();
     let event_loop = EventLoop::new()?;
@@ -183,8 +206,10 @@ fn main() -> anyhow::Result<()> {
     let mut app = HokusaiApp { 
         window: None, 
         engine: None, 
-        drops: Vec::new(),
-        start_time: Instant::now() // Start the clock
+        start_time: Instant::now(), // Start the clock
+        pending_screenshots: Vec::new(),
+        source: std::env::args().nth(1).map(ProjectSource::new),
+        watcher: None,
     };
     
     event_loop.run_app(&mut app)?;
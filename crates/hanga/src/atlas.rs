@@ -0,0 +1,48 @@
+//! Project textures sprite batches sample (See `gyo_core::shader::ATLAS_GROUP`).
+//!
+//! Any `TexturePng` asset can be an atlas: Sprites pick their region of it
+//! with `InstanceRaw::uv_rect`. Batches without an atlas sample a white
+//! texel, so their sprites keep the flat instance colour.
+
+use crate::channels;
+use gyo_core::texture::Texture;
+
+/// An uploaded atlas and its sampler.
+pub(crate) struct Atlas {
+    view: wgpu::TextureView,
+    sampler: wgpu::Sampler,
+}
+
+impl Atlas {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, label: &str, texture: &Texture) -> Self {
+        let texture = channels::upload(device, queue, label, texture, wgpu::TextureFormat::Rgba8UnormSrgb);
+        Self {
+            view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("Atlas Sampler"),
+                // Regions at the edge of the atlas do not bleed into the other side
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// The atlas of batches without one: A single white texel
+    pub fn blank(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        Self::new(device, queue, "Blank Atlas", &Texture::new(1, 1, vec![255; 4]))
+    }
+
+    /// What to bind at `binding` of the atlas group
+    pub fn resource(&self, binding: u32) -> wgpu::BindingResource<'_> {
+        if binding == 1 {
+            wgpu::BindingResource::Sampler(&self.sampler)
+        } else {
+            wgpu::BindingResource::TextureView(&self.view)
+        }
+    }
+}
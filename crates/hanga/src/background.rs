@@ -0,0 +1,149 @@
+//! The layer sprite scenes draw over (See `gyo_core::background`).
+
+use crate::bindings::ShaderBindings;
+use crate::loader::LoadedProject;
+use crate::pipeline_state;
+use crate::HangaEngine;
+use gyo_core::background::Background;
+use gyo_core::pipeline::{CompareFunction, DepthState};
+use gyo_core::PipelineState;
+use wgpu::util::DeviceExt;
+
+pub(crate) struct BackgroundLayer {
+    /// What the frame is cleared to
+    clear: wgpu::Color,
+    /// Drawn over the cleared frame, if anything is
+    draw: Option<(wgpu::RenderPipeline, BackgroundBindings)>,
+}
+
+/// What a background pipeline draws with.
+enum BackgroundBindings {
+    /// The bind group of a pipeline in `background.wgsl`
+    BuiltIn(wgpu::BindGroup),
+    /// The bindings of a `Background::Shader`
+    Shader(Box<ShaderBindings>),
+}
+
+impl BackgroundLayer {
+    /// The background `project` declares, drawn in `format`. With `depth`,
+    /// it is drawn in a pass with a depth buffer, which it leaves alone
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        project: &LoadedProject,
+        uniform_buffer: &wgpu::Buffer,
+        depth: bool,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> anyhow::Result<Self> {
+        let depth = depth.then_some(DepthState { write: false, compare: CompareFunction::Always });
+        let color = |[r, g, b, a]: [f32; 4]| wgpu::Color { r: r as f64, g: g as f64, b: b as f64, a: a as f64 };
+        let built_in = |entry_point, binding, buffer: &wgpu::Buffer| {
+            let pipeline = Self::create_built_in_pipeline(device, format, entry_point, depth, cache);
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Background Bind Group"),
+                layout: &pipeline.get_bind_group_layout(0),
+                entries: &[wgpu::BindGroupEntry { binding, resource: buffer.as_entire_binding() }],
+            });
+            Some((pipeline, BackgroundBindings::BuiltIn(bind_group)))
+        };
+
+        let (clear, draw) = match &project.manifest.background {
+            Background::None => (wgpu::Color::BLACK, None),
+            Background::Color(rgba) => (color(*rgba), None),
+            Background::Sky => (wgpu::Color::BLACK, built_in("fs_sky", 0, uniform_buffer)),
+            Background::Gradient { bottom, top } => {
+                let colors = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Background Gradient"),
+                    contents: bytemuck::cast_slice(&[*bottom, *top]),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+                (wgpu::Color::BLACK, built_in("fs_gradient", 1, &colors))
+            }
+            Background::Shader { asset_id } => {
+                let shader = project.background_shader.as_ref().expect("The loader validates the background shader");
+                let bindings = ShaderBindings::new(device, queue, shader, &project.textures, uniform_buffer, None)?;
+                let state = PipelineState { depth, ..PipelineState::new(asset_id.as_str()) };
+                let pipeline = HangaEngine::create_image_pipeline(device, format, shader, &state, bindings.layout(), cache);
+                (wgpu::Color::BLACK, Some((pipeline, BackgroundBindings::Shader(Box::new(bindings)))))
+            }
+        };
+        Ok(Self { clear, draw })
+    }
+
+    pub fn clear_color(&self) -> wgpu::Color {
+        self.clear
+    }
+
+    /// Samples the textures of `project` from now on (If the background is
+    /// a shader), without rebuilding its pipeline
+    pub fn set_textures(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        project: &LoadedProject,
+        uniform_buffer: &wgpu::Buffer,
+    ) -> anyhow::Result<()> {
+        let (Some((_, BackgroundBindings::Shader(bindings))), Some(shader)) = (&mut self.draw, &project.background_shader) else {
+            return Ok(());
+        };
+        bindings.set_textures(device, queue, shader, &project.textures, uniform_buffer, None)
+    }
+
+    pub fn draw<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>) {
+        let Some((pipeline, bindings)) = &self.draw else { return };
+        rpass.set_pipeline(pipeline);
+        match bindings {
+            BackgroundBindings::BuiltIn(bind_group) => rpass.set_bind_group(0, bind_group, &[]),
+            BackgroundBindings::Shader(bindings) => bindings.set(rpass),
+        }
+        rpass.draw(0..3, 0..1); // Draw 3 vertices (1 triangle)
+    }
+
+    // Helper for `background.wgsl` (No Vertex Buffers, layout from the entry point)
+    fn create_built_in_pipeline(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        entry_point: &str,
+        depth: Option<DepthState>,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> wgpu::RenderPipeline {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("background.wgsl"));
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Background Pipeline"),
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[], // No buffers needed!
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point,
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE), // Background overwrites everything
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: pipeline_state::depth_stencil_state(depth),
+            multisample: wgpu::MultisampleState {
+                count: 1, mask: !0, alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache,
+        })
+    }
+}
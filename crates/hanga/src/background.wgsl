@@ -1,4 +1,23 @@
-// crates/hanga/src/sky.wgsl
+// crates/hanga/src/background.wgsl
+// Built-in backgrounds: Full-screen triangles drawn before the sprites.
+
+// Same layout as the prelude's `Uniforms`
+struct Uniforms {
+    cursor: vec2<f32>,
+    mouse_down: u32,
+    mouse_press: vec2<f32>,
+    mouse_release: vec2<f32>,
+    resolution: vec2<f32>,
+    time: f32,
+};
+
+struct Gradient {
+    bottom: vec4<f32>,
+    top: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> u: Uniforms;
+@group(0) @binding(1) var<uniform> gradient: Gradient;
 
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
@@ -15,11 +34,19 @@ fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
     );
     var out: VertexOutput;
     out.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
-    // Invert Y for WGPU texture coordinates if needed, but for sky it matters little
-    out.uv = uv;
+    out.uv = uv; // (0, 0) at the bottom-left
     return out;
 }
 
+// --- GRADIENT ---
+
+@fragment
+fn fs_gradient(in: VertexOutput) -> @location(0) vec4<f32> {
+    return mix(gradient.bottom, gradient.top, in.uv.y);
+}
+
+// --- SKY ---
+
 // --- FBM NOISE FUNCTIONS ---
 // A simple hash function
 fn hash(p: vec2<f32>) -> f32 {
@@ -54,14 +81,13 @@ fn fbm(p: vec2<f32>) -> f32 {
 }
 
 @fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+fn fs_sky(in: VertexOutput) -> @location(0) vec4<f32> {
     // Basic sky gradient
     let sky_blue = vec3<f32>(0.0, 0.05, 0.2); // Deep blue
     let clouds = vec3<f32>(0.8, 0.8, 0.9);    // White-ish
     
-    // Calculate noise based on UV
-    // TODO: Pass 'time' uniform to animate this later!
-    let n = fbm(in.uv * 3.0);
+    // Calculate noise based on UV, drifting with time
+    let n = fbm(in.uv * 3.0 + vec2<f32>(u.time * 0.05, 0.0));
     
     // Mix sky and clouds
     let color = mix(sky_blue, clouds, n);
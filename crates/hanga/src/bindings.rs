@@ -0,0 +1,192 @@
+//! Explicit pipeline layouts and bind groups, built from shader reflection.
+//!
+//! Every bind group the shader declares gets a layout with exactly the
+//! bindings its entry points use (See `gyo_core::reflect`), filled with what
+//! the runtime provides: The `Uniform` buffer, the project's channels and,
+//! for sprite shaders, the camera and the atlas of the batch being drawn.
+
+use crate::atlas::Atlas;
+use crate::channels::Channels;
+use gyo_core::reflect::{self, ShaderResource};
+use gyo_core::shader::{ATLAS_GROUP, CAMERA_BINDING, CHANNEL_GROUP, UNIFORM_GROUP};
+use gyo_core::texture::Texture;
+use gyo_core::validate::ValidatedShader;
+
+pub(crate) struct ShaderBindings {
+    layout: ShaderLayout,
+    /// One per `@group`, in order (With the blank atlas, if the shader
+    /// samples one)
+    pub bind_groups: Vec<wgpu::BindGroup>,
+    /// The atlas group with each project texture, by id, and whether the
+    /// texture is opaque (Empty unless the shader samples an atlas)
+    atlases: Vec<(String, wgpu::BindGroup, bool)>,
+}
+
+impl ShaderBindings {
+    /// Bindings with the project's textures as channels and atlases, and
+    /// the camera of sprite scenes
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        shader: &ValidatedShader,
+        textures: &[(String, Texture)],
+        uniform_buffer: &wgpu::Buffer,
+        camera_buffer: Option<&wgpu::Buffer>,
+    ) -> anyhow::Result<Self> {
+        let mut bindings = Self { layout: ShaderLayout::new(device, shader), bind_groups: Vec::new(), atlases: Vec::new() };
+        bindings.set_textures(device, queue, shader, textures, uniform_buffer, camera_buffer)?;
+        Ok(bindings)
+    }
+
+    /// What pipelines drawing with these bindings are laid out with
+    pub fn layout(&self) -> &wgpu::PipelineLayout {
+        &self.layout.layout
+    }
+
+    /// Uploads `textures` and fills the same layouts with them again, so the
+    /// pipelines built for these bindings sample the new ones
+    pub fn set_textures(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        shader: &ValidatedShader,
+        textures: &[(String, Texture)],
+        uniform_buffer: &wgpu::Buffer,
+        camera_buffer: Option<&wgpu::Buffer>,
+    ) -> anyhow::Result<()> {
+        let layout = &self.layout;
+        let channels = layout.uses_channels().then(|| Channels::new(device, queue, shader, textures));
+        let blank = layout.uses_atlas().then(|| Atlas::blank(device, queue));
+        let bind_groups = layout.bind_groups(device, [Some(uniform_buffer), camera_buffer], channels.as_ref(), blank.as_ref())?;
+        let atlases = match blank {
+            Some(_) => textures.iter()
+                .map(|(id, texture)| {
+                    let atlas = Atlas::new(device, queue, id, texture);
+                    let opaque = texture.data.chunks_exact(4).all(|texel| texel[3] == 255);
+                    Ok((id.clone(), layout.atlas_bind_group(device, &atlas)?, opaque))
+                })
+                .collect::<anyhow::Result<_>>()?,
+            None => Vec::new(),
+        };
+        self.bind_groups = bind_groups;
+        self.atlases = atlases;
+        Ok(())
+    }
+
+    pub fn set<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>) {
+        for (group, bind_group) in self.bind_groups.iter().enumerate() {
+            rpass.set_bind_group(group as u32, bind_group, &[]);
+        }
+    }
+
+    /// True if `atlas` can be bound with [`ShaderBindings::set_atlas`]
+    pub fn has_atlas(&self, atlas: &str) -> bool {
+        self.atlases.iter().any(|(id, ..)| id == atlas)
+    }
+
+    /// False if the texture called `atlas` has translucent texels
+    pub fn atlas_is_opaque(&self, atlas: &str) -> bool {
+        self.atlases.iter().all(|(id, _, opaque)| id != atlas || *opaque)
+    }
+
+    /// Binds the texture called `atlas` to the atlas group, or the blank atlas
+    /// for `None` and textures the project no longer has. Does nothing if the
+    /// shader samples no atlas.
+    pub fn set_atlas<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>, atlas: Option<&str>) {
+        let Some(blank) = self.bind_groups.get(ATLAS_GROUP as usize) else { return };
+        let bind_group = atlas
+            .and_then(|atlas| self.atlases.iter().find(|(id, ..)| id == atlas))
+            .map_or(blank, |(_, bind_group, _)| bind_group);
+        rpass.set_bind_group(ATLAS_GROUP, bind_group, &[]);
+    }
+}
+
+/// The layouts of a shader, for filling bind groups more than once (Render
+/// graph passes rebind their channels when targets are resized).
+pub(crate) struct ShaderLayout {
+    pub layout: wgpu::PipelineLayout,
+    group_layouts: Vec<wgpu::BindGroupLayout>,
+    resources: Vec<ShaderResource>,
+}
+
+impl ShaderLayout {
+    /// Layouts from what the shader uses
+    pub fn new(device: &wgpu::Device, shader: &ValidatedShader) -> Self {
+        let resources = reflect::resources(shader);
+        let group_layouts: Vec<wgpu::BindGroupLayout> = reflect::bind_group_entries(&resources).iter()
+            .enumerate()
+            .map(|(group, entries)| device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some(&format!("Shader Group {} Layout", group)),
+                entries,
+            }))
+            .collect();
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Shader Pipeline Layout"),
+            bind_group_layouts: &group_layouts.iter().collect::<Vec<_>>(),
+            push_constant_ranges: &[],
+        });
+        Self { layout, group_layouts, resources }
+    }
+
+    pub fn uses_channels(&self) -> bool {
+        self.resources.iter().any(|r| r.group == CHANNEL_GROUP)
+    }
+
+    pub fn uses_atlas(&self) -> bool {
+        self.resources.iter().any(|r| r.group == ATLAS_GROUP)
+    }
+
+    /// Fills the layouts with the runtime's resources. `buffers` are the
+    /// `Uniform` and camera buffers
+    pub fn bind_groups(
+        &self,
+        device: &wgpu::Device,
+        buffers: [Option<&wgpu::Buffer>; 2],
+        channels: Option<&Channels>,
+        atlas: Option<&Atlas>,
+    ) -> anyhow::Result<Vec<wgpu::BindGroup>> {
+        (0..self.group_layouts.len() as u32)
+            .map(|group| self.bind_group(device, group, &|r| resource(r, buffers, channels, atlas)))
+            .collect()
+    }
+
+    /// Fills the atlas group with `atlas`
+    pub fn atlas_bind_group(&self, device: &wgpu::Device, atlas: &Atlas) -> anyhow::Result<wgpu::BindGroup> {
+        self.bind_group(device, ATLAS_GROUP, &|r| resource(r, [None, None], None, Some(atlas)))
+    }
+
+    fn bind_group<'a>(
+        &self,
+        device: &wgpu::Device,
+        group: u32,
+        resource: &dyn Fn(&ShaderResource) -> anyhow::Result<wgpu::BindingResource<'a>>,
+    ) -> anyhow::Result<wgpu::BindGroup> {
+        let entries = self.resources.iter()
+            .filter(|r| r.group == group)
+            .map(|r| Ok(wgpu::BindGroupEntry { binding: r.binding, resource: resource(r)? }))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&format!("Shader Group {} Bind Group", group)),
+            layout: &self.group_layouts[group as usize],
+            entries: &entries,
+        }))
+    }
+}
+
+fn resource<'a>(
+    resource: &ShaderResource,
+    [uniform_buffer, camera_buffer]: [Option<&'a wgpu::Buffer>; 2],
+    channels: Option<&'a Channels>,
+    atlas: Option<&'a Atlas>,
+) -> anyhow::Result<wgpu::BindingResource<'a>> {
+    let bound = match (resource.group, resource.binding) {
+        (UNIFORM_GROUP, 0) => uniform_buffer.map(wgpu::Buffer::as_entire_binding),
+        (UNIFORM_GROUP, CAMERA_BINDING) => camera_buffer.map(wgpu::Buffer::as_entire_binding),
+        (CHANNEL_GROUP, binding) => channels.map(|channels| channels.resource(binding)),
+        (ATLAS_GROUP, binding) => atlas.map(|atlas| atlas.resource(binding)),
+        _ => None,
+    };
+    bound.ok_or_else(|| anyhow::anyhow!(
+        "Nothing to bind to `{}` (@group({}) @binding({}))", resource.name, resource.group, resource.binding,
+    ))
+}
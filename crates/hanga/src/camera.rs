@@ -0,0 +1,209 @@
+//! The 2D camera of sprite scenes (See `gyo_core::shader::CAMERA_BINDING`).
+//!
+//! Sprites are placed on a virtual canvas (`1280` x `720` units by default)
+//! which the camera fits to the window with a [`ScalePolicy`], then pans,
+//! zooms and turns. Three spaces are involved:
+//!
+//! - World: Sprite positions, `y` pointing down. At rest, the canvas spans
+//!   `0..virtual_size`
+//! - NDC: `-1..1` across the viewport, `y` pointing up
+//! - Screen: Logical pixels of the window, origin at the top-left. Physical
+//!   pixels (What winit reports) are logical ones times the scale factor
+
+use crate::viewport::Viewport;
+
+/// How the virtual canvas fits windows of another aspect ratio.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ScalePolicy {
+    /// Scaled uniformly to fit, bars fill the rest of the window
+    #[default]
+    Letterbox,
+    /// Scaled to the window, distorting it
+    Stretch,
+    /// Scaled uniformly to fit, showing more of the world on the long side
+    Expand,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera2D {
+    /// Size of the canvas in world units
+    pub virtual_size: [f32; 2],
+    pub policy: ScalePolicy,
+    /// The world point at the centre of the view
+    pub position: [f32; 2],
+    /// Above 1 magnifies
+    pub zoom: f32,
+    /// Clockwise turn of the view in radians (The world turns the other way)
+    pub rotation: f32,
+}
+
+impl Default for Camera2D {
+    fn default() -> Self {
+        Self::new([1280.0, 720.0])
+    }
+}
+
+impl Camera2D {
+    /// A camera looking at the whole of a `virtual_size` canvas
+    pub fn new(virtual_size: [f32; 2]) -> Self {
+        Self {
+            virtual_size,
+            policy: ScalePolicy::default(),
+            position: [virtual_size[0] / 2.0, virtual_size[1] / 2.0],
+            zoom: 1.0,
+            rotation: 0.0,
+        }
+    }
+
+    pub fn with_policy(mut self, policy: ScalePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_position(mut self, position: [f32; 2]) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn with_zoom(mut self, zoom: f32) -> Self {
+        self.zoom = zoom;
+        self
+    }
+
+    pub fn with_rotation(mut self, radians: f32) -> Self {
+        self.rotation = radians;
+        self
+    }
+
+    /// Moves the view by `delta` world units
+    pub fn pan(&mut self, delta: [f32; 2]) {
+        self.position = [self.position[0] + delta[0], self.position[1] + delta[1]];
+    }
+
+    /// The camera fitted to a surface of `size` physical pixels
+    pub fn view(&self, size: [u32; 2], scale_factor: f32) -> CameraView {
+        let [width, height] = size.map(|s| s.max(1) as f32);
+        let [w, h] = self.virtual_size;
+        let fit = (width / w).min(height / h);
+        let full = Viewport { x: 0.0, y: 0.0, width, height, min_depth: 0.0, max_depth: 1.0 };
+
+        // 1. How much of the world (At zoom 1) the viewport shows
+        let (viewport, visible) = match self.policy {
+            ScalePolicy::Stretch => (full, [w, h]),
+            ScalePolicy::Letterbox => {
+                // Whole pixels, so the scissor rect matches the viewport
+                let (fit_width, fit_height) = ((w * fit).round().max(1.0), (h * fit).round().max(1.0));
+                let viewport = Viewport {
+                    x: ((width - fit_width) / 2.0).floor(),
+                    y: ((height - fit_height) / 2.0).floor(),
+                    width: fit_width,
+                    height: fit_height,
+                    ..full
+                };
+                (viewport, [w, h])
+            }
+            ScalePolicy::Expand => (full, [width / fit, height / fit]),
+        };
+
+        // 2. World to NDC: Pan, turn, zoom and flip `y`
+        let (sin, cos) = self.rotation.sin_cos();
+        let kx = 2.0 * self.zoom / visible[0];
+        let ky = -2.0 * self.zoom / visible[1];
+        let x_axis = [kx * cos, -ky * sin];
+        let y_axis = [kx * sin, ky * cos];
+        let [px, py] = self.position;
+        let view_proj = [
+            [x_axis[0], x_axis[1], 0.0, 0.0],
+            [y_axis[0], y_axis[1], 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [-(x_axis[0] * px + y_axis[0] * py), -(x_axis[1] * px + y_axis[1] * py), 0.0, 1.0],
+        ];
+
+        CameraView { camera: *self, visible, viewport, scale_factor, view_proj }
+    }
+}
+
+/// A camera fitted to a surface: What the sprite shader gets, and the
+/// conversions between its spaces.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraView {
+    camera: Camera2D,
+    /// World units across the viewport at zoom 1
+    visible: [f32; 2],
+    /// In physical pixels
+    viewport: Viewport,
+    scale_factor: f32,
+    view_proj: [[f32; 4]; 4],
+}
+
+impl CameraView {
+    /// World to clip space, by columns (The `camera.view_proj` of shaders)
+    pub fn view_proj(&self) -> [[f32; 4]; 4] {
+        self.view_proj
+    }
+
+    /// Where the canvas is drawn, in physical pixels
+    pub fn viewport(&self) -> &Viewport {
+        &self.viewport
+    }
+
+    /// World units across a physical pixel (The larger of the two axes)
+    pub fn pixel_size(&self) -> f32 {
+        let across = [self.visible[0] / self.viewport.width, self.visible[1] / self.viewport.height];
+        across[0].max(across[1]) / self.camera.zoom
+    }
+
+    pub fn world_to_ndc(&self, world: [f32; 2]) -> [f32; 2] {
+        let m = &self.view_proj;
+        [
+            m[0][0] * world[0] + m[1][0] * world[1] + m[3][0],
+            m[0][1] * world[0] + m[1][1] * world[1] + m[3][1],
+        ]
+    }
+
+    pub fn ndc_to_world(&self, ndc: [f32; 2]) -> [f32; 2] {
+        let Camera2D { position, zoom, rotation, .. } = self.camera;
+        let (sin, cos) = rotation.sin_cos();
+        let view = [ndc[0] * self.visible[0] / (2.0 * zoom), -ndc[1] * self.visible[1] / (2.0 * zoom)];
+        [
+            position[0] + cos * view[0] - sin * view[1],
+            position[1] + sin * view[0] + cos * view[1],
+        ]
+    }
+
+    /// `screen` in logical pixels
+    pub fn screen_to_ndc(&self, screen: [f32; 2]) -> [f32; 2] {
+        let Viewport { x, y, width, height, .. } = self.viewport;
+        let physical = [screen[0] * self.scale_factor, screen[1] * self.scale_factor];
+        [(physical[0] - x) / width * 2.0 - 1.0, 1.0 - (physical[1] - y) / height * 2.0]
+    }
+
+    /// In logical pixels
+    pub fn ndc_to_screen(&self, ndc: [f32; 2]) -> [f32; 2] {
+        let Viewport { x, y, width, height, .. } = self.viewport;
+        let physical = [x + (ndc[0] + 1.0) / 2.0 * width, y + (1.0 - ndc[1]) / 2.0 * height];
+        [physical[0] / self.scale_factor, physical[1] / self.scale_factor]
+    }
+
+    /// `screen` in logical pixels
+    pub fn screen_to_world(&self, screen: [f32; 2]) -> [f32; 2] {
+        self.ndc_to_world(self.screen_to_ndc(screen))
+    }
+
+    /// In logical pixels
+    pub fn world_to_screen(&self, world: [f32; 2]) -> [f32; 2] {
+        self.ndc_to_screen(self.world_to_ndc(world))
+    }
+
+    /// Logical pixels of a `physical` position, like winit's cursor
+    pub fn physical_to_screen(&self, physical: [f32; 2]) -> [f32; 2] {
+        [physical[0] / self.scale_factor, physical[1] / self.scale_factor]
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        bytemuck::bytes_of(&self.view_proj)
+    }
+}
+
+// Must match the camera block of sprite shaders
+const _: () = assert!(std::mem::size_of::<[[f32; 4]; 4]>() == gyo_core::shader::CAMERA_SIZE as usize);
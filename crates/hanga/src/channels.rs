@@ -0,0 +1,102 @@
+//! Project textures bound to shaders as `iChannel0..3`.
+//!
+//! Channel N is the Nth `TexturePng` asset of the project, bound at
+//! `@group(1)` with its texture at binding 2N and its sampler at 2N + 1.
+//!
+//! GLSL shaders get their channels flipped vertically, like Shadertoy's
+//! default `vflip`: `texture(iChannel0, uv)` shows the image upright with
+//! `uv` from the bottom-left. WGSL shaders flip in the `image()` helper instead.
+
+use gyo_core::shader::CHANNEL_COUNT;
+use gyo_core::texture::Texture;
+use gyo_core::validate::{ShaderLanguage, ValidatedShader};
+use wgpu::util::DeviceExt;
+
+/// The uploaded channel textures and their shared sampler.
+pub(crate) struct Channels {
+    views: Vec<wgpu::TextureView>,
+    sampler: wgpu::Sampler,
+}
+
+impl Channels {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        shader: &ValidatedShader,
+        textures: &[(String, Texture)],
+    ) -> Self {
+        // 1. Upload the channels (Missing ones are black)
+        let black = Texture::new(1, 1, vec![0, 0, 0, 255]);
+        let flip = shader.language == ShaderLanguage::Glsl;
+        let views = (0..CHANNEL_COUNT as usize)
+            .map(|i| {
+                let (label, texture) = textures.get(i)
+                    .map(|(id, texture)| (id.as_str(), texture))
+                    .unwrap_or(("Empty Channel", &black));
+                let texture = if flip { flip_rows(texture) } else { texture.clone() };
+                upload(device, queue, label, &texture, wgpu::TextureFormat::Rgba8UnormSrgb).create_view(&wgpu::TextureViewDescriptor::default())
+            })
+            .collect();
+
+        Self { views, sampler: sampler(device) }
+    }
+
+    /// Channels from views of textures already on the device, in order.
+    /// Missing ones are black.
+    pub fn from_views(device: &wgpu::Device, queue: &wgpu::Queue, mut views: Vec<wgpu::TextureView>) -> Self {
+        let black = Texture::new(1, 1, vec![0, 0, 0, 255]);
+        while views.len() < CHANNEL_COUNT as usize {
+            views.push(upload(device, queue, "Empty Channel", &black, wgpu::TextureFormat::Rgba8UnormSrgb).create_view(&wgpu::TextureViewDescriptor::default()));
+        }
+        Self { views, sampler: sampler(device) }
+    }
+
+    /// What to bind at `binding` of the channel group
+    pub fn resource(&self, binding: u32) -> wgpu::BindingResource<'_> {
+        if binding % 2 == 1 {
+            wgpu::BindingResource::Sampler(&self.sampler)
+        } else {
+            wgpu::BindingResource::TextureView(&self.views[(binding / 2) as usize % self.views.len()])
+        }
+    }
+}
+
+/// Shadertoy defaults: Linear filtering, repeating
+fn sampler(device: &wgpu::Device) -> wgpu::Sampler {
+    device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("Channel Sampler"),
+        address_mode_u: wgpu::AddressMode::Repeat,
+        address_mode_v: wgpu::AddressMode::Repeat,
+        address_mode_w: wgpu::AddressMode::Repeat,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        mipmap_filter: wgpu::FilterMode::Nearest,
+        ..Default::default()
+    })
+}
+
+fn flip_rows(texture: &Texture) -> Texture {
+    let row = texture.width as usize * 4;
+    let data = texture.data.chunks_exact(row).rev().flatten().copied().collect();
+    Texture::new(texture.width, texture.height, data)
+}
+
+/// Uploads a texture for sampling: `Rgba8UnormSrgb` for colours, `Rgba8Unorm`
+/// for data read as stored
+pub(crate) fn upload(device: &wgpu::Device, queue: &wgpu::Queue, label: &str, texture: &Texture, format: wgpu::TextureFormat) -> wgpu::Texture {
+    device.create_texture_with_data(
+        queue,
+        &wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d { width: texture.width, height: texture.height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        },
+        wgpu::util::TextureDataOrder::LayerMajor,
+        &texture.data,
+    )
+}
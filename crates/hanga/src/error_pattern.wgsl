@@ -0,0 +1,8 @@
+// Built-in image shader shown while a project has no working shader.
+// Slowly scrolling magenta stripes, hard to mistake for real output.
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    let stripe = fract((frag_coord.x + frag_coord.y + u.time * 40.0) / 48.0);
+    let magenta = vec3<f32>(0.55, 0.0, 0.45);
+    let dark = vec3<f32>(0.08, 0.0, 0.06);
+    return vec4<f32>(select(dark, magenta, stripe < 0.5), 1.0);
+}
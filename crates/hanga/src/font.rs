@@ -0,0 +1,117 @@
+//! 8x16 bitmap font for printable ASCII, used by the error overlay.
+//!
+//! Rasterized once from DejaVu Sans Mono at 14px (Bitstream Vera license).
+//! Each glyph is 16 rows, the most significant bit is the leftmost pixel.
+
+pub const GLYPH_WIDTH: u32 = 8;
+pub const GLYPH_HEIGHT: u32 = 16;
+
+const FIRST: u8 = b' ';
+const LAST: u8 = b'~';
+
+/// Returns the bitmap of `c`, or of `?` for characters outside printable ASCII.
+pub fn glyph(c: char) -> &'static [u8; 16] {
+    let index = match c {
+        ' '..='~' => c as u8,
+        _ => b'?',
+    };
+    &GLYPHS[(index - FIRST) as usize]
+}
+
+const GLYPHS: [[u8; 16]; (LAST - FIRST + 1) as usize] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x00, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x00, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00], // '!'
+    [0x00, 0x00, 0x00, 0x28, 0x28, 0x28, 0x28, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '"'
+    [0x00, 0x00, 0x00, 0x14, 0x14, 0x34, 0x7e, 0x2c, 0xfc, 0xfe, 0x58, 0x58, 0x00, 0x00, 0x00, 0x00], // '#'
+    [0x00, 0x00, 0x00, 0x10, 0x3c, 0x74, 0x50, 0x78, 0x1c, 0x16, 0x56, 0x7c, 0x10, 0x10, 0x00, 0x00], // '$'
+    [0x00, 0x00, 0x00, 0x20, 0xf0, 0x90, 0xf2, 0x3c, 0x6c, 0x1a, 0x12, 0x0e, 0x00, 0x00, 0x00, 0x00], // '%'
+    [0x00, 0x00, 0x00, 0x38, 0x60, 0x60, 0x20, 0x72, 0xda, 0xce, 0xc6, 0x7e, 0x00, 0x00, 0x00, 0x00], // '&'
+    [0x00, 0x00, 0x00, 0x10, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '\''
+    [0x00, 0x00, 0x00, 0x08, 0x18, 0x10, 0x10, 0x30, 0x30, 0x10, 0x10, 0x10, 0x08, 0x08, 0x00, 0x00], // '('
+    [0x00, 0x00, 0x00, 0x20, 0x10, 0x10, 0x18, 0x18, 0x18, 0x18, 0x18, 0x10, 0x30, 0x20, 0x00, 0x00], // ')'
+    [0x00, 0x00, 0x00, 0x10, 0x54, 0x38, 0x7c, 0x50, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '*'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x10, 0x18, 0xfe, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00], // '+'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x18, 0x30, 0x30, 0x00, 0x00], // ','
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x38, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x18, 0x00, 0x00, 0x00, 0x00], // '.'
+    [0x00, 0x00, 0x00, 0x04, 0x0c, 0x08, 0x18, 0x18, 0x10, 0x30, 0x20, 0x60, 0x40, 0x00, 0x00, 0x00], // '/'
+    [0x00, 0x00, 0x00, 0x38, 0x6c, 0x44, 0x46, 0x56, 0x46, 0x44, 0x64, 0x38, 0x00, 0x00, 0x00, 0x00], // '0'
+    [0x00, 0x00, 0x00, 0x38, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x7e, 0x00, 0x00, 0x00, 0x00], // '1'
+    [0x00, 0x00, 0x00, 0x78, 0x4c, 0x04, 0x04, 0x0c, 0x18, 0x30, 0x60, 0x7c, 0x00, 0x00, 0x00, 0x00], // '2'
+    [0x00, 0x00, 0x00, 0x78, 0x4c, 0x04, 0x0c, 0x38, 0x04, 0x04, 0x04, 0x7c, 0x00, 0x00, 0x00, 0x00], // '3'
+    [0x00, 0x00, 0x00, 0x0c, 0x1c, 0x3c, 0x2c, 0x6c, 0x4c, 0xfe, 0x0c, 0x0c, 0x00, 0x00, 0x00, 0x00], // '4'
+    [0x00, 0x00, 0x00, 0x7c, 0x60, 0x40, 0x78, 0x4c, 0x04, 0x04, 0x0c, 0x78, 0x00, 0x00, 0x00, 0x00], // '5'
+    [0x00, 0x00, 0x00, 0x3c, 0x60, 0x40, 0x58, 0x6c, 0x46, 0x46, 0x64, 0x3c, 0x00, 0x00, 0x00, 0x00], // '6'
+    [0x00, 0x00, 0x00, 0x7c, 0x0c, 0x0c, 0x08, 0x08, 0x18, 0x10, 0x30, 0x30, 0x00, 0x00, 0x00, 0x00], // '7'
+    [0x00, 0x00, 0x00, 0x38, 0x6c, 0x44, 0x6c, 0x38, 0x64, 0x46, 0x64, 0x7c, 0x00, 0x00, 0x00, 0x00], // '8'
+    [0x00, 0x00, 0x00, 0x38, 0x6c, 0x44, 0x46, 0x6e, 0x3e, 0x04, 0x0c, 0x78, 0x00, 0x00, 0x00, 0x00], // '9'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x10, 0x00, 0x00, 0x10, 0x18, 0x00, 0x00, 0x00, 0x00], // ':'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x10, 0x00, 0x00, 0x18, 0x18, 0x30, 0x30, 0x00, 0x00], // ';'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x1e, 0x70, 0xe0, 0x3c, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00], // '<'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x7e, 0x7e, 0x00, 0xfe, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '='
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x70, 0x1c, 0x0e, 0x38, 0xe0, 0x00, 0x00, 0x00, 0x00, 0x00], // '>'
+    [0x00, 0x00, 0x00, 0x38, 0x4c, 0x04, 0x0c, 0x18, 0x10, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00], // '?'
+    [0x00, 0x00, 0x00, 0x00, 0x3c, 0x42, 0xde, 0x96, 0xb2, 0xb2, 0xde, 0x40, 0x60, 0x1c, 0x00, 0x00], // '@'
+    [0x00, 0x00, 0x00, 0x18, 0x38, 0x38, 0x2c, 0x6c, 0x6c, 0x7c, 0xc6, 0xc6, 0x00, 0x00, 0x00, 0x00], // 'A'
+    [0x00, 0x00, 0x00, 0x78, 0x6c, 0x46, 0x4c, 0x7c, 0x46, 0x46, 0x46, 0x7c, 0x00, 0x00, 0x00, 0x00], // 'B'
+    [0x00, 0x00, 0x00, 0x3c, 0x64, 0x60, 0x40, 0x40, 0x40, 0x60, 0x60, 0x3c, 0x00, 0x00, 0x00, 0x00], // 'C'
+    [0x00, 0x00, 0x00, 0x78, 0x5c, 0x44, 0x46, 0x46, 0x46, 0x44, 0x4c, 0x78, 0x00, 0x00, 0x00, 0x00], // 'D'
+    [0x00, 0x00, 0x00, 0x7c, 0x60, 0x60, 0x60, 0x7c, 0x60, 0x60, 0x60, 0x7e, 0x00, 0x00, 0x00, 0x00], // 'E'
+    [0x00, 0x00, 0x00, 0x7e, 0x60, 0x60, 0x60, 0x7c, 0x60, 0x60, 0x60, 0x60, 0x00, 0x00, 0x00, 0x00], // 'F'
+    [0x00, 0x00, 0x00, 0x3c, 0x64, 0x40, 0x40, 0xcc, 0x4e, 0x46, 0x66, 0x3c, 0x00, 0x00, 0x00, 0x00], // 'G'
+    [0x00, 0x00, 0x00, 0x46, 0x46, 0x46, 0x46, 0x7e, 0x46, 0x46, 0x46, 0x46, 0x00, 0x00, 0x00, 0x00], // 'H'
+    [0x00, 0x00, 0x00, 0x7c, 0x18, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x7c, 0x00, 0x00, 0x00, 0x00], // 'I'
+    [0x00, 0x00, 0x00, 0x3c, 0x0c, 0x0c, 0x0c, 0x0c, 0x0c, 0x0c, 0x0c, 0x78, 0x00, 0x00, 0x00, 0x00], // 'J'
+    [0x00, 0x00, 0x00, 0x46, 0x4c, 0x58, 0x70, 0x70, 0x58, 0x4c, 0x44, 0x46, 0x00, 0x00, 0x00, 0x00], // 'K'
+    [0x00, 0x00, 0x00, 0x60, 0x60, 0x60, 0x60, 0x60, 0x60, 0x60, 0x60, 0x7e, 0x00, 0x00, 0x00, 0x00], // 'L'
+    [0x00, 0x00, 0x00, 0xc6, 0xee, 0xee, 0xee, 0xfe, 0xd6, 0xc6, 0xc6, 0xc6, 0x00, 0x00, 0x00, 0x00], // 'M'
+    [0x00, 0x00, 0x00, 0x66, 0x66, 0x66, 0x76, 0x56, 0x5e, 0x4e, 0x4e, 0x46, 0x00, 0x00, 0x00, 0x00], // 'N'
+    [0x00, 0x00, 0x00, 0x38, 0x6c, 0x46, 0x46, 0x46, 0x46, 0x46, 0x64, 0x3c, 0x00, 0x00, 0x00, 0x00], // 'O'
+    [0x00, 0x00, 0x00, 0x7c, 0x6e, 0x66, 0x66, 0x7c, 0x78, 0x60, 0x60, 0x60, 0x00, 0x00, 0x00, 0x00], // 'P'
+    [0x00, 0x00, 0x00, 0x38, 0x6c, 0x46, 0x46, 0x46, 0x46, 0x46, 0x64, 0x3c, 0x0c, 0x04, 0x00, 0x00], // 'Q'
+    [0x00, 0x00, 0x00, 0x78, 0x4c, 0x44, 0x44, 0x7c, 0x7c, 0x44, 0x46, 0x42, 0x00, 0x00, 0x00, 0x00], // 'R'
+    [0x00, 0x00, 0x00, 0x3c, 0x64, 0x40, 0x60, 0x3c, 0x0c, 0x06, 0x44, 0x7c, 0x00, 0x00, 0x00, 0x00], // 'S'
+    [0x00, 0x00, 0x00, 0xfe, 0x18, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00], // 'T'
+    [0x00, 0x00, 0x00, 0x44, 0x46, 0x46, 0x46, 0x46, 0x46, 0x46, 0x64, 0x7c, 0x00, 0x00, 0x00, 0x00], // 'U'
+    [0x00, 0x00, 0x00, 0xc6, 0x46, 0x44, 0x64, 0x6c, 0x2c, 0x28, 0x38, 0x18, 0x00, 0x00, 0x00, 0x00], // 'V'
+    [0x00, 0x00, 0x00, 0x82, 0xc2, 0xd2, 0xde, 0xfe, 0x6e, 0x6e, 0x6c, 0x6c, 0x00, 0x00, 0x00, 0x00], // 'W'
+    [0x00, 0x00, 0x00, 0x46, 0x64, 0x2c, 0x38, 0x18, 0x38, 0x6c, 0x64, 0xc6, 0x00, 0x00, 0x00, 0x00], // 'X'
+    [0x00, 0x00, 0x00, 0xc6, 0x44, 0x6c, 0x38, 0x38, 0x10, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00], // 'Y'
+    [0x00, 0x00, 0x00, 0x7e, 0x06, 0x0c, 0x08, 0x18, 0x30, 0x20, 0x60, 0x7e, 0x00, 0x00, 0x00, 0x00], // 'Z'
+    [0x00, 0x00, 0x00, 0x18, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x18, 0x00, 0x00], // '['
+    [0x00, 0x00, 0x00, 0x40, 0x60, 0x60, 0x20, 0x30, 0x10, 0x18, 0x08, 0x0c, 0x04, 0x00, 0x00, 0x00], // '\\'
+    [0x00, 0x00, 0x00, 0x38, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x38, 0x00, 0x00], // ']'
+    [0x00, 0x00, 0x00, 0x18, 0x38, 0x64, 0x42, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '^'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xfe, 0x00], // '_'
+    [0x00, 0x00, 0x20, 0x30, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '`'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x38, 0x6c, 0x04, 0x7c, 0x44, 0x4c, 0x7c, 0x00, 0x00, 0x00, 0x00], // 'a'
+    [0x00, 0x00, 0x00, 0x40, 0x40, 0x78, 0x6c, 0x66, 0x46, 0x46, 0x64, 0x7c, 0x00, 0x00, 0x00, 0x00], // 'b'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x1c, 0x34, 0x60, 0x60, 0x60, 0x60, 0x3c, 0x00, 0x00, 0x00, 0x00], // 'c'
+    [0x00, 0x00, 0x00, 0x04, 0x04, 0x34, 0x6c, 0x44, 0x44, 0x44, 0x4c, 0x7c, 0x00, 0x00, 0x00, 0x00], // 'd'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x38, 0x6c, 0x46, 0x7e, 0x40, 0x60, 0x3c, 0x00, 0x00, 0x00, 0x00], // 'e'
+    [0x00, 0x00, 0x00, 0x1c, 0x10, 0x7c, 0x30, 0x10, 0x10, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00], // 'f'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x34, 0x6c, 0x44, 0x44, 0x44, 0x6c, 0x3c, 0x04, 0x7c, 0x38, 0x00], // 'g'
+    [0x00, 0x00, 0x00, 0x40, 0x40, 0x78, 0x6c, 0x64, 0x44, 0x44, 0x44, 0x44, 0x00, 0x00, 0x00, 0x00], // 'h'
+    [0x00, 0x00, 0x00, 0x10, 0x00, 0x30, 0x10, 0x10, 0x10, 0x10, 0x10, 0x7e, 0x00, 0x00, 0x00, 0x00], // 'i'
+    [0x00, 0x00, 0x00, 0x18, 0x00, 0x38, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x70, 0x60, 0x00], // 'j'
+    [0x00, 0x00, 0x00, 0x60, 0x60, 0x64, 0x6c, 0x78, 0x78, 0x68, 0x6c, 0x66, 0x00, 0x00, 0x00, 0x00], // 'k'
+    [0x00, 0x00, 0x00, 0x70, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x10, 0x1c, 0x00, 0x00, 0x00, 0x00], // 'l'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x7c, 0xfe, 0xd6, 0xd6, 0xd6, 0xd6, 0xd6, 0x00, 0x00, 0x00, 0x00], // 'm'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x58, 0x6c, 0x64, 0x44, 0x44, 0x44, 0x44, 0x00, 0x00, 0x00, 0x00], // 'n'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x38, 0x6c, 0x44, 0x46, 0x46, 0x64, 0x3c, 0x00, 0x00, 0x00, 0x00], // 'o'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x58, 0x6c, 0x66, 0x46, 0x46, 0x64, 0x7c, 0x40, 0x40, 0x40, 0x00], // 'p'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x34, 0x6c, 0x44, 0x44, 0x44, 0x64, 0x3c, 0x04, 0x04, 0x04, 0x00], // 'q'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x2e, 0x3e, 0x30, 0x20, 0x20, 0x20, 0x20, 0x00, 0x00, 0x00, 0x00], // 'r'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x3c, 0x64, 0x60, 0x38, 0x0c, 0x04, 0x7c, 0x00, 0x00, 0x00, 0x00], // 's'
+    [0x00, 0x00, 0x00, 0x00, 0x30, 0x7c, 0x30, 0x30, 0x30, 0x30, 0x30, 0x1c, 0x00, 0x00, 0x00, 0x00], // 't'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x44, 0x44, 0x44, 0x44, 0x44, 0x64, 0x3c, 0x00, 0x00, 0x00, 0x00], // 'u'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0x44, 0x64, 0x6c, 0x28, 0x38, 0x18, 0x00, 0x00, 0x00, 0x00], // 'v'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x82, 0xc2, 0xd2, 0x5e, 0x7c, 0x6c, 0x6c, 0x00, 0x00, 0x00, 0x00], // 'w'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x44, 0x6c, 0x38, 0x18, 0x38, 0x6c, 0x46, 0x00, 0x00, 0x00, 0x00], // 'x'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x42, 0x46, 0x64, 0x2c, 0x38, 0x38, 0x18, 0x10, 0x70, 0x60, 0x00], // 'y'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x7c, 0x0c, 0x08, 0x18, 0x30, 0x60, 0x7c, 0x00, 0x00, 0x00, 0x00], // 'z'
+    [0x00, 0x00, 0x00, 0x1c, 0x10, 0x10, 0x10, 0x10, 0x70, 0x10, 0x10, 0x10, 0x18, 0x1c, 0x00, 0x00], // '{'
+    [0x00, 0x00, 0x00, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x00], // '|'
+    [0x00, 0x00, 0x00, 0x70, 0x10, 0x10, 0x10, 0x18, 0x0c, 0x18, 0x10, 0x10, 0x10, 0x70, 0x00, 0x00], // '}'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x70, 0xfe, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '~'
+];
@@ -0,0 +1,277 @@
+//! Runs the render graph of a project (See `gyo_core::graph`).
+//!
+//! Every pass has its own pipeline, `Uniform` buffer and target textures.
+//! Bind groups are built for both frame parities up front, so `PingPong`
+//! targets swap by picking the other set instead of rebinding.
+
+use crate::bindings::ShaderLayout;
+use crate::channels::{self, Channels};
+use crate::loader::LoadedProject;
+use crate::uniform::Uniform;
+use crate::HangaEngine;
+use gyo_core::graph::{Pass, PassInput, Persistence, TargetFormat};
+use gyo_core::PipelineState;
+use std::mem;
+use std::sync::Arc;
+
+/// A pass with what it draws with.
+struct GraphPass {
+    pass: Pass,
+    /// Of the `LoadedPass` it was built from
+    fingerprint: u64,
+    /// Shared with the graph it was kept from on reload
+    pipeline: Arc<wgpu::RenderPipeline>,
+    layout: Arc<ShaderLayout>,
+    /// The engine's Uniform with `resolution` set to the target's size
+    uniform_buffer: wgpu::Buffer,
+    /// One texture, two for `PingPong`, none for the screen pass
+    targets: Vec<wgpu::Texture>,
+    /// Channels bound on even and odd frames
+    bind_groups: [Vec<wgpu::BindGroup>; 2],
+}
+
+impl GraphPass {
+    /// The target drawn on frames of `parity`
+    fn written(&self, parity: usize) -> &wgpu::Texture {
+        &self.targets[parity % self.targets.len()]
+    }
+}
+
+pub(crate) struct RenderGraph {
+    /// In the order they run
+    passes: Vec<GraphPass>,
+    /// `TexturePng` assets the passes read, by id
+    textures: Vec<(String, wgpu::Texture)>,
+    /// Size of the screen
+    size: (u32, u32),
+    /// Frames prepared so far
+    frame: u64,
+}
+
+impl RenderGraph {
+    /// Builds the passes of `project`, with the screen pass drawing in `format`
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        project: &LoadedProject,
+        cache: Option<&wgpu::PipelineCache>,
+        width: u32,
+        height: u32,
+    ) -> anyhow::Result<Self> {
+        Self::build(device, queue, format, project, cache, (width, height), |_| None)
+    }
+
+    /// Like [`RenderGraph::new`], keeping the pipelines of the passes of
+    /// `self` with the same fingerprint in the same place. The targets start
+    /// over.
+    pub fn rebuild(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        project: &LoadedProject,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> anyhow::Result<Self> {
+        let kept = |index: usize| self.passes.get(index)
+            .filter(|kept| kept.fingerprint == project.passes[index].fingerprint());
+        Self::build(device, queue, format, project, cache, self.size, kept)
+    }
+
+    fn build<'a>(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        project: &LoadedProject,
+        cache: Option<&wgpu::PipelineCache>,
+        (width, height): (u32, u32),
+        kept: impl Fn(usize) -> Option<&'a GraphPass>,
+    ) -> anyhow::Result<Self> {
+        // 1. Textures read by the passes
+        let textures = Self::upload_textures(device, queue, project);
+
+        // 2. Pipelines (Passes have no depth buffer)
+        let passes = project.passes.iter().enumerate()
+            .map(|(index, loaded)| {
+                let (pipeline, layout) = match kept(index) {
+                    Some(kept) => (kept.pipeline.clone(), kept.layout.clone()),
+                    None => {
+                        let layout = ShaderLayout::new(device, &loaded.shader);
+                        let format = loaded.pass.target.map_or(format, |target| target_format(target.format));
+                        let state = PipelineState { depth: None, ..loaded.pipeline.clone() };
+                        let pipeline = HangaEngine::create_image_pipeline(device, format, &loaded.shader, &state, &layout.layout, cache);
+                        (Arc::new(pipeline), Arc::new(layout))
+                    }
+                };
+                GraphPass {
+                    pass: loaded.pass.clone(),
+                    fingerprint: loaded.fingerprint(),
+                    pipeline,
+                    layout,
+                    uniform_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some(&format!("{} Uniform Buffer", loaded.pass.name)),
+                        size: mem::size_of::<Uniform>() as wgpu::BufferAddress,
+                        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                        mapped_at_creation: false,
+                    }),
+                    targets: Vec::new(),
+                    bind_groups: Default::default(),
+                }
+            })
+            .collect();
+
+        // 3. Targets and bind groups
+        let mut graph = Self { passes, textures, size: (width, height), frame: 0 };
+        graph.resize(device, queue, width, height)?;
+        Ok(graph)
+    }
+
+    /// Recreates the targets for a new screen size. What `Persistent` and
+    /// `PingPong` targets held is lost.
+    pub fn resize(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, width: u32, height: u32) -> anyhow::Result<()> {
+        self.size = (width, height);
+        for pass in &mut self.passes {
+            let Some(target) = pass.pass.target else { continue };
+            let (width, height) = target.size(width, height);
+            let count = if target.persistence == Persistence::PingPong { 2 } else { 1 };
+            pass.targets = (0..count)
+                .map(|_| device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some(&pass.pass.name),
+                    size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: target_format(target.format),
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                }))
+                .collect();
+        }
+        self.rebind(device, queue)
+    }
+
+    /// Samples the textures of `project` from now on, keeping the pipelines
+    /// and what the targets hold
+    pub fn set_textures(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, project: &LoadedProject) -> anyhow::Result<()> {
+        self.textures = Self::upload_textures(device, queue, project);
+        self.rebind(device, queue)
+    }
+
+    /// The `TexturePng` assets of `project` the passes read
+    fn upload_textures(device: &wgpu::Device, queue: &wgpu::Queue, project: &LoadedProject) -> Vec<(String, wgpu::Texture)> {
+        project.textures.iter()
+            .filter(|(id, _)| project.passes.iter().any(|p| p.pass.inputs.contains(&PassInput::Texture(id.clone()))))
+            .map(|(id, texture)| (id.clone(), channels::upload(device, queue, id, texture, wgpu::TextureFormat::Rgba8UnormSrgb)))
+            .collect()
+    }
+
+    /// Bind groups of every pass for both parities
+    fn rebind(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<()> {
+        for index in 0..self.passes.len() {
+            for parity in 0..2 {
+                let bind_groups = self.bind_groups(device, queue, index, parity)?;
+                self.passes[index].bind_groups[parity] = bind_groups;
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts a frame: Writes the Uniform of every pass, in its target's pixels
+    pub fn prepare(&mut self, queue: &wgpu::Queue, uniform: &Uniform) {
+        self.frame += 1;
+        let (width, height) = self.size;
+        for pass in &self.passes {
+            let (target_width, target_height) = pass.pass.target.map_or(self.size, |t| t.size(width, height));
+            let scale = |[x, y]: [f32; 2]| [
+                x * target_width as f32 / width as f32,
+                y * target_height as f32 / height as f32,
+            ];
+            let mut pass_uniform = *uniform;
+            pass_uniform.cursor = scale(uniform.cursor);
+            pass_uniform.mouse_press = scale(uniform.mouse_press);
+            pass_uniform.mouse_release = scale(uniform.mouse_release);
+            pass_uniform.resolution = [target_width as f32, target_height as f32];
+            queue.write_buffer(&pass.uniform_buffer, 0, pass_uniform.as_bytes());
+        }
+    }
+
+    /// Draws the passes with targets, in order
+    pub fn encode_buffers(&self, encoder: &mut wgpu::CommandEncoder) {
+        let parity = self.parity();
+        for pass in &self.passes {
+            let Some(target) = pass.pass.target else { continue };
+            let view = pass.written(parity).create_view(&wgpu::TextureViewDescriptor::default());
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some(&pass.pass.name),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: match target.persistence {
+                            Persistence::Persistent => wgpu::LoadOp::Load,
+                            Persistence::Transient | Persistence::PingPong => wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        },
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            Self::draw(pass, parity, &mut rpass);
+        }
+    }
+
+    /// Draws the screen pass into the frame
+    pub fn draw_screen<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>) {
+        if let Some(pass) = self.passes.iter().find(|p| p.pass.target.is_none()) {
+            Self::draw(pass, self.parity(), rpass);
+        }
+    }
+
+    fn draw<'a>(pass: &'a GraphPass, parity: usize, rpass: &mut wgpu::RenderPass<'a>) {
+        rpass.set_pipeline(&pass.pipeline);
+        for (group, bind_group) in pass.bind_groups[parity].iter().enumerate() {
+            rpass.set_bind_group(group as u32, bind_group, &[]);
+        }
+        rpass.draw(0..3, 0..1); // Full-screen triangle
+    }
+
+    fn parity(&self) -> usize {
+        (self.frame % 2) as usize
+    }
+
+    /// Bind groups of pass `index` on frames of `parity`
+    fn bind_groups(&self, device: &wgpu::Device, queue: &wgpu::Queue, index: usize, parity: usize) -> anyhow::Result<Vec<wgpu::BindGroup>> {
+        let pass = &self.passes[index];
+        let channels = pass.layout.uses_channels().then(|| {
+            let views = pass.pass.inputs.iter()
+                .map(|input| self.input(input, parity).create_view(&wgpu::TextureViewDescriptor::default()))
+                .collect();
+            Channels::from_views(device, queue, views)
+        });
+        pass.layout.bind_groups(device, [Some(&pass.uniform_buffer), None], channels.as_ref(), None)
+    }
+
+    /// The texture `input` reads on frames of `parity`
+    fn input(&self, input: &PassInput, parity: usize) -> &wgpu::Texture {
+        let pass = |name: &str| self.passes.iter()
+            .find(|p| p.pass.name == name)
+            .expect("Inputs are checked by `graph::schedule`");
+        match input {
+            PassInput::Pass(name) => pass(name).written(parity),
+            PassInput::PreviousFrame(name) => pass(name).written(parity + 1),
+            PassInput::Texture(id) => self.textures.iter()
+                .find(|(texture, _)| texture == id)
+                .map(|(_, texture)| texture)
+                .expect("Inputs are checked by `graph::schedule`"),
+        }
+    }
+}
+
+fn target_format(format: TargetFormat) -> wgpu::TextureFormat {
+    match format {
+        TargetFormat::Rgba8Unorm => wgpu::TextureFormat::Rgba8Unorm,
+        TargetFormat::Rgba16Float => wgpu::TextureFormat::Rgba16Float,
+    }
+}
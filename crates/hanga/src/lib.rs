@@ -1,30 +1,188 @@
 use std::sync::Arc;
 use std::mem;
+use std::time::Duration;
 use hanga_traits::Runtime;
-//use wgpu::util::DeviceExt; 
+//use wgpu::util::DeviceExt;
 
 pub mod pipeline_2d;
+mod atlas;
+mod background;
+mod bindings;
+pub mod camera;
+mod channels;
+mod font;
+mod graph;
 pub mod loader;
+mod overlay;
+mod particles;
+mod pipeline_state;
+mod post;
+mod shapes;
+pub mod pausable_instant;
+pub mod pipeline_cache;
+pub mod reference;
+pub mod screenshot;
+pub mod uniform;
+pub mod vector;
+pub mod viewport;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod watch;
 
-use pipeline_2d::{SpriteBatch, InstanceRaw};
-use loader::ProjectLoader;
+use background::BackgroundLayer;
+use bindings::ShaderBindings;
+use camera::{Camera2D, CameraView};
+use gyo_core::shader;
+use gyo_core::texture::Texture;
+use graph::RenderGraph;
+use pipeline_2d::{FrameUploads, SpriteBatch, InstanceRaw, ShapeBatch};
+use gyo_core::pipeline::{BlendMode, CompareFunction, DepthState};
+use gyo_core::particles::Emitter;
+use gyo_core::{PipelineState, ShaderStage};
+use gyo_core::validate::{self, ShaderError, ShaderLanguage, ValidatedShader};
+use loader::{LoadedProject, PipelineFingerprints, ProjectLoader};
+use overlay::ErrorOverlay;
+use particles::Particles;
+use pausable_instant::PausableInstant;
+use pipeline_cache::PipelineCache;
+use post::PostChain;
+use screenshot::{Screenshot, ScreenshotQueue};
+use shapes::ShapeLayer;
+use uniform::Uniform;
+use vector::{VectorBatch, VectorLayer};
+use gyo_core::vector::Path;
+
+/// Format of the offscreen target used by headless engines
+const HEADLESS_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// What the loaded project draws each frame.
+enum Scene {
+    /// Raw vertex/fragment code: Background + instanced sprites
+    Sprites {
+        pipelines: SpritePipelines,  // Sprites
+        background: BackgroundLayer, // Declared in the manifest
+        vectors: Box<VectorLayer>,   // Over the sprites
+        shapes: Box<ShapeLayer>,     // Over the vector paths
+        bindings: ShaderBindings,    // Of the sprite shader
+    },
+    /// `main_image` code: A single full-screen pass fed by the Uniform
+    Image {
+        pipeline: wgpu::RenderPipeline,
+        /// The Uniform and project textures (`iChannel0..3`), as the shader uses them
+        bindings: ShaderBindings,
+        /// Drawn against a depth buffer
+        depth: bool,
+    },
+    /// A render graph: Buffer passes, then an image pass into the screen
+    Graph {
+        graph: RenderGraph,
+    },
+}
+
+/// What a reload built, swapped into the scene once all of it built.
+#[derive(Default)]
+struct SceneUpdate {
+    /// A new scene and post-processing, drawn another way than the last
+    scene: Option<(Scene, Option<PostChain>)>,
+    /// Parts of the current scene whose pipelines changed
+    sprites: Option<(SpritePipelines, ShaderBindings)>,
+    image: Option<(wgpu::RenderPipeline, ShaderBindings, bool)>,
+    background: Option<BackgroundLayer>,
+    graph: Option<RenderGraph>,
+    post: Option<PostChain>,
+    /// The pipelines rebuilt in the current scene, for the log
+    rebuilt: Vec<String>,
+}
+
+/// How sprites are drawn.
+enum SpritePipelines {
+    /// With the depth state the manifest declares, in push order
+    Declared(wgpu::RenderPipeline),
+    /// In layers by `pos.z`: Opaque sprites against the depth buffer (`None`
+    /// for blend modes that do not cover what is behind), then translucent
+    /// ones back to front, tested against the opaque ones
+    Layered {
+        opaque: Option<wgpu::RenderPipeline>,
+        translucent: wgpu::RenderPipeline,
+    },
+}
+
+impl Scene {
+    fn uses_depth(&self) -> bool {
+        match self {
+            Scene::Sprites { .. } => true,
+            Scene::Image { depth, .. } => *depth,
+            Scene::Graph { .. } => false,
+        }
+    }
+}
+
+/// Where frames end up.
+enum Target {
+    Window {
+        window: Arc<winit::window::Window>,
+        surface: wgpu::Surface<'static>,
+    },
+    Headless {
+        texture: wgpu::Texture,
+    },
+}
 
 pub struct HangaEngine {
-    window: Arc<winit::window::Window>,
+    target: Target,
     device: Arc<wgpu::Device>,
     queue: Arc<wgpu::Queue>,
-    surface: wgpu::Surface<'static>,
     config: wgpu::SurfaceConfiguration,
-    
-    // Pipelines
-    render_pipeline: wgpu::RenderPipeline, // Sprites
-    sky_pipeline: wgpu::RenderPipeline,    // Sky (NEW)
-    
-    sprite_batch: SpriteBatch,
+
+    scene: Scene,
+    /// Fingerprints of the pipelines `scene` and `post` were built from
+    /// (`None` for the error pattern)
+    scene_shader: Option<PipelineFingerprints>,
+    /// Fingerprint of the textures `scene` samples
+    scene_textures: u64,
+    /// Depth buffer of scenes that declare depth state
+    depth_texture: Option<wgpu::Texture>,
+    /// Post-processing effects the scene is drawn through, if the project
+    /// declares any
+    post: Option<PostChain>,
+    /// Compiled pipelines of the project, kept across runs
+    pipeline_cache: PipelineCache,
+    overlay: ErrorOverlay,
+
+    /// The project last handed to the engine, recompiled on variant changes
+    project_bytes: Vec<u8>,
+    /// Variant the scene was compiled for, `None` for the default
+    variant: Option<String>,
+    /// Variants the project declares
+    variants: Vec<String>,
+
+    /// The batch without an atlas, then one per atlas in the order they
+    /// were first asked for (Also the order they are drawn in)
+    sprite_batches: Vec<SpriteBatch>,
+    /// Translucent sprites of layered scenes in draw order: `(batch, instances)`
+    translucent_runs: Vec<(usize, std::ops::Range<u32>)>,
+    /// Emitters of the manifest and those added in code, drawn after the sprites
+    particles: Particles,
+    /// Drawn after the particles
+    vector_batch: VectorBatch,
+    /// Drawn last
+    shape_batch: ShapeBatch,
+    /// What `prepare_frame` uploaded for the next frame
+    uploads: FrameUploads,
+    /// `SvgPath` assets of the project, by id
+    paths: Vec<(String, Path)>,
+    screenshots: ScreenshotQueue,
+
+    // Shader inputs
+    uniform: Uniform,
+    uniform_buffer: wgpu::Buffer,
+    /// Where sprites are seen from, and its `view_proj`
+    camera: Camera2D,
+    camera_buffer: wgpu::Buffer,
+    clock: PausableInstant,
 }
 
 impl HangaEngine {
-    async fn init_wgpu(window: Arc<winit::window::Window>) -> (Arc<wgpu::Device>, Arc<wgpu::Queue>, wgpu::Surface<'static>, wgpu::SurfaceConfiguration) {
+    async fn init_wgpu(window: Arc<winit::window::Window>) -> (Arc<wgpu::Device>, Arc<wgpu::Queue>, wgpu::Surface<'static>, wgpu::SurfaceConfiguration, PipelineCache) {
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
             ..Default::default()
@@ -39,179 +197,997 @@ impl HangaEngine {
             force_fallback_adapter: false,
         }).await.expect("No suitable GPU adapter found");
 
-        let (device, queue) = adapter.request_device(
+        let (device, queue) = Self::request_device(&adapter).await.expect("Failed to create device");
+
+        let size = window.inner_size();
+        let mut config = surface.get_default_config(&adapter, size.width, size.height)
+            .expect("Surface not supported");
+
+        // Allow copying presented frames back for screenshots (if the platform lets us)
+        if surface.get_capabilities(&adapter).usages.contains(wgpu::TextureUsages::COPY_SRC) {
+            config.usage |= wgpu::TextureUsages::COPY_SRC;
+        }
+
+        surface.configure(&device, &config);
+        let pipeline_cache = PipelineCache::new(&adapter, &device);
+
+        (Arc::new(device), Arc::new(queue), surface, config, pipeline_cache)
+    }
+
+    async fn request_device(adapter: &wgpu::Adapter) -> Result<(wgpu::Device, wgpu::Queue), wgpu::RequestDeviceError> {
+        adapter.request_device(
             &wgpu::DeviceDescriptor {
                 label: Some("Hanga Device"),
-                required_features: wgpu::Features::empty(),
+                // Pipeline caches where the backend has them (See `pipeline_cache`)
+                required_features: adapter.features() & wgpu::Features::PIPELINE_CACHE,
                 required_limits: wgpu::Limits::downlevel_defaults(),
                 memory_hints: wgpu::MemoryHints::Performance,
             },
-            None, 
-        ).await.expect("Failed to create device");
+            None,
+        ).await
+    }
 
-        let size = window.inner_size();
-        let config = surface.get_default_config(&adapter, size.width, size.height)
-            .expect("Surface not supported");
-        
-        surface.configure(&device, &config);
+    /// Creates an engine without a window. Frames are rendered into an
+    /// offscreen `width` x `height` texture, see [`HangaEngine::capture_frame`].
+    pub async fn new_headless(project_bytes: &[u8], width: u32, height: u32) -> anyhow::Result<Self> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+
+        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        }).await.ok_or_else(|| anyhow::anyhow!("No suitable GPU adapter found"))?;
+
+        let (device, queue) = Self::request_device(&adapter).await?;
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format: HEADLESS_FORMAT,
+            width: width.max(1),
+            height: height.max(1),
+            present_mode: wgpu::PresentMode::Fifo,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+        };
+        let texture = Self::create_offscreen_texture(&device, &config);
+        let pipeline_cache = PipelineCache::new(&adapter, &device);
+
+        Self::from_parts(Target::Headless { texture }, Arc::new(device), Arc::new(queue), config, pipeline_cache, project_bytes)
+    }
+
+    fn create_offscreen_texture(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Headless Target"),
+            size: wgpu::Extent3d { width: config.width, height: config.height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: config.usage,
+            view_formats: &[],
+        })
+    }
+
+    fn from_parts(
+        target: Target,
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
+        config: wgpu::SurfaceConfiguration,
+        mut pipeline_cache: PipelineCache,
+        project_bytes: &[u8],
+    ) -> anyhow::Result<Self> {
+        let uniform = Uniform::default();
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Uniform Buffer"),
+            size: mem::size_of::<Uniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let camera_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Camera Buffer"),
+            size: shader::CAMERA_SIZE as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        println!("📂 Engine Loading Project...");
+        let mut variants = Vec::new();
+        let mut particles = Particles::default();
+        let mut paths = Vec::new();
+        let mut scene_textures = 0;
+        let scene = match ProjectLoader::load(project_bytes) {
+            Ok(project) => {
+                variants = project.manifest.variants.iter().map(|v| v.name.clone()).collect();
+                paths = project.paths.clone();
+                scene_textures = project.texture_fingerprint();
+                particles.set_declared(&device, &project.manifest.particles);
+                pipeline_cache.load(&device, &project.manifest.title, project.fingerprint());
+                Self::try_build_scene(&device, &queue, &config, &project, [&uniform_buffer, &camera_buffer], pipeline_cache.get())
+                    .map(|(scene, post)| (scene, post, project.pipeline_fingerprints()))
+            }
+            // Broken shaders still start the engine, showing what went wrong
+            Err(e) if e.downcast_ref::<ShaderError>().is_some() => Err(e),
+            Err(e) => return Err(e),
+        };
+
+        let mut overlay = ErrorOverlay::new(&device, config.format);
+        let (scene, post, scene_shader) = match scene {
+            Ok((scene, post, fingerprint)) => {
+                pipeline_cache.save();
+                (scene, post, Some(fingerprint))
+            }
+            Err(e) => {
+                println!("❌ Shader Error:\n{:#}", e);
+                overlay.set_message(Some(format!("{:#}", e)));
+                (Self::create_error_scene(&device, &queue, &config, &uniform_buffer), None, None)
+            }
+        };
+
+        let depth_texture = Self::create_depth_texture(&device, &config, &scene);
+        let sprite_batches = vec![SpriteBatch::new(&device, 10_000)];
+        let vector_batch = VectorBatch::new(&device, 10_000);
+        let shape_batch = ShapeBatch::new(&device, 1_000);
+
+        Ok(Self {
+            target, device, queue, config,
+            scene,
+            scene_shader,
+            scene_textures,
+            depth_texture,
+            post,
+            pipeline_cache,
+            overlay,
+            project_bytes: project_bytes.to_vec(),
+            variant: None,
+            variants,
+            sprite_batches,
+            translucent_runs: Vec::new(),
+            particles,
+            vector_batch,
+            shape_batch,
+            uploads: FrameUploads::new(),
+            paths,
+            screenshots: ScreenshotQueue::default(),
+            uniform,
+            uniform_buffer,
+            camera: Camera2D::default(),
+            camera_buffer,
+            clock: PausableInstant::now(),
+        })
+    }
+
+    /// The depth buffer `scene` is drawn against, if it uses one
+    fn create_depth_texture(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration, scene: &Scene) -> Option<wgpu::Texture> {
+        scene.uses_depth().then(|| device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Depth Buffer"),
+            size: wgpu::Extent3d { width: config.width, height: config.height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: pipeline_state::DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        }))
+    }
+
+    /// Builds the pipelines of a loaded project inside a validation error
+    /// scope, so a bad pipeline is reported instead of killing the device.
+    /// With post-processing, the scene draws into the chain's HDR target.
+    /// `buffers` are the `Uniform` and camera buffers.
+    fn try_build_scene(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        config: &wgpu::SurfaceConfiguration,
+        project: &LoadedProject,
+        [uniform_buffer, camera_buffer]: [&wgpu::Buffer; 2],
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> anyhow::Result<(Scene, Option<PostChain>)> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let shader = &project.shader;
+        let state = &project.pipeline;
+        let depth = state.depth.is_some();
+        let format = if project.effects.is_empty() { config.format } else { post::HDR_FORMAT };
+        let scene = if !project.passes.is_empty() {
+            RenderGraph::new(device, queue, format, project, cache, config.width, config.height)
+                .map(|graph| Scene::Graph { graph })
+        } else {
+            ShaderBindings::new(device, queue, shader, &project.textures, uniform_buffer, Some(camera_buffer))
+                .and_then(|bindings| Ok(if shader.is_image() {
+                    Scene::Image {
+                        pipeline: Self::create_image_pipeline(device, format, shader, state, bindings.layout(), cache),
+                        bindings,
+                        depth,
+                    }
+                } else {
+                    // Create BOTH layers
+                    Scene::Sprites {
+                        pipelines: Self::create_sprite_pipelines(device, format, shader, state, bindings.layout(), cache),
+                        background: BackgroundLayer::new(device, queue, format, project, uniform_buffer, true, cache)?,
+                        vectors: Box::new(VectorLayer::new(device, format, camera_buffer, cache)),
+                        shapes: Box::new(ShapeLayer::new(device, format, cache)),
+                        bindings,
+                    }
+                }))
+        };
+        let scene = scene.and_then(|scene| {
+            let post = (!project.effects.is_empty())
+                .then(|| PostChain::new(device, queue, config, &project.effects, uniform_buffer, cache))
+                .transpose()?;
+            Ok((scene, post))
+        });
+
+        match futures::executor::block_on(device.pop_error_scope()) {
+            Some(error) => anyhow::bail!("Failed to create pipeline: {}", error),
+            None => scene,
+        }
+    }
+
+    /// What the device compiles for a validated shader
+    fn shader_source(shader: &ValidatedShader) -> wgpu::ShaderSource<'_> {
+        let code = shader.composed.code.as_str().into();
+        match shader.language {
+            ShaderLanguage::Wgsl => wgpu::ShaderSource::Wgsl(code),
+            ShaderLanguage::Glsl => wgpu::ShaderSource::Glsl {
+                shader: code,
+                stage: naga::ShaderStage::Fragment,
+                defines: Default::default(),
+            },
+            ShaderLanguage::SpirV => wgpu::ShaderSource::SpirV(shader.binary.as_slice().into()),
+        }
+    }
+
+    // Helper for the built-in error pattern (Shown when there is no working shader)
+    fn create_error_scene(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        config: &wgpu::SurfaceConfiguration,
+        uniform_buffer: &wgpu::Buffer,
+    ) -> Scene {
+        let shader = validate::validate_asset("<error pattern>", include_str!("error_pattern.wgsl"))
+            .expect("The built-in error pattern is valid");
+        let bindings = ShaderBindings::new(device, queue, &shader, &[], uniform_buffer, None)
+            .expect("The error pattern only uses the Uniform");
+        let state = PipelineState::new("<error pattern>");
+        let pipeline = Self::create_image_pipeline(device, config.format, &shader, &state, bindings.layout(), None);
+        Scene::Image { pipeline, bindings, depth: false }
+    }
+
+    /// Swaps in a new version of the project.
+    ///
+    /// Only the pipelines whose fingerprint changed (The main shader, the
+    /// background, each pass and each effect) are rebuilt, and changed
+    /// textures are uploaded under the others in place; time, input and the
+    /// sprite batch carry over. A project drawn another way (Another kind of
+    /// scene, or post-processing added or removed) is rebuilt whole. If the new version fails to load or its
+    /// pipelines fail to build, the current scene keeps rendering and the
+    /// error is shown over it until a later version loads. The error is
+    /// returned as well.
+    pub fn reload_project(&mut self, project_bytes: &[u8]) -> anyhow::Result<()> {
+        self.project_bytes = project_bytes.to_vec();
+        let variant = self.variant.clone();
+        self.load_variant(variant.as_deref())
+    }
+
+    /// Recompiles the project for the variant called `name` (See
+    /// `gyo_core::ShaderVariant`), `None` for the default one.
+    ///
+    /// Like a reload: Time and input carry over, and if the variant fails
+    /// to build the current scene keeps rendering under the error.
+    pub fn set_variant(&mut self, name: Option<&str>) -> anyhow::Result<()> {
+        self.load_variant(name)?;
+        self.variant = name.map(str::to_string);
+        Ok(())
+    }
+
+    /// The variant selected with [`HangaEngine::set_variant`]
+    pub fn variant(&self) -> Option<&str> {
+        self.variant.as_deref()
+    }
+
+    /// Names of the variants the project declares, the default first
+    pub fn variants(&self) -> &[String] {
+        &self.variants
+    }
+
+    fn load_variant(&mut self, variant: Option<&str>) -> anyhow::Result<()> {
+        let project = match ProjectLoader::load_variant(&self.project_bytes, variant) {
+            Ok(project) => project,
+            Err(e) => return Err(self.show_error(e)),
+        };
+
+        // 1. Build the pipelines that changed (All of them if the scene is drawn another way)
+        self.pipeline_cache.load(&self.device, &project.manifest.title, project.fingerprint());
+        let update = match &self.scene_shader {
+            Some(previous) if self.draws_like(&project) => self.update_scene(&project, previous),
+            _ => Self::try_build_scene(&self.device, &self.queue, &self.config, &project, [&self.uniform_buffer, &self.camera_buffer], self.pipeline_cache.get())
+                .map(|scene| SceneUpdate { scene: Some(scene), ..Default::default() }),
+        };
+        let update = match update {
+            Ok(update) => update,
+            Err(e) => return Err(self.show_error(e)),
+        };
+
+        // 2. Swap them in, then upload changed textures under the pipelines that were kept
+        let (rebuilt, replaced) = (update.rebuilt.join(", "), update.scene.is_some());
+        if replaced || !rebuilt.is_empty() {
+            self.apply(update);
+            self.pipeline_cache.save();
+        }
+        self.scene_shader = Some(project.pipeline_fingerprints());
+        let textures = project.texture_fingerprint();
+        let textures_changed = self.scene_textures != textures;
+        if textures_changed && !replaced {
+            if let Err(e) = self.set_textures(&project) {
+                return Err(self.show_error(e));
+            }
+        }
+        self.scene_textures = textures;
+
+        // 3. What the scene no longer needs to build
+        self.variants = project.manifest.variants.iter().map(|v| v.name.clone()).collect();
+        self.paths = project.paths.clone();
+        self.particles.set_declared(&self.device, &project.manifest.particles);
+
+        match (&project.variant, replaced) {
+            (Some(variant), true) => println!("♻️ Project reloaded (Variant `{}`)", variant),
+            (None, true) => println!("♻️ Project reloaded"),
+            _ if !rebuilt.is_empty() => println!("♻️ Project reloaded (Rebuilt {})", rebuilt),
+            _ if textures_changed => println!("♻️ Project reloaded (Textures changed, shaders unchanged)"),
+            _ => println!("♻️ Project reloaded (Shaders unchanged)"),
+        }
+        self.overlay.set_message(None);
+        Ok(())
+    }
+
+    /// True if `project` draws the way the current scene does: The same
+    /// kind of scene, with or without post-processing. Its pipelines can
+    /// then be rebuilt one by one.
+    fn draws_like(&self, project: &LoadedProject) -> bool {
+        let same_kind = match &self.scene {
+            Scene::Sprites { .. } => project.passes.is_empty() && !project.shader.is_image(),
+            Scene::Image { .. } => project.passes.is_empty() && project.shader.is_image(),
+            Scene::Graph { .. } => !project.passes.is_empty(),
+        };
+        same_kind && self.post.is_some() != project.effects.is_empty()
+    }
+
+    /// Builds the pipelines of `project` whose fingerprints differ from
+    /// `previous`, for a scene it draws like (See `draws_like`), inside a
+    /// validation error scope. The scene is left alone.
+    fn update_scene(&self, project: &LoadedProject, previous: &PipelineFingerprints) -> anyhow::Result<SceneUpdate> {
+        let (device, queue, cache) = (&*self.device, &*self.queue, self.pipeline_cache.get());
+        let current = project.pipeline_fingerprints();
+        let format = if project.effects.is_empty() { self.config.format } else { post::HDR_FORMAT };
+        let mut update = SceneUpdate::default();
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let result = (|| {
+            match &self.scene {
+                Scene::Sprites { .. } => {
+                    if previous.main != current.main {
+                        let bindings = ShaderBindings::new(device, queue, &project.shader, &project.textures, &self.uniform_buffer, Some(&self.camera_buffer))?;
+                        let pipelines = Self::create_sprite_pipelines(device, format, &project.shader, &project.pipeline, bindings.layout(), cache);
+                        update.sprites = Some((pipelines, bindings));
+                        update.rebuilt.push("the sprite shader".to_string());
+                    }
+                    if previous.background != current.background {
+                        update.background = Some(BackgroundLayer::new(device, queue, format, project, &self.uniform_buffer, true, cache)?);
+                        update.rebuilt.push("the background".to_string());
+                    }
+                }
+                Scene::Image { .. } => {
+                    if previous.main != current.main {
+                        let bindings = ShaderBindings::new(device, queue, &project.shader, &project.textures, &self.uniform_buffer, Some(&self.camera_buffer))?;
+                        let pipeline = Self::create_image_pipeline(device, format, &project.shader, &project.pipeline, bindings.layout(), cache);
+                        update.image = Some((pipeline, bindings, project.pipeline.depth.is_some()));
+                        update.rebuilt.push("the image shader".to_string());
+                    }
+                }
+                Scene::Graph { graph } => {
+                    if previous.passes != current.passes {
+                        update.graph = Some(graph.rebuild(device, queue, format, project, cache)?);
+                        update.rebuilt.extend(Self::changed(&previous.passes, &current.passes)
+                            .map(|index| format!("pass `{}`", project.passes[index].pass.name)));
+                    }
+                }
+            }
+            if let Some(post) = &self.post {
+                if previous.effects != current.effects {
+                    update.post = Some(post.rebuild(device, queue, &self.config, &project.effects, &self.uniform_buffer, cache)?);
+                    update.rebuilt.extend(Self::changed(&previous.effects, &current.effects)
+                        .map(|index| format!("effect {}", index)));
+                }
+            }
+            Ok(())
+        })();
+
+        match futures::executor::block_on(device.pop_error_scope()) {
+            Some(error) => anyhow::bail!("Failed to create pipeline: {}", error),
+            None => result.map(|()| update),
+        }
+    }
+
+    /// Indices of `current` without the same fingerprint in `previous`
+    fn changed<'a>(previous: &'a [u64], current: &'a [u64]) -> impl Iterator<Item = usize> + 'a {
+        current.iter().enumerate()
+            .filter(|(index, fingerprint)| previous.get(*index) != Some(fingerprint))
+            .map(|(index, _)| index)
+    }
+
+    /// Swaps what a reload built into the scene
+    fn apply(&mut self, update: SceneUpdate) {
+        if let Some((scene, post)) = update.scene {
+            self.scene = scene;
+            self.post = post;
+        }
+        match &mut self.scene {
+            Scene::Sprites { pipelines, bindings, background, .. } => {
+                if let Some((new_pipelines, new_bindings)) = update.sprites {
+                    *pipelines = new_pipelines;
+                    *bindings = new_bindings;
+                }
+                if let Some(new_background) = update.background {
+                    *background = new_background;
+                }
+            }
+            Scene::Image { pipeline, bindings, depth } => {
+                if let Some((new_pipeline, new_bindings, new_depth)) = update.image {
+                    *pipeline = new_pipeline;
+                    *bindings = new_bindings;
+                    *depth = new_depth;
+                }
+            }
+            Scene::Graph { graph } => {
+                if let Some(new_graph) = update.graph {
+                    *graph = new_graph;
+                }
+            }
+        }
+        if let Some(post) = update.post {
+            self.post = Some(post);
+        }
+        self.depth_texture = Self::create_depth_texture(&self.device, &self.config, &self.scene);
+    }
+
+    /// Uploads the textures of `project` to the current scene, its
+    /// background and post-processing, keeping their pipelines. `project`
+    /// must have the scene's shaders (See `LoadedProject::fingerprint`)
+    fn set_textures(&mut self, project: &LoadedProject) -> anyhow::Result<()> {
+        let (device, queue) = (&self.device, &self.queue);
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let result = (|| {
+            match &mut self.scene {
+                Scene::Sprites { background, bindings, .. } => {
+                    bindings.set_textures(device, queue, &project.shader, &project.textures, &self.uniform_buffer, Some(&self.camera_buffer))?;
+                    background.set_textures(device, queue, project, &self.uniform_buffer)?;
+                }
+                Scene::Image { bindings, .. } => {
+                    bindings.set_textures(device, queue, &project.shader, &project.textures, &self.uniform_buffer, Some(&self.camera_buffer))?;
+                }
+                Scene::Graph { graph } => graph.set_textures(device, queue, project)?,
+            }
+            if let Some(post) = &mut self.post {
+                post.set_luts(device, queue, &project.effects, &self.uniform_buffer)?;
+            }
+            Ok(())
+        })();
+
+        match futures::executor::block_on(device.pop_error_scope()) {
+            Some(error) => anyhow::bail!("Failed to upload textures: {}", error),
+            None => result,
+        }
+    }
 
-        (Arc::new(device), Arc::new(queue), surface, config)
+    /// Shows `error` over the frame (Until the next successful reload).
+    /// For failures outside the engine, like a project that cannot be read.
+    pub fn show_error(&mut self, error: anyhow::Error) -> anyhow::Error {
+        println!("❌ Shader Error (Keeping the last good version):\n{:#}", error);
+        self.overlay.set_message(Some(format!("{:#}", error)));
+        error
     }
 
-    // Helper for the Sky (No Vertex Buffers)
-    fn create_sky_pipeline(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> wgpu::RenderPipeline {
-        let shader = device.create_shader_module(wgpu::include_wgsl!("sky.wgsl"));
-        
+    /// The error currently shown over the frame, if any
+    pub fn error_message(&self) -> Option<&str> {
+        self.overlay.message()
+    }
+
+    // Helper for Image Shaders (Loaded Code already wrapped in the runtime prelude)
+    fn create_image_pipeline(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        image: &ValidatedShader,
+        state: &PipelineState,
+        layout: &wgpu::PipelineLayout,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> wgpu::RenderPipeline {
+        let vertex = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Gyosho Image Vertex Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader::VERTEX_SHADER.into()),
+        });
+        let fragment = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Gyosho Loaded Image Shader"),
+            source: Self::shader_source(image),
+        });
+
         device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Sky Pipeline"),
-            layout: None,
+            label: Some("Image Pipeline"),
+            layout: Some(layout),
             vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[], // No buffers needed!
+                module: &vertex,
+                entry_point: shader::ENTRY_POINT,
+                buffers: &[], // Full-screen triangle
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
             },
             fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
+                module: &fragment,
+                entry_point: image.entry_point(ShaderStage::Fragment).unwrap_or(shader::ENTRY_POINT),
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE), // Sky overwrites everything
+                    format,
+                    blend: Some(pipeline_state::blend_state(state.blend.unwrap_or(BlendMode::Replace))),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
             }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None,
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-            },
-            depth_stencil: None,
+            primitive: pipeline_state::primitive_state(state),
+            depth_stencil: pipeline_state::depth_stencil_state(state.depth),
             multisample: wgpu::MultisampleState {
                 count: 1, mask: !0, alpha_to_coverage_enabled: false,
             },
             multiview: None,
-            cache: None,
+            cache,
         })
     }
 
+    /// The declared pipeline, or else the pipelines of layered sprites
+    fn create_sprite_pipelines(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sprites: &ValidatedShader,
+        state: &PipelineState,
+        layout: &wgpu::PipelineLayout,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> SpritePipelines {
+        if state.depth.is_some() {
+            return SpritePipelines::Declared(Self::create_pipeline(device, format, sprites, state, layout, cache));
+        }
+        let layer = |write| PipelineState {
+            depth: Some(DepthState { write, compare: CompareFunction::LessEqual }),
+            ..state.clone()
+        };
+        // Only sprites that replace what is behind them can hide it
+        let occluding = matches!(state.blend.unwrap_or(BlendMode::Alpha), BlendMode::Replace | BlendMode::Alpha | BlendMode::Premultiplied);
+        SpritePipelines::Layered {
+            opaque: occluding.then(|| Self::create_pipeline(device, format, sprites, &layer(true), layout, cache)),
+            translucent: Self::create_pipeline(device, format, sprites, &layer(false), layout, cache),
+        }
+    }
+
     // Helper for Sprites (Loaded Code)
-    fn create_pipeline(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration, source_code: &str) -> wgpu::RenderPipeline {
+    fn create_pipeline(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sprites: &ValidatedShader,
+        state: &PipelineState,
+        layout: &wgpu::PipelineLayout,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> wgpu::RenderPipeline {
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Gyosho Loaded Shader"),
-            source: wgpu::ShaderSource::Wgsl(source_code.into()),
+            source: Self::shader_source(sprites),
         });
 
         device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Sprite Pipeline"),
-            layout: None, 
+            layout: Some(layout),
             vertex: wgpu::VertexState {
                 module: &shader,
-                entry_point: "vs_main",
-                buffers: &[InstanceRaw::desc()], 
+                entry_point: sprites.entry_point(ShaderStage::Vertex).unwrap_or("vs_main"),
+                buffers: &[InstanceRaw::desc()],
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
             },
             fragment: Some(wgpu::FragmentState {
                 module: &shader,
-                entry_point: "fs_main",
+                entry_point: sprites.entry_point(ShaderStage::Fragment).unwrap_or("fs_main"),
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING), // Sprites blend over sky
+                    format,
+                    // Sprites blend over the background unless declared otherwise
+                    blend: Some(pipeline_state::blend_state(state.blend.unwrap_or(BlendMode::Alpha))),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
             }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None, 
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-            },
-            depth_stencil: None,
+            primitive: pipeline_state::primitive_state(state),
+            depth_stencil: pipeline_state::depth_stencil_state(state.depth),
             multisample: wgpu::MultisampleState {
                 count: 1, mask: !0, alpha_to_coverage_enabled: false,
             },
             multiview: None,
-            cache: None,
+            cache,
         })
     }
 
-    pub fn get_batch(&mut self) -> &mut SpriteBatch { &mut self.sprite_batch }
-    pub fn prepare_frame(&mut self) { self.sprite_batch.prepare(&self.device, &self.queue); }
-}
+    pub fn get_batch(&mut self) -> &mut SpriteBatch { &mut self.sprite_batches[0] }
 
-impl Runtime for HangaEngine {
-    async fn new(window: Arc<winit::window::Window>, project_bytes: &[u8]) -> anyhow::Result<Self> {
-        let (device, queue, surface, config) = Self::init_wgpu(window.clone()).await;
-        
-        println!("📂 Engine Loading Project...");
-        let project = ProjectLoader::load(project_bytes)?;
-        
-        // Create BOTH pipelines
-        let render_pipeline = Self::create_pipeline(&device, &config, &project.source_code);
-        let sky_pipeline = Self::create_sky_pipeline(&device, &config);
-        
-        let sprite_batch = SpriteBatch::new(&device, 10_000); 
+    /// The batch sampling the `TexturePng` asset called `atlas`, created on
+    /// first use. Fails if the sprite shader cannot sample it.
+    pub fn atlas_batch(&mut self, atlas: &str) -> anyhow::Result<&mut SpriteBatch> {
+        // Only sprite scenes draw batches
+        if let Scene::Sprites { bindings, .. } = &self.scene {
+            anyhow::ensure!(
+                bindings.has_atlas(atlas),
+                "`{}` is not an atlas of the sprite shader (A TexturePng asset, sampled at @group({}))",
+                atlas, gyo_core::shader::ATLAS_GROUP,
+            );
+        }
+        let index = match self.sprite_batches.iter().position(|b| b.atlas() == Some(atlas)) {
+            Some(index) => index,
+            None => {
+                self.sprite_batches.push(SpriteBatch::new(&self.device, 1_000).with_atlas(atlas));
+                self.sprite_batches.len() - 1
+            }
+        };
+        Ok(&mut self.sprite_batches[index])
+    }
 
-        Ok(Self {
-            window, device, queue, surface, config,
-            render_pipeline,
-            sky_pipeline,
-            sprite_batch,
-        })
+    /// Filled and stroked vector paths, drawn over the sprites and
+    /// particles of sprite scenes
+    pub fn vector_batch(&mut self) -> &mut VectorBatch { &mut self.vector_batch }
+
+    /// The `SvgPath` asset called `id`
+    pub fn path(&self, id: &str) -> Option<&Path> {
+        self.paths.iter().find(|(path_id, _)| path_id == id).map(|(_, path)| path)
     }
 
-    fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        if new_size.width > 0 && new_size.height > 0 {
-            self.config.width = new_size.width;
-            self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
+    /// Circles, rounded rectangles, rings, capsules and lines, drawn over
+    /// the sprites, particles and vector paths of sprite scenes
+    pub fn shape_batch(&mut self) -> &mut ShapeBatch { &mut self.shape_batch }
+
+    /// Uploads the batches, ordered for the scene's sprite pipelines
+    pub fn prepare_frame(&mut self) {
+        let layers = match &self.scene {
+            Scene::Sprites { pipelines: SpritePipelines::Layered { opaque, .. }, bindings, .. } => Some((opaque.is_some(), bindings)),
+            _ => None,
+        };
+        for batch in &mut self.sprite_batches {
+            match layers {
+                Some((occluding, bindings)) => {
+                    let opaque_atlas = batch.atlas().is_none_or(|atlas| bindings.atlas_is_opaque(atlas));
+                    batch.prepare_layers(&self.device, &mut self.uploads, occluding && opaque_atlas);
+                }
+                None => batch.prepare(&self.device, &mut self.uploads),
+            }
         }
+        self.translucent_runs = match layers {
+            Some(_) => pipeline_2d::translucent_runs(&self.sprite_batches),
+            None => Vec::new(),
+        };
+        self.vector_batch.prepare(&self.device, &mut self.uploads);
+        self.shape_batch.prepare(&self.device, &mut self.uploads);
     }
 
-    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?;
-        let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+    /// Adds a particle emitter, or replaces the one of the same name (The
+    /// manifest's included). Fails if its settings are out of range, or if
+    /// the sprite shader cannot sample its atlas.
+    pub fn add_emitter(&mut self, emitter: Emitter) -> anyhow::Result<()> {
+        Emitter { atlas: None, ..emitter.clone() }.check(&[])?;
+        if let (Some(atlas), Scene::Sprites { bindings, .. }) = (&emitter.atlas, &self.scene) {
+            anyhow::ensure!(
+                bindings.has_atlas(atlas),
+                "Emitter `{}` samples `{}`, which is not an atlas of the sprite shader", emitter.name, atlas,
+            );
+        }
+        self.particles.add(&self.device, emitter, false);
+        Ok(())
+    }
+
+    /// Removes the emitter called `name` and its particles. False if there
+    /// is none
+    pub fn remove_emitter(&mut self, name: &str) -> bool {
+        self.particles.remove(name)
+    }
+
+    /// The emitter called `name`, to change its settings between frames
+    /// (Like the wind). A new capacity empties it.
+    pub fn emitter_mut(&mut self, name: &str) -> Option<&mut Emitter> {
+        self.particles.get_mut(name)
+    }
+
+    /// Captures the next presented frame without blocking the render loop.
+    /// Poll the returned handle, or let it write a PNG from a background thread.
+    pub fn request_screenshot(&mut self) -> Screenshot {
+        let screenshot = self.screenshots.request();
+        if !self.config.usage.contains(wgpu::TextureUsages::COPY_SRC) {
+            self.screenshots.reject("Surface does not support COPY_SRC, frames cannot be captured");
+        } else if let Target::Window { window, .. } = &self.target {
+            window.request_redraw();
+        }
+        screenshot
+    }
+
+    /// Renders one frame and blocks until it has been read back.
+    /// Intended for headless engines (Tests, offline export).
+    pub fn capture_frame(&mut self) -> anyhow::Result<Texture> {
+        let screenshot = self.screenshots.request();
+        self.render()?;
+
+        loop {
+            if let Some(image) = screenshot.try_take() {
+                return image;
+            }
+            self.device.poll(wgpu::Maintain::Wait);
+            self.screenshots.poll(&self.device);
+        }
+    }
+
+    // --- Time & Input (Mirrors the Uniform fields) ---
+
+    pub fn is_paused(&self) -> bool { self.clock.is_paused() }
+    pub fn pause(&mut self) { self.clock.pause(); }
+    pub fn resume(&mut self) { self.clock.resume(); }
+    pub fn restart(&mut self) { self.clock.reset(); }
 
+    /// Jumps the shader clock to `seconds`. Pause first to render a fixed time.
+    pub fn set_time(&mut self, seconds: f32) {
+        self.clock.set_elapsed(Duration::from_secs_f32(seconds.max(0.0)));
+    }
+
+    /// Cursor position in pixels, origin at the bottom-left (Shadertoy convention)
+    pub fn update_cursor(&mut self, cursor: [f32; 2]) {
+        self.uniform.cursor = cursor;
+    }
+
+    pub fn update_mouse_press(&mut self) {
+        self.uniform.mouse_press = self.uniform.cursor;
+        self.uniform.mouse_down = 1;
+    }
+
+    pub fn update_mouse_release(&mut self) {
+        self.uniform.mouse_release = self.uniform.cursor;
+        self.uniform.mouse_down = 0;
+    }
+
+    // --- 2D Camera (Sprite scenes) ---
+
+    pub fn camera(&self) -> &Camera2D { &self.camera }
+    pub fn camera_mut(&mut self) -> &mut Camera2D { &mut self.camera }
+    pub fn set_camera(&mut self, camera: Camera2D) { self.camera = camera; }
+
+    /// The camera fitted to the current frame, for converting between
+    /// screen, world and NDC coordinates
+    pub fn camera_view(&self) -> CameraView {
+        let scale_factor = match &self.target {
+            Target::Window { window, .. } => window.scale_factor() as f32,
+            Target::Headless { .. } => 1.0,
+        };
+        self.camera.view([self.config.width, self.config.height], scale_factor)
+    }
+
+    fn update_uniform(&mut self) {
+        self.uniform.time = self.clock.elapsed().as_secs_f32();
+        self.uniform.resolution = [self.config.width as f32, self.config.height as f32];
+        self.queue.write_buffer(&self.uniform_buffer, 0, self.uniform.as_bytes());
+        let camera_view = self.camera_view();
+        self.queue.write_buffer(&self.camera_buffer, 0, camera_view.as_bytes());
+        // Particles only move in sprite scenes, which run their compute pass
+        match &mut self.scene {
+            Scene::Graph { graph } => graph.prepare(&self.queue, &self.uniform),
+            Scene::Sprites { shapes, .. } => {
+                shapes.prepare(&self.queue, &camera_view);
+                self.particles.update(&self.device, &self.queue, self.uniform.time);
+            }
+            Scene::Image { .. } => {}
+        }
+    }
+
+    /// Submits the batch uploads of `prepare_frame`, then the frame that draws them
+    fn submit(&mut self, encoder: wgpu::CommandEncoder) {
+        let uploads = self.uploads.finish();
+        self.queue.submit(uploads.into_iter().chain(std::iter::once(encoder.finish())));
+        self.uploads.recall();
+    }
+
+    fn encode_frame(&self, view: &wgpu::TextureView) -> wgpu::CommandEncoder {
         let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Hanga Render Encoder"),
         });
+        let depth_view = self.depth_texture.as_ref()
+            .map(|texture| texture.create_view(&wgpu::TextureViewDescriptor::default()));
+
+        // Buffer passes draw into their targets before the frame, particles
+        // move before sprite scenes draw them
+        match &self.scene {
+            Scene::Graph { graph } => graph.encode_buffers(&mut encoder),
+            Scene::Sprites { .. } => self.particles.encode(&mut encoder),
+            Scene::Image { .. } => {}
+        }
+        // With post-processing, the scene draws into the HDR target
+        let scene_view = self.post.as_ref().map(PostChain::scene_view);
+        let clear = match &self.scene {
+            Scene::Sprites { background, .. } => background.clear_color(),
+            Scene::Image { .. } | Scene::Graph { .. } => wgpu::Color::BLACK,
+        };
 
         {
             let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Main Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view: scene_view.as_ref().unwrap_or(view),
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK), // Clear logic still handled here
+                        load: wgpu::LoadOp::Clear(clear), // The background's colour for sprites
                         store: wgpu::StoreOp::Store,
                     },
                 })],
-                depth_stencil_attachment: None,
+                depth_stencil_attachment: depth_view.as_ref().map(|view| wgpu::RenderPassDepthStencilAttachment {
+                    view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Discard,
+                    }),
+                    stencil_ops: None,
+                }),
                 timestamp_writes: None,
                 occlusion_query_set: None,
             });
 
-            // 1. DRAW SKY
-            rpass.set_pipeline(&self.sky_pipeline);
-            rpass.draw(0..3, 0..1); // Draw 3 vertices (1 triangle)
+            match &self.scene {
+                Scene::Sprites { pipelines, background, vectors, shapes, bindings } => {
+                    // 0. FIT THE CANVAS (Letterboxed canvases leave bars of the clear colour)
+                    let viewport = *self.camera_view().viewport();
+                    rpass.set_viewport(viewport.x, viewport.y, viewport.width, viewport.height, viewport.min_depth, viewport.max_depth);
+                    rpass.set_scissor_rect(viewport.x as u32, viewport.y as u32, viewport.width as u32, viewport.height as u32);
+
+                    // 1. DRAW BACKGROUND
+                    background.draw(&mut rpass);
+
+                    // 2. DRAW SPRITES (Rain)
+                    match pipelines {
+                        SpritePipelines::Declared(pipeline) => {
+                            rpass.set_pipeline(pipeline);
+                            bindings.set(&mut rpass);
+                            for batch in &self.sprite_batches {
+                                bindings.set_atlas(&mut rpass, batch.atlas());
+                                batch.draw(&mut rpass);
+                            }
+                            for (atlas, instances) in self.particles.batches() {
+                                bindings.set_atlas(&mut rpass, atlas);
+                                instances.draw(&mut rpass);
+                            }
+                        }
+                        SpritePipelines::Layered { opaque, translucent } => {
+                            if let Some(opaque) = opaque {
+                                rpass.set_pipeline(opaque);
+                                bindings.set(&mut rpass);
+                                for batch in &self.sprite_batches {
+                                    bindings.set_atlas(&mut rpass, batch.atlas());
+                                    batch.draw_range(&mut rpass, batch.opaque());
+                                }
+                            }
+                            rpass.set_pipeline(translucent);
+                            bindings.set(&mut rpass);
+                            for (index, instances) in &self.translucent_runs {
+                                let batch = &self.sprite_batches[*index];
+                                bindings.set_atlas(&mut rpass, batch.atlas());
+                                batch.draw_range(&mut rpass, instances.clone());
+                            }
+                            // Then the particles (Translucent, tested against the opaque sprites)
+                            for (atlas, instances) in self.particles.batches() {
+                                bindings.set_atlas(&mut rpass, atlas);
+                                instances.draw(&mut rpass);
+                            }
+                        }
+                    }
+
+                    // 3. DRAW VECTOR PATHS (In push order, over the sprites)
+                    vectors.draw(&mut rpass, &self.vector_batch);
+
+                    // 4. DRAW SHAPES (Back to front, over everything)
+                    shapes.draw(&mut rpass, &self.shape_batch);
+                }
+                Scene::Image { pipeline, bindings, .. } => {
+                    rpass.set_pipeline(pipeline);
+                    bindings.set(&mut rpass);
+                    rpass.draw(0..3, 0..1);
+                }
+                Scene::Graph { graph } => graph.draw_screen(&mut rpass),
+            }
+        }
+
+        // 5. POST-PROCESSING (Into the frame)
+        if let Some(post) = &self.post {
+            post.encode(&mut encoder, view);
+        }
+
+        // 6. DRAW ERRORS (If the last build failed)
+        self.overlay.draw(&mut encoder, view);
 
-            // 2. DRAW SPRITES (Rain)
-            rpass.set_pipeline(&self.render_pipeline);
-            self.sprite_batch.draw(&mut rpass);
+        encoder
+    }
+}
+
+impl Runtime for HangaEngine {
+    async fn new(window: Arc<winit::window::Window>, project_bytes: &[u8]) -> anyhow::Result<Self> {
+        let (device, queue, surface, config, pipeline_cache) = Self::init_wgpu(window.clone()).await;
+        Self::from_parts(Target::Window { window, surface }, device, queue, config, pipeline_cache, project_bytes)
+    }
+
+    fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+        if new_size.width > 0 && new_size.height > 0 {
+            self.config.width = new_size.width;
+            self.config.height = new_size.height;
+            match &mut self.target {
+                Target::Window { surface, .. } => surface.configure(&self.device, &self.config),
+                Target::Headless { texture } => *texture = Self::create_offscreen_texture(&self.device, &self.config),
+            }
+            self.depth_texture = Self::create_depth_texture(&self.device, &self.config, &self.scene);
+            if let Scene::Graph { graph } = &mut self.scene {
+                if let Err(e) = graph.resize(&self.device, &self.queue, self.config.width, self.config.height) {
+                    self.show_error(e);
+                }
+            }
+            if let Some(post) = &mut self.post {
+                if let Err(e) = post.resize(&self.device, &self.queue, &self.uniform_buffer, self.config.width, self.config.height) {
+                    self.show_error(e);
+                }
+            }
         }
+    }
+
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        self.update_uniform();
+        self.overlay.prepare(&self.device, &self.queue, self.config.width, self.config.height);
+
+        match &self.target {
+            Target::Window { surface, .. } => {
+                let output = surface.get_current_texture()?;
+                let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+                let mut encoder = self.encode_frame(&view);
+
+                // 5. CAPTURE (Only when a screenshot was requested)
+                self.screenshots.encode_copy(&self.device, &mut encoder, &output.texture);
+
+                self.submit(encoder);
+                self.screenshots.submitted();
+                output.present();
+            }
+            Target::Headless { texture } => {
+                let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+                let mut encoder = self.encode_frame(&view);
+                self.screenshots.encode_copy(&self.device, &mut encoder, texture);
 
-        self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
+                self.submit(encoder);
+                self.screenshots.submitted();
+            }
+        }
+
+        // Hand back any captures the GPU has finished with
+        self.screenshots.poll(&self.device);
 
         Ok(())
     }
+
+    fn process_input(&mut self, event: &winit::event::Event<()>) -> bool {
+        use winit::event::{ElementState, Event, MouseButton, WindowEvent};
+
+        match event {
+            Event::WindowEvent { event: WindowEvent::CursorMoved { position, .. }, .. } => {
+                // Flip Y so the origin matches `frag_coord` in image shaders
+                self.update_cursor([position.x as f32, self.config.height as f32 - position.y as f32]);
+                true
+            }
+            Event::WindowEvent { event: WindowEvent::MouseInput { state, button: MouseButton::Left, .. }, .. } => {
+                match state {
+                    ElementState::Pressed => self.update_mouse_press(),
+                    ElementState::Released => self.update_mouse_release(),
+                }
+                true
+            }
+            _ => false,
+        }
+    }
 }
@@ -1,51 +1,302 @@
-use gyo_core::{GyoshoFile, Manifest, AssetKind};
+use crate::pipeline_2d::InstanceRaw;
+use gyo_core::{GyoshoFile, Manifest, AssetEntry, AssetKind, PipelineState, ShaderStage};
+use gyo_core::graph::{self, Pass, PassInput};
+use gyo_core::post::{self, Effect};
+use gyo_core::particles;
+use gyo_core::shader::CHANNEL_GROUP;
+use gyo_core::texture::Texture;
+use gyo_core::reflect::{self, BindingCheck};
+use gyo_core::validate::{self, ShaderLanguage, ValidatedShader};
+use gyo_core::vector::{self, Path};
 use anyhow::{Context, Result};
-use std::io::{Cursor, Read};
+use std::hash::{Hash, Hasher};
+use std::io::Cursor;
 use binrw::BinRead; // <--- FIX: This was missing!
 
 pub struct LoadedProject {
     pub manifest: Manifest,
+    /// Text of the main shader (Empty for SPIR-V)
     pub source_code: String,
+    /// The main shader (The screen pass of a render graph, or else the first
+    /// shader asset with a pipeline declaration, or else the first with an
+    /// entry point), already validated by naga with its imports
+    pub shader: ValidatedShader,
+    /// How the main shader is drawn (Its entry points are already applied
+    /// to `shader`)
+    pub pipeline: PipelineState,
+    /// `TexturePng` assets by id, in manifest order
+    pub textures: Vec<(String, Texture)>,
+    /// Channels sampled without a texture and textures nothing samples
+    pub bindings: BindingCheck,
+    /// The variant the shaders were preprocessed for (`None` if the
+    /// project declares none)
+    pub variant: Option<String>,
+    /// The render graph in the order the passes run (Empty without one)
+    pub passes: Vec<LoadedPass>,
+    /// Post-processing effects in order (Empty without any)
+    pub effects: Vec<LoadedEffect>,
+    /// The shader of a `Background::Shader` (See `manifest.background`)
+    pub background_shader: Option<ValidatedShader>,
+    /// `SvgPath` assets by id, in manifest order
+    pub paths: Vec<(String, Path)>,
+}
+
+/// Hashes of what each pipeline of a project is built from (See
+/// [`LoadedProject::pipeline_fingerprints`]).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PipelineFingerprints {
+    /// The main shader and its pipeline state
+    pub main: u64,
+    /// The background and its shader
+    pub background: u64,
+    /// Each pass of the render graph, in order
+    pub passes: Vec<u64>,
+    /// Each post-processing effect, in order
+    pub effects: Vec<u64>,
+}
+
+/// A pass of the render graph with its validated shader.
+pub struct LoadedPass {
+    pub pass: Pass,
+    pub shader: ValidatedShader,
+    pub pipeline: PipelineState,
+}
+
+/// A post-processing effect with its validated shader.
+pub struct LoadedEffect {
+    pub effect: Effect,
+    /// The shader of a `Custom` effect
+    pub shader: Option<ValidatedShader>,
+    /// The LUT of a `ColorGrade` effect, and its size
+    pub lut: Option<(Texture, u32)>,
+}
+
+impl LoadedProject {
+    /// Hash of everything the scene's pipelines are built from, to name
+    /// pipeline caches. The same from one build to the next.
+    pub fn fingerprint(&self) -> u64 {
+        stable_hash(self.pipeline_fingerprints())
+    }
+
+    /// Hash of what each pipeline is built from, to rebuild only the
+    /// pipelines a reload changed
+    pub fn pipeline_fingerprints(&self) -> PipelineFingerprints {
+        let background_shader = self.background_shader.as_ref().map(ValidatedShader::fingerprint);
+        PipelineFingerprints {
+            main: stable_hash((self.shader.fingerprint(), &self.pipeline)),
+            background: stable_hash((&self.manifest.background, background_shader)),
+            passes: self.passes.iter().map(LoadedPass::fingerprint).collect(),
+            effects: self.effects.iter().map(LoadedEffect::fingerprint).collect(),
+        }
+    }
+
+    /// Hash of the `TexturePng` assets (Channels, atlases, graph inputs and
+    /// LUTs), to detect changed images the pipelines can keep drawing
+    pub fn texture_fingerprint(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        for (id, texture) in &self.textures {
+            (id, texture.width, texture.height, &texture.data).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+impl LoadedPass {
+    /// Hash of what the pass's pipeline is built from
+    pub fn fingerprint(&self) -> u64 {
+        stable_hash((&self.pass, self.shader.fingerprint(), &self.pipeline))
+    }
+}
+
+impl LoadedEffect {
+    /// Hash of what the effect's pipelines are built from
+    pub fn fingerprint(&self) -> u64 {
+        stable_hash((&self.effect, self.shader.as_ref().map(ValidatedShader::fingerprint)))
+    }
+}
+
+/// SipHash 1-3 with fixed keys: Unlike `DefaultHasher`, the same in every build
+fn stable_hash(value: impl Hash) -> u64 {
+    let mut hasher = siphasher::sip::SipHasher13::new();
+    value.hash(&mut hasher);
+    hasher.finish()
 }
 
 pub struct ProjectLoader;
 
 impl ProjectLoader {
+    /// Parses a `.gyo` file and validates every shader asset with naga.
+    ///
+    /// Invalid shaders, and shaders expecting bindings the runtime does not
+    /// provide, fail with a `validate::ShaderError` (Use `error.downcast_ref`)
+    /// listing a diagnostic per problem.
     pub fn load(bytes: &[u8]) -> Result<LoadedProject> {
+        Self::load_variant(bytes, None)
+    }
+
+    /// Like [`ProjectLoader::load`], preprocessing the shaders for the
+    /// variant called `variant` (The default one for `None`).
+    pub fn load_variant(bytes: &[u8], variant: Option<&str>) -> Result<LoadedProject> {
         // 1. Parse the Container
         let mut cursor = Cursor::new(bytes);
         // This .read() call requires binrw::BinRead to be in scope
         let file = GyoshoFile::read(&mut cursor)
             .context("Failed to parse GYO header")?;
         
-        let manifest: Manifest = bincode::deserialize(&file.manifest_bytes)
+        let manifest: Manifest = file.manifest()
             .context("Failed to deserialize Manifest")?;
+        let variant = manifest.variant(variant)?.cloned();
 
         // 2. Decompress Payload
-        let mut decoder = zstd::Decoder::new(Cursor::new(&file.compressed_payload))?;
-        let mut decompressed_payload = Vec::new();
-        decoder.read_to_end(&mut decompressed_payload)
+        let decompressed_payload = file.decompress_payload()
             .context("Failed to decompress GYO payload")?;
 
-        // 3. Extract Source Code
-        let source_code_asset = manifest.assets.iter()
-            .find(|a| matches!(a.kind, AssetKind::SumiSource))
-            .context("No Source Code found in project manifest")?;
+        let asset_bytes = |asset: &AssetEntry| -> Result<&[u8]> { asset.bytes(&decompressed_payload) };
 
-        let start = source_code_asset.offset as usize;
-        let end = start + source_code_asset.size as usize;
-        
-        if end > decompressed_payload.len() {
-            anyhow::bail!("Corrupt file: Asset defined outside payload bounds");
+        // 3. Validate every shader up front
+        let shader_assets = manifest.assets.iter()
+            .filter(|a| ShaderLanguage::of(&a.kind).is_some())
+            .map(|asset| Ok((asset, asset_bytes(asset)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let mut shaders = validate::validate_all(shader_assets.iter().copied(), variant.as_ref())?;
+        validate::apply_pipelines(&mut shaders, &manifest.pipelines)?;
+
+        // 4. Check the bindings they expect against the assets (Passes of a
+        // render graph get their channels from their inputs, effects read
+        // the frame as channel 0)
+        let (effect_shaders, checked): (Vec<_>, Vec<_>) = shaders.iter()
+            .map(|(asset, shader)| (*asset, shader))
+            .partition(|(asset, _)| post::is_effect_shader(&manifest.effects, &asset.id));
+        let mut bindings = reflect::check_bindings(&checked, &manifest.assets)?;
+        if !manifest.passes.is_empty() {
+            bindings = graph_bindings(&manifest, &shaders);
+        }
+        if !manifest.effects.is_empty() {
+            let effect_bindings = reflect::check_bindings(&effect_shaders, &[])?;
+            bindings.missing_channels.extend(effect_bindings.missing_channels.into_iter().filter(|(_, channel)| *channel > 0));
+            bindings.unused_textures.retain(|id| !post::is_lut(&manifest.effects, id));
         }
+        for warning in bindings.warnings() {
+            println!("⚠️ {}", warning);
+        }
+
+        // 5. Schedule the render graph
+        let passes = if manifest.passes.is_empty() {
+            Vec::new()
+        } else {
+            let order = graph::schedule(&manifest.passes, &manifest.assets)?;
+            graph::check_shaders(&manifest.passes, &shaders)?;
+            order.into_iter()
+                .map(|i| {
+                    let pass = manifest.passes[i].clone();
+                    let (_, shader) = shaders.iter().find(|(asset, _)| asset.id == pass.asset_id).unwrap();
+                    let pipeline = manifest.pipeline(&pass.asset_id)
+                        .cloned()
+                        .unwrap_or_else(|| PipelineState::new(pass.asset_id.as_str()));
+                    LoadedPass { pass, shader: shader.clone(), pipeline }
+                })
+                .collect()
+        };
 
-        let source_bytes = &decompressed_payload[start..end];
-        let source_code = String::from_utf8(source_bytes.to_vec())
-            .context("Source code is not valid UTF-8")?;
+        // 6. Extract Source Code of the main shader: The screen pass, or the
+        // first declaring a pipeline, or else the first with an entry point
+        // (Imported libraries have none), skipping effect and background
+        // shaders. Empty for binaries
+        post::check(&manifest.effects, &manifest.assets)?;
+        post::check_shaders(&manifest.effects, &shaders)?;
+        manifest.background.check(&manifest.assets)?;
+        manifest.background.check_shader(&shaders)?;
+        particles::check(&manifest.particles, &manifest.assets)?;
+        let background_shader = manifest.background.asset_id()
+            .and_then(|id| shaders.iter().find(|(asset, _)| asset.id == id))
+            .map(|(_, shader)| shader.clone());
+        let drawn_elsewhere = |asset: &AssetEntry| post::is_effect_shader(&manifest.effects, &asset.id)
+            || manifest.background.asset_id() == Some(asset.id.as_str());
+        let mut effects: Vec<LoadedEffect> = manifest.effects.iter()
+            .map(|effect| LoadedEffect {
+                effect: effect.clone(),
+                shader: match effect {
+                    Effect::Custom { asset_id } => shaders.iter()
+                        .find(|(asset, _)| asset.id == *asset_id)
+                        .map(|(_, shader)| shader.clone()),
+                    _ => None,
+                },
+                lut: None,
+            })
+            .collect();
+
+        anyhow::ensure!(!shaders.is_empty(), "No Source Code found in project manifest");
+        let main = manifest.passes.iter()
+            .find(|pass| pass.target.is_none())
+            .and_then(|pass| shaders.iter().position(|(asset, _)| asset.id == pass.asset_id))
+            .or_else(|| manifest.pipelines.iter()
+                .find_map(|pipeline| shaders.iter().position(|(asset, _)| asset.id == pipeline.asset_id && !drawn_elsewhere(asset))))
+            .or_else(|| shaders.iter().position(|(asset, shader)| !shader.module.entry_points.is_empty() && !drawn_elsewhere(asset)))
+            .unwrap_or(0);
+        let (main_asset, shader) = shaders.swap_remove(main);
+        let pipeline = manifest.pipeline(&main_asset.id)
+            .cloned()
+            .unwrap_or_else(|| PipelineState::new(main_asset.id.as_str()));
+        // Sprite shaders read `InstanceRaw` as their vertex buffer
+        if manifest.passes.is_empty() && !shader.is_image() {
+            let entry_point = shader.entry_point(ShaderStage::Vertex).unwrap_or("vs_main");
+            reflect::check_vertex_inputs(main_asset, &shader, entry_point, InstanceRaw::desc().attributes)?;
+        }
+        let source_code = match ShaderLanguage::of(&main_asset.kind) {
+            Some(ShaderLanguage::SpirV) => String::new(),
+            _ => String::from_utf8(asset_bytes(main_asset)?.to_vec())?,
+        };
+
+        // 7. Decode Textures (In manifest order: `iChannel0` is the first)
+        let textures = manifest.assets.iter()
+            .filter(|a| matches!(a.kind, AssetKind::TexturePng))
+            .map(|asset| {
+                let texture = Texture::read_png(asset_bytes(asset)?)
+                    .with_context(|| format!("Failed to decode texture {}", asset.id))?;
+                Ok((asset.id.clone(), texture))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for effect in &mut effects {
+            let Effect::ColorGrade { lut } = &effect.effect else { continue };
+            let (id, texture) = textures.iter().find(|(id, _)| id == lut).expect("LUTs are checked by `post::check`");
+            effect.lut = Some((texture.clone(), post::lut_size(id, texture)?));
+        }
+
+        // 8. Read Vector Paths
+        let paths = vector::read_paths(&manifest.assets, asset_bytes)?;
 
         Ok(LoadedProject {
             manifest,
             source_code,
+            shader,
+            pipeline,
+            textures,
+            bindings,
+            variant: variant.map(|v| v.name),
+            passes,
+            effects,
+            background_shader,
+            paths,
         })
     }
 }
+
+/// Channels the passes sample without an input, and textures no pass reads
+fn graph_bindings(manifest: &Manifest, shaders: &[(&AssetEntry, ValidatedShader)]) -> BindingCheck {
+    let mut check = BindingCheck::default();
+    for pass in &manifest.passes {
+        let Some((_, shader)) = shaders.iter().find(|(asset, _)| asset.id == pass.asset_id) else { continue };
+        for resource in reflect::resources(shader) {
+            let channel = resource.binding / 2;
+            if resource.group == CHANNEL_GROUP && resource.binding % 2 == 0 && channel as usize >= pass.inputs.len() {
+                check.missing_channels.push((pass.asset_id.clone(), channel));
+            }
+        }
+    }
+    check.unused_textures = manifest.assets.iter()
+        .filter(|a| matches!(a.kind, AssetKind::TexturePng))
+        .filter(|a| !manifest.passes.iter().any(|p| p.inputs.contains(&PassInput::Texture(a.id.clone()))))
+        .map(|a| a.id.clone())
+        .collect();
+    check
+}
@@ -0,0 +1,222 @@
+//! On-screen error overlay.
+//!
+//! When a shader fails to build, the diagnostic text is rasterized on the CPU
+//! with the built-in bitmap font and drawn over the frame, so artists see what
+//! broke without looking at a terminal.
+
+use crate::font::{self, GLYPH_HEIGHT, GLYPH_WIDTH};
+use gyo_core::texture::Texture;
+
+/// Distance of the text panel from the frame edges, in pixels
+const MARGIN: u32 = 8;
+/// Space between the panel border and the text, in pixels
+const PADDING: u32 = 6;
+
+const PANEL_COLOR: [u8; 4] = [0, 0, 0, 210];
+const TITLE_COLOR: [u8; 4] = [255, 110, 110, 255];
+const TEXT_COLOR: [u8; 4] = [235, 235, 235, 255];
+
+pub(crate) struct ErrorOverlay {
+    pipeline: wgpu::RenderPipeline,
+    message: Option<String>,
+    /// Rasterized message and the target size it was drawn for
+    image: Option<(wgpu::BindGroup, [u32; 2])>,
+}
+
+impl ErrorOverlay {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("overlay.wgsl"));
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Error Overlay Pipeline"),
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING), // Text panel over the frame
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1, mask: !0, alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        Self { pipeline, message: None, image: None }
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// Shows `message` from the next frame on, `None` hides the overlay.
+    pub fn set_message(&mut self, message: Option<String>) {
+        if self.message != message {
+            self.message = message;
+            self.image = None;
+        }
+    }
+
+    /// Rasterizes and uploads the message if it or the target size changed.
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, width: u32, height: u32) {
+        let Some(message) = &self.message else { return };
+        if self.image.as_ref().is_some_and(|(_, size)| *size == [width, height]) {
+            return;
+        }
+
+        let image = rasterize(message, width, height);
+        let size = wgpu::Extent3d { width, height, depth_or_array_layers: 1 };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Error Overlay Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb, // Colors above are display values
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &image.data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * width),
+                rows_per_image: Some(height),
+            },
+            size,
+        );
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Error Overlay Bind Group"),
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            }],
+        });
+        self.image = Some((bind_group, [width, height]));
+    }
+
+    /// Draws the prepared message over `view`, if there is one.
+    pub fn draw(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        let (Some(_), Some((bind_group, _))) = (&self.message, &self.image) else { return };
+
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Error Overlay Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load, // Keep the frame underneath
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, bind_group, &[]);
+        rpass.draw(0..3, 0..1);
+    }
+}
+
+/// Lays `message` out in a panel at the top-left of a `width` x `height` image.
+/// Long lines are wrapped, lines that do not fit are dropped with a marker.
+fn rasterize(message: &str, width: u32, height: u32) -> Texture {
+    // Double the glyphs on large (HiDPI) targets
+    let scale = if height >= 1440 { 2 } else { 1 };
+    let (cell_w, cell_h) = (GLYPH_WIDTH * scale, GLYPH_HEIGHT * scale);
+
+    let inner_w = width.saturating_sub(2 * (MARGIN + PADDING));
+    let inner_h = height.saturating_sub(2 * (MARGIN + PADDING));
+    let columns = (inner_w / cell_w).max(1) as usize;
+    let rows = (inner_h / cell_h).max(1) as usize;
+
+    let mut lines = wrap(message, columns);
+    if lines.len() > rows {
+        lines.truncate(rows.saturating_sub(1));
+        lines.push("...".to_string());
+    }
+
+    let mut image = Texture::new(width, height, vec![0; (width * height * 4) as usize]);
+    let text_w = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) as u32 * cell_w;
+    let panel = [
+        MARGIN,
+        MARGIN,
+        (text_w + 2 * PADDING).min(width.saturating_sub(MARGIN)),
+        (lines.len() as u32 * cell_h + 2 * PADDING).min(height.saturating_sub(MARGIN)),
+    ];
+    fill(&mut image, panel, PANEL_COLOR);
+
+    for (row, line) in lines.iter().enumerate() {
+        let color = if row == 0 { TITLE_COLOR } else { TEXT_COLOR };
+        let y = MARGIN + PADDING + row as u32 * cell_h;
+        for (column, c) in line.chars().enumerate() {
+            let x = MARGIN + PADDING + column as u32 * cell_w;
+            draw_glyph(&mut image, font::glyph(c), x, y, scale, color);
+        }
+    }
+
+    image
+}
+
+/// Splits `message` into lines of at most `columns` characters (Tabs become 4 spaces)
+fn wrap(message: &str, columns: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for line in message.lines() {
+        let chars: Vec<char> = line.replace('\t', "    ").chars().collect();
+        if chars.is_empty() {
+            lines.push(String::new());
+        }
+        lines.extend(chars.chunks(columns).map(|chunk| chunk.iter().collect()));
+    }
+    lines
+}
+
+fn fill(image: &mut Texture, [x, y, w, h]: [u32; 4], color: [u8; 4]) {
+    for py in y..(y + h).min(image.height) {
+        for px in x..(x + w).min(image.width) {
+            let i = ((py * image.width + px) * 4) as usize;
+            image.data[i..i + 4].copy_from_slice(&color);
+        }
+    }
+}
+
+fn draw_glyph(image: &mut Texture, glyph: &[u8; 16], x: u32, y: u32, scale: u32, color: [u8; 4]) {
+    for (gy, bits) in glyph.iter().enumerate() {
+        for gx in 0..GLYPH_WIDTH {
+            if bits & (0x80 >> gx) != 0 {
+                fill(image, [x + gx * scale, y + gy as u32 * scale, scale, scale], color);
+            }
+        }
+    }
+}
@@ -0,0 +1,16 @@
+// Error overlay: Copies a pre-rendered text image (Same size as the target) over the frame
+
+@group(0) @binding(0)
+var overlay: texture_2d<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> @builtin(position) vec4<f32> {
+    let x = f32(i32((in_vertex_index << 1u) & 2u));
+    let y = f32(i32(in_vertex_index & 2u));
+    return vec4(2.0 * vec2(x, y) - vec2(1.0), 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    return textureLoad(overlay, vec2<i32>(position.xy), 0);
+}
@@ -0,0 +1,276 @@
+//! GPU particles (See `gyo_core::particles`).
+//!
+//! A compute pass (`particles.wgsl`) advances every emitter's particles in
+//! a storage buffer and writes them out as `InstanceRaw`s into a second
+//! one, which the sprite pipelines draw as their instance buffer. Nothing
+//! goes through the CPU but the emitter settings.
+//!
+//! Particles follow the shader clock: They freeze while it is paused, and
+//! restart when it goes back. Long frames are simulated in steps of at
+//! most [`MAX_STEP`] seconds, so a frame rendered after a jump in time looks
+//! the same as one reached at a steady frame rate.
+
+use crate::pipeline_2d::InstanceRaw;
+use gyo_core::particles::{Emitter, MAX_PARTICLES};
+use std::hash::{Hash, Hasher};
+use std::mem;
+
+/// Longest simulation step in seconds
+pub const MAX_STEP: f32 = 1.0 / 60.0;
+
+/// Most steps a frame simulates (Longer frames lose the rest)
+const MAX_STEPS: u32 = 600;
+
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Settings of a frame (Matches `Params` in `particles.wgsl`)
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    area: [f32; 4],
+    velocity_min: [f32; 2],
+    velocity_max: [f32; 2],
+    gravity: [f32; 2],
+    wind: [f32; 2],
+    color: [f32; 4],
+    color_end: [f32; 4],
+    size: [f32; 2],
+    lifetime: [f32; 2],
+    scale: [f32; 2],
+    drag: f32,
+    noise: f32,
+    noise_scale: f32,
+    layer: f32,
+    time: f32,
+    step: f32,
+    steps: u32,
+    spawned: u32,
+    cursor: u32,
+    emitted: u32,
+    capacity: u32,
+    align: u32,
+    salt: u32,
+    _padding: u32,
+}
+
+/// Size of a `Particle` in `particles.wgsl`
+const PARTICLE_SIZE: u64 = 32;
+
+/// An emitter and its buffers.
+struct GpuEmitter {
+    emitter: Emitter,
+    /// Declared in the manifest (Replaced on reload) rather than in code
+    declared: bool,
+    /// `emitter.capacity` when the buffers were made (Within the limits)
+    capacity: u32,
+    params: Params,
+    params_buffer: wgpu::Buffer,
+    particles: wgpu::Buffer,
+    instances: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    /// Particles spawned so far, and the fraction of one owed
+    emitted: u64,
+    owed: f64,
+}
+
+#[derive(Default)]
+pub(crate) struct Particles {
+    /// Made with the first emitter (Backends without compute shaders can
+    /// still draw projects without particles)
+    pipeline: Option<wgpu::ComputePipeline>,
+    emitters: Vec<GpuEmitter>,
+    /// Shader time of the last frame, `None` before the first
+    time: Option<f32>,
+    /// Empties the buffers this frame (The clock went back)
+    reset: bool,
+}
+
+impl Particles {
+    /// Adds an emitter, or replaces the one of the same name (Keeping its
+    /// particles if the capacity is unchanged)
+    pub fn add(&mut self, device: &wgpu::Device, emitter: Emitter, declared: bool) {
+        match self.emitters.iter().position(|e| e.emitter.name == emitter.name) {
+            Some(index) => {
+                self.emitters[index].emitter = emitter;
+                self.emitters[index].declared = declared;
+            }
+            None => {
+                let gpu = self.create(device, emitter, declared);
+                self.emitters.push(gpu);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let count = self.emitters.len();
+        self.emitters.retain(|e| e.emitter.name != name);
+        self.emitters.len() < count
+    }
+
+    /// Swaps the declared emitters for those of a reloaded manifest
+    pub fn set_declared(&mut self, device: &wgpu::Device, emitters: &[Emitter]) {
+        self.emitters.retain(|e| !e.declared || emitters.iter().any(|d| d.name == e.emitter.name));
+        for emitter in emitters {
+            let unchanged = self.emitters.iter().any(|e| e.declared && e.emitter == *emitter);
+            if !unchanged {
+                self.add(device, emitter.clone(), true);
+            }
+        }
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Emitter> {
+        self.emitters.iter_mut().find(|e| e.emitter.name == name).map(|e| &mut e.emitter)
+    }
+
+    fn create(&mut self, device: &wgpu::Device, emitter: Emitter, declared: bool) -> GpuEmitter {
+        let pipeline = self.pipeline.get_or_insert_with(|| {
+            let shader = device.create_shader_module(wgpu::include_wgsl!("particles.wgsl"));
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("Particle Pipeline"),
+                layout: None,
+                module: &shader,
+                entry_point: "cs_main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                cache: None,
+            })
+        });
+        let capacity = emitter.capacity.clamp(1, MAX_PARTICLES);
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Params"),
+            size: mem::size_of::<Params>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let particles = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("Particles `{}`", emitter.name)),
+            size: capacity as u64 * PARTICLE_SIZE,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        // Zeroed: Empty slots are dead, and drawn without area
+        let instances = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("Particle Instances `{}`", emitter.name)),
+            size: capacity as u64 * mem::size_of::<InstanceRaw>() as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Particle Bind Group"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: particles.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: instances.as_entire_binding() },
+            ],
+        });
+        GpuEmitter {
+            emitter, declared, capacity,
+            params: Params::default(),
+            params_buffer, particles, instances, bind_group,
+            emitted: 0,
+            owed: 0.0,
+        }
+    }
+
+    /// Works out the steps from the last frame's shader time to `time`, and
+    /// uploads what each emitter spawns over them
+    pub fn update(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, time: f32) {
+        // 1. Buffers of emitters whose capacity changed
+        for i in 0..self.emitters.len() {
+            if self.emitters[i].emitter.capacity.clamp(1, MAX_PARTICLES) != self.emitters[i].capacity {
+                let (emitter, declared) = (self.emitters[i].emitter.clone(), self.emitters[i].declared);
+                self.emitters[i] = self.create(device, emitter, declared);
+            }
+        }
+
+        // 2. The steps (None when the clock went back)
+        let elapsed = time - self.time.unwrap_or(time);
+        self.time = Some(time);
+        self.reset = elapsed < 0.0;
+        let steps = ((elapsed.max(0.0) / MAX_STEP).ceil() as u32).min(MAX_STEPS);
+        let step = if steps == 0 { 0.0 } else { (elapsed / steps as f32).min(MAX_STEP) };
+
+        // 3. What each emitter spawns
+        let start = time - step * steps as f32;
+        for gpu in &mut self.emitters {
+            if self.reset {
+                gpu.emitted = 0;
+                gpu.owed = 0.0;
+            }
+            let emitter = &gpu.emitter;
+            gpu.owed += emitter.rate as f64 * (step * steps as f32) as f64;
+            let spawned = gpu.owed.floor().min(u32::MAX as f64);
+            gpu.owed -= spawned;
+            let mut salt = std::collections::hash_map::DefaultHasher::new();
+            emitter.name.hash(&mut salt);
+
+            gpu.params = Params {
+                area: emitter.area,
+                velocity_min: emitter.velocity[0],
+                velocity_max: emitter.velocity[1],
+                gravity: emitter.forces.gravity,
+                wind: emitter.forces.wind,
+                color: emitter.color,
+                color_end: emitter.color_end,
+                size: emitter.size,
+                lifetime: emitter.lifetime,
+                scale: emitter.scale,
+                drag: emitter.forces.drag,
+                noise: emitter.forces.noise,
+                noise_scale: emitter.forces.noise_scale,
+                layer: emitter.layer,
+                time: start,
+                step,
+                steps,
+                spawned: spawned as u32,
+                cursor: (gpu.emitted % gpu.capacity as u64) as u32,
+                emitted: gpu.emitted as u32,
+                capacity: gpu.capacity,
+                align: emitter.align as u32,
+                salt: salt.finish() as u32,
+                _padding: 0,
+            };
+            gpu.emitted += spawned as u64;
+            queue.write_buffer(&gpu.params_buffer, 0, bytemuck::bytes_of(&gpu.params));
+        }
+    }
+
+    /// Simulates the steps of [`Particles::update`]
+    pub fn encode(&self, encoder: &mut wgpu::CommandEncoder) {
+        let Some(pipeline) = &self.pipeline else { return };
+        if self.reset {
+            for gpu in &self.emitters {
+                encoder.clear_buffer(&gpu.particles, 0, None);
+                encoder.clear_buffer(&gpu.instances, 0, None);
+            }
+        }
+        let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Particle Pass"),
+            timestamp_writes: None,
+        });
+        cpass.set_pipeline(pipeline);
+        for gpu in self.emitters.iter().filter(|gpu| gpu.params.steps > 0) {
+            cpass.set_bind_group(0, &gpu.bind_group, &[]);
+            cpass.dispatch_workgroups(gpu.capacity.div_ceil(WORKGROUP_SIZE), 1, 1);
+        }
+    }
+
+    /// Each emitter's atlas and instances, back to front (Emitters in the
+    /// same layer in the order they were added)
+    pub fn batches(&self) -> Vec<(Option<&str>, ParticleInstances<'_>)> {
+        let mut emitters: Vec<&GpuEmitter> = self.emitters.iter().collect();
+        emitters.sort_by(|a, b| b.emitter.layer.total_cmp(&a.emitter.layer));
+        emitters.into_iter().map(|gpu| (gpu.emitter.atlas.as_deref(), ParticleInstances(gpu))).collect()
+    }
+}
+
+/// The instance buffer of an emitter, as drawn by the sprite pipelines.
+pub(crate) struct ParticleInstances<'a>(&'a GpuEmitter);
+
+impl<'a> ParticleInstances<'a> {
+    pub fn draw(&self, rpass: &mut wgpu::RenderPass<'a>) {
+        rpass.set_vertex_buffer(0, self.0.instances.slice(..));
+        // Draw 6 vertices (1 Quad) per slot: Dead particles have no area
+        rpass.draw(0..6, 0..self.0.capacity);
+    }
+}
@@ -0,0 +1,144 @@
+// crates/hanga/src/particles.wgsl
+// Advances the particles of an emitter by `steps` steps, spawning the new
+// ones along the way, then writes them out as sprite instances.
+
+struct Params {
+    area: vec4<f32>,         // x, y, width, height
+    velocity_min: vec2<f32>,
+    velocity_max: vec2<f32>,
+    gravity: vec2<f32>,
+    wind: vec2<f32>,
+    color: vec4<f32>,
+    color_end: vec4<f32>,
+    size: vec2<f32>,
+    lifetime: vec2<f32>,     // Shortest, longest
+    scale: vec2<f32>,        // Smallest, largest
+    drag: f32,
+    noise: f32,
+    noise_scale: f32,
+    layer: f32,
+    time: f32,               // At the start of the first step
+    step: f32,               // Seconds per step
+    steps: u32,
+    spawned: u32,            // Particles spawned over all the steps
+    cursor: u32,             // Ring slot of the first one
+    emitted: u32,            // Particles spawned before (Wrapping)
+    capacity: u32,
+    align: u32,
+    salt: u32,               // Tells emitters apart
+};
+
+struct Particle {
+    pos: vec2<f32>,
+    vel: vec2<f32>,
+    age: f32,
+    lifetime: f32,           // Dead once `age` reaches it (0 for empty slots)
+    scale: f32,
+    _padding: f32,
+};
+
+// Matches `InstanceRaw` (Scalars only, so the stride is 76 bytes)
+struct Instance {
+    pos_x: f32, pos_y: f32, pos_z: f32,
+    size_x: f32, size_y: f32,
+    uv_x: f32, uv_y: f32, uv_w: f32, uv_h: f32,
+    r: f32, g: f32, b: f32, a: f32,
+    pivot_x: f32, pivot_y: f32,
+    t0: f32, t1: f32, t2: f32, t3: f32,
+};
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read_write> particles: array<Particle>;
+@group(0) @binding(2) var<storage, read_write> instances: array<Instance>;
+
+// PCG hash
+fn hash(x: u32) -> u32 {
+    let state = x * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn random(seed: ptr<function, u32>) -> f32 {
+    *seed = hash(*seed);
+    return f32(*seed) / 4294967295.0;
+}
+
+fn spawn(ordinal: u32) -> Particle {
+    var seed = hash(ordinal ^ params.salt);
+    var p: Particle;
+    p.pos = params.area.xy + vec2<f32>(random(&seed), random(&seed)) * params.area.zw;
+    p.vel = mix(params.velocity_min, params.velocity_max, vec2<f32>(random(&seed), random(&seed)));
+    p.age = 0.0;
+    p.lifetime = mix(params.lifetime.x, params.lifetime.y, random(&seed));
+    p.scale = mix(params.scale.x, params.scale.y, random(&seed));
+    return p;
+}
+
+// Smooth swirls, roughly -1..1 on each axis
+fn turbulence(p: vec2<f32>, t: f32) -> vec2<f32> {
+    let q = p / params.noise_scale;
+    return vec2<f32>(
+        sin(q.y * 1.7 + t) + 0.5 * sin(q.y * 3.1 - q.x * 1.3 - t * 1.3),
+        cos(q.x * 1.9 - t * 0.7) + 0.5 * cos(q.x * 2.9 + q.y * 1.1 + t),
+    ) / 1.5;
+}
+
+fn integrate(p: ptr<function, Particle>, t: f32) {
+    let h = params.step;
+    let accel = params.gravity
+        + params.drag * (params.wind - (*p).vel)
+        + params.noise * turbulence((*p).pos, t);
+    (*p).vel += accel * h;
+    (*p).pos += (*p).vel * h;
+    (*p).age += h;
+}
+
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= params.capacity) { return; }
+
+    // 1. Is this slot respawned? The newest of the spawned particles that
+    // land on it wins, at its share of the steps
+    var p = particles[i];
+    let slot = (i + params.capacity - params.cursor) % params.capacity;
+    var spawn_step = params.steps;
+    var ordinal = 0u;
+    if (slot < params.spawned) {
+        ordinal = slot + params.capacity * ((params.spawned - 1u - slot) / params.capacity);
+        let share = f32(ordinal) * f32(params.steps) / f32(params.spawned);
+        spawn_step = min(u32(share), params.steps - 1u);
+    }
+
+    // 2. Simulate
+    for (var s = 0u; s < params.steps; s++) {
+        if (s == spawn_step) {
+            p = spawn(params.emitted + ordinal);
+        }
+        if (p.age < p.lifetime) {
+            integrate(&p, params.time + f32(s) * params.step);
+        }
+    }
+    particles[i] = p;
+
+    // 3. Draw (Dead particles get no area)
+    let alive = p.age < p.lifetime;
+    let life = select(1.0, p.age / p.lifetime, alive);
+    let color = mix(params.color, params.color_end, life);
+    let size = select(vec2<f32>(0.0), params.size * p.scale, alive);
+    var transform = vec4<f32>(1.0, 0.0, 0.0, 1.0);
+    let speed = length(p.vel);
+    if (params.align != 0u && speed > 0.0) {
+        // The sprite's height along the velocity
+        let d = p.vel / speed;
+        transform = vec4<f32>(d.y, -d.x, d.x, d.y);
+    }
+    instances[i] = Instance(
+        p.pos.x, p.pos.y, params.layer,
+        size.x, size.y,
+        0.0, 0.0, 1.0, 1.0,
+        color.r, color.g, color.b, color.a,
+        0.5, 0.5,
+        transform.x, transform.y, transform.z, transform.w,
+    );
+}
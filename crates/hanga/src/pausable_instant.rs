@@ -27,7 +27,18 @@ impl PausableInstant {
         }
     }
 
+    pub fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    /// Jumps to `duration`, keeping the paused state.
+    pub fn set_elapsed(&mut self, duration: Duration) {
+        self.instant = Instant::now();
+        self.stored_duration = duration;
+    }
+
     pub fn pause(&mut self) {
+        if self.is_paused { return; }
         self.is_paused = true;
 
         if let Some(duration) = self.stored_duration.checked_add(self.instant.elapsed()) {
@@ -43,7 +54,7 @@ impl PausableInstant {
         self.instant = Instant::now();
     }
 
-    fn reset(&mut self) {
+    pub fn reset(&mut self) {
         self.instant = Instant::now();
         self.stored_duration = Duration::ZERO;
     }
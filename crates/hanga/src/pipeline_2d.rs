@@ -1,21 +1,101 @@
 use bytemuck::{Pod, Zeroable};
+use std::ops::Range;
 
 /// The "Atom" of the 2D engine. 
-/// Matches the Buffer Layout in the WGSL Vertex Shader.
+/// Matches the Buffer Layout in the WGSL Vertex Shader (Checked when a
+/// project loads, see `gyo_core::reflect::check_vertex_inputs`).
+///
+/// A corner of the sprite ends up at `pos + transform * (corner - pivot) * size`:
+/// The pivot is placed at `pos`, and rotated, skewed or scaled around.
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
 pub struct InstanceRaw {
-    /// Position (x, y) + Depth/Layer (z)
+    /// Position (x, y) of the pivot + Depth/Layer (z, from 0 in front to 1
+    /// at the back)
     pub pos: [f32; 3], 
     /// Size (width, height)
     pub size: [f32; 2],
-    /// UV Offset (x, y) and Scale (w, h) for texture atlases
+    /// UV Offset (x, y) and Scale (w, h) of the region sampled from the
+    /// batch's atlas (Origin at its top-left)
     pub uv_rect: [f32; 4],
-    /// Color Tint (RGBA)
+    /// Color Tint (RGBA), multiplied with the atlas. Sprites with an alpha
+    /// below 1 are translucent
     pub color: [f32; 4],
+    /// Point of the sprite placed at `pos`, in fractions of its size
+    /// ([0, 0] is the top-left corner, [0.5, 0.5] the centre)
+    pub pivot: [f32; 2],
+    /// 2x2 matrix applied around the pivot, column-major:
+    /// x' = t[0] * x + t[2] * y, y' = t[1] * x + t[3] * y
+    pub transform: [f32; 4],
+}
+
+impl Default for InstanceRaw {
+    fn default() -> Self {
+        Self {
+            pos: [0.0; 3],
+            size: [0.0; 2],
+            uv_rect: [0.0, 0.0, 1.0, 1.0],
+            color: [1.0; 4],
+            pivot: [0.0; 2],
+            transform: Self::IDENTITY,
+        }
+    }
 }
 
 impl InstanceRaw {
+    /// `transform` that leaves sprites as they are
+    pub const IDENTITY: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+    /// A white sprite showing the whole atlas, its top-left corner at `pos`
+    pub fn new(pos: [f32; 3], size: [f32; 2]) -> Self {
+        Self { pos, size, ..Self::default() }
+    }
+
+    pub fn with_uv_rect(mut self, uv_rect: [f32; 4]) -> Self {
+        self.uv_rect = uv_rect;
+        self
+    }
+
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_pivot(mut self, pivot: [f32; 2]) -> Self {
+        self.pivot = pivot;
+        self
+    }
+
+    /// Rotates around the pivot by `radians` (Clockwise on screen, where y
+    /// points down), after the current transform
+    pub fn with_rotation(self, radians: f32) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        self.then([cos, sin, -sin, cos])
+    }
+
+    /// Shears around the pivot, after the current transform: `x` moves
+    /// points sideways per unit below the pivot, `y` down per unit right of it
+    pub fn with_skew(self, x: f32, y: f32) -> Self {
+        self.then([1.0, y, x, 1.0])
+    }
+
+    /// Applies a 2x3 affine transform `[a, b, c, d, tx, ty]` to the placed
+    /// sprite (x' = a * x + c * y + tx, y' = b * x + d * y + ty): Unlike the
+    /// other transforms, it moves `pos` too
+    pub fn with_affine(mut self, [a, b, c, d, tx, ty]: [f32; 6]) -> Self {
+        let [x, y, _] = self.pos;
+        self.pos[0] = a * x + c * y + tx;
+        self.pos[1] = b * x + d * y + ty;
+        self.then([a, b, c, d])
+    }
+
+    /// Applies `[a, b, c, d]` after `transform`
+    fn then(mut self, [a, b, c, d]: [f32; 4]) -> Self {
+        let [e, f, g, h] = self.transform;
+        self.transform = [a * e + c * f, b * e + d * f, a * g + c * h, b * g + d * h];
+        self
+    }
+
     pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
         use std::mem;
         wgpu::VertexBufferLayout {
@@ -24,55 +104,111 @@ impl InstanceRaw {
             attributes: &[
                 // Location 0: Pos (vec3)
                 wgpu::VertexAttribute {
-                    offset: 0,
+                    offset: mem::offset_of!(InstanceRaw, pos) as wgpu::BufferAddress,
                     shader_location: 0,
                     format: wgpu::VertexFormat::Float32x3,
                 },
                 // Location 1: Size (vec2)
                 wgpu::VertexAttribute {
-                    offset: mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    offset: mem::offset_of!(InstanceRaw, size) as wgpu::BufferAddress,
                     shader_location: 1,
                     format: wgpu::VertexFormat::Float32x2,
                 },
                 // Location 2: UV Rect (vec4)
                 wgpu::VertexAttribute {
-                    offset: (mem::size_of::<[f32; 3]>() + mem::size_of::<[f32; 2]>()) as wgpu::BufferAddress,
+                    offset: mem::offset_of!(InstanceRaw, uv_rect) as wgpu::BufferAddress,
                     shader_location: 2,
                     format: wgpu::VertexFormat::Float32x4,
                 },
                 // Location 3: Color (vec4)
                 wgpu::VertexAttribute {
-                    offset: (mem::size_of::<[f32; 3]>() + mem::size_of::<[f32; 2]>() + mem::size_of::<[f32; 4]>()) as wgpu::BufferAddress,
+                    offset: mem::offset_of!(InstanceRaw, color) as wgpu::BufferAddress,
                     shader_location: 3,
                     format: wgpu::VertexFormat::Float32x4,
                 },
+                // Location 4: Pivot (vec2)
+                wgpu::VertexAttribute {
+                    offset: mem::offset_of!(InstanceRaw, pivot) as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                // Location 5: Transform (vec4, the columns of a mat2x2)
+                wgpu::VertexAttribute {
+                    offset: mem::offset_of!(InstanceRaw, transform) as wgpu::BufferAddress,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
             ],
         }
     }
 }
 
+/// Elements per upload block: Blocks that changed since the last frame are
+/// uploaded, the others left alone
+const UPLOAD_BLOCK: usize = 64;
+
+/// Frames a batch may use at most a quarter of its capacity before it shrinks
+pub const SHRINK_AFTER_FRAMES: u32 = 120;
+
+/// Size of the chunks of the staging belt (Larger uploads get their own)
+const STAGING_CHUNK_SIZE: wgpu::BufferAddress = 64 * 1024;
+
+/// What the uploads of a batch cost (See [`SpriteBatch::metrics`]).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BatchMetrics {
+    /// Sprites (Or shapes, vertices...) the buffer holds
+    pub capacity: usize,
+    /// Bytes the last `prepare` uploaded, in how many ranges
+    pub uploaded_bytes: u64,
+    pub upload_ranges: usize,
+    /// How often the buffer was replaced by a larger or smaller one
+    pub grows: u32,
+    pub shrinks: u32,
+}
+
+/// Sprites drawn with one atlas (A `TexturePng` asset of the project).
+///
+/// Uploads go through an [`UploadBuffer`]: A staging belt and a ring of
+/// buffers, so writing a frame never waits on the GPU reading the last one,
+/// and only the blocks of sprites that changed.
 pub struct SpriteBatch {
     instances: Vec<InstanceRaw>,
-    instance_buffer: wgpu::Buffer,
-    capacity: usize,
+    gpu: UploadBuffer<InstanceRaw>,
+    /// Asset id of the atlas, `None` for flat colours
+    atlas: Option<String>,
+    /// The uploaded sprites, when drawn in layers: Opaque ones, then
+    /// translucent ones back to front
+    layered: Vec<InstanceRaw>,
+    /// How many of the uploaded sprites are opaque
+    opaque: usize,
 }
 
 impl SpriteBatch {
     pub fn new(device: &wgpu::Device, initial_capacity: usize) -> Self {
-        let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Sprite Instance Buffer"),
-            size: (initial_capacity * std::mem::size_of::<InstanceRaw>()) as wgpu::BufferAddress,
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
         Self {
             instances: Vec::with_capacity(initial_capacity),
-            instance_buffer,
-            capacity: initial_capacity,
+            gpu: UploadBuffer::new(device, "Sprite Instance Buffer", wgpu::BufferUsages::VERTEX, initial_capacity),
+            atlas: None,
+            layered: Vec::new(),
+            opaque: 0,
         }
     }
 
+    /// Samples the `TexturePng` asset called `atlas`
+    pub fn with_atlas(mut self, atlas: impl Into<String>) -> Self {
+        self.atlas = Some(atlas.into());
+        self
+    }
+
+    pub fn atlas(&self) -> Option<&str> {
+        self.atlas.as_deref()
+    }
+
+    /// Capacity, resizes and the last upload
+    pub fn metrics(&self) -> &BatchMetrics {
+        self.gpu.metrics()
+    }
+
     /// Add a sprite to the batch for this frame
     pub fn push(&mut self, instance: InstanceRaw) {
         self.instances.push(instance);
@@ -83,38 +219,501 @@ impl SpriteBatch {
         self.instances.clear();
     }
 
-    /// Uploads data to GPU. Resizes the buffer if we have more sprites than capacity.
-    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
-        if self.instances.is_empty() { return; }
-
-        // Resize buffer if needed (Double capacity strategy)
-        if self.instances.len() > self.capacity {
-            self.capacity = self.instances.len().max(self.capacity * 2);
-            println!("⚡ SpriteBatch Resizing: New Capacity = {}", self.capacity);
-            
-            self.instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some("Sprite Instance Buffer (Resized)"),
-                size: (self.capacity * std::mem::size_of::<InstanceRaw>()) as wgpu::BufferAddress,
-                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                mapped_at_creation: false,
-            });
-        }
+    /// Uploads the sprites that changed to the GPU, in push order. Resizes the
+    /// buffer if the batch outgrew it, or has long used little of it.
+    pub(crate) fn prepare(&mut self, device: &wgpu::Device, uploads: &mut FrameUploads) {
+        self.opaque = 0;
+        self.layered.clear();
+        self.gpu.upload(device, uploads, &self.instances);
+    }
 
-        // Upload data
-        queue.write_buffer(
-            &self.instance_buffer, 
-            0, 
-            bytemuck::cast_slice(&self.instances)
-        );
+    /// Like [`SpriteBatch::prepare`], ordering the sprites for drawing in
+    /// layers: Opaque sprites first (In push order, against the depth
+    /// buffer), then translucent ones back to front (Largest `z` first, ties
+    /// in push order). With `can_be_opaque` false (A translucent atlas, or
+    /// a blend mode that does not cover what is behind), all are translucent.
+    pub(crate) fn prepare_layers(&mut self, device: &wgpu::Device, uploads: &mut FrameUploads, can_be_opaque: bool) {
+        // A stable sort keeps ties in push order
+        let is_opaque = |i: &&InstanceRaw| can_be_opaque && i.color[3] >= 1.0;
+        self.layered.clear();
+        self.layered.extend(self.instances.iter().filter(is_opaque));
+        self.opaque = self.layered.len();
+        self.layered.extend(self.instances.iter().filter(|i| !is_opaque(i)));
+        self.layered[self.opaque..].sort_by(|a, b| b.pos[2].total_cmp(&a.pos[2]));
+        self.gpu.upload(device, uploads, &self.layered);
     }
 
+    /// Draws the sprites of the last `prepare` (None before the first)
     pub fn draw<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>) {
-        if self.instances.is_empty() { return; }
+        self.draw_range(rpass, 0..self.gpu.uploaded().len() as u32);
+    }
+
+    /// Draws the uploaded sprites in `instances` (Those past the upload are
+    /// left out)
+    pub(crate) fn draw_range<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>, instances: Range<u32>) {
+        let instances = instances.start..instances.end.min(self.gpu.uploaded().len() as u32);
+        if instances.is_empty() { return; }
         
         // FIX: Bind to Slot 0 (Since we have no Geometry buffer)
-        rpass.set_vertex_buffer(0, self.instance_buffer.slice(0..(self.instances.len() * std::mem::size_of::<InstanceRaw>()) as wgpu::BufferAddress));
+        rpass.set_vertex_buffer(0, self.gpu.slice());
         
         // Draw 6 vertices (1 Quad) * N Instances
-        rpass.draw(0..6, 0..self.instances.len() as u32);
+        rpass.draw(0..6, instances);
+    }
+
+    /// The opaque sprites uploaded by [`SpriteBatch::prepare_layers`]
+    pub(crate) fn opaque(&self) -> Range<u32> {
+        0..self.opaque as u32
+    }
+}
+
+/// A shape drawn by its signed distance (Matches `ShapeInput` in
+/// `shapes.wgsl`).
+///
+/// Every shape is a rounded rectangle, turned around its centre: Circles
+/// and capsules round it all the way, lines not at all. The `stroke` is
+/// centred on the outline, and the fill covers the inside.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct ShapeInstance {
+    /// Centre (x, y) + Depth/Layer (z, from 0 in front to 1 at the back)
+    pub pos: [f32; 3],
+    /// Half the width and height
+    pub half_size: [f32; 2],
+    /// Cosine and sine of the turn (Clockwise on screen)
+    pub axis: [f32; 2],
+    /// Radius of the corners
+    pub radius: f32,
+    /// Width of the stroke, 0 for none
+    pub stroke_width: f32,
+    /// Linear RGBA of the inside
+    pub fill: [f32; 4],
+    /// Linear RGBA of the stroke
+    pub stroke: [f32; 4],
+}
+
+impl ShapeInstance {
+    /// A white `size` rectangle with its top-left corner at `position`
+    pub fn rect(position: [f32; 2], size: [f32; 2]) -> Self {
+        let half_size = [size[0] / 2.0, size[1] / 2.0];
+        Self {
+            pos: [position[0] + half_size[0], position[1] + half_size[1], 0.0],
+            half_size,
+            axis: [1.0, 0.0],
+            radius: 0.0,
+            stroke_width: 0.0,
+            fill: [1.0; 4],
+            stroke: [0.0; 4],
+        }
+    }
+
+    pub fn rounded_rect(position: [f32; 2], size: [f32; 2], radius: f32) -> Self {
+        Self { radius, ..Self::rect(position, size) }
+    }
+
+    pub fn circle(center: [f32; 2], radius: f32) -> Self {
+        Self::rounded_rect([center[0] - radius, center[1] - radius], [radius * 2.0; 2], radius)
+    }
+
+    /// The outline of a circle, `width` wide across `radius` (No fill)
+    pub fn ring(center: [f32; 2], radius: f32, width: f32) -> Self {
+        Self::circle(center, radius).with_fill([0.0; 4]).with_stroke(width, [1.0; 4])
+    }
+
+    /// From `a` to `b`, with round ends of `radius`
+    pub fn capsule(a: [f32; 2], b: [f32; 2], radius: f32) -> Self {
+        Self { radius, ..Self::segment(a, b, radius, radius) }
+    }
+
+    /// From `a` to `b`, `width` wide with flat ends at `a` and `b`
+    pub fn line(a: [f32; 2], b: [f32; 2], width: f32) -> Self {
+        Self::segment(a, b, 0.0, width / 2.0)
+    }
+
+    /// A rectangle along `a` to `b`, reaching `cap` past them
+    fn segment(a: [f32; 2], b: [f32; 2], cap: f32, half_width: f32) -> Self {
+        let d = [b[0] - a[0], b[1] - a[1]];
+        let length = d[0].hypot(d[1]);
+        let axis = if length > 0.0 { [d[0] / length, d[1] / length] } else { [1.0, 0.0] };
+        Self {
+            pos: [(a[0] + b[0]) / 2.0, (a[1] + b[1]) / 2.0, 0.0],
+            half_size: [length / 2.0 + cap, half_width],
+            axis,
+            ..Self::rect([0.0; 2], [0.0; 2])
+        }
+    }
+
+    pub fn with_fill(mut self, color: [f32; 4]) -> Self {
+        self.fill = color;
+        self
+    }
+
+    pub fn with_stroke(mut self, width: f32, color: [f32; 4]) -> Self {
+        self.stroke_width = width;
+        self.stroke = color;
+        self
+    }
+
+    pub fn with_layer(mut self, z: f32) -> Self {
+        self.pos[2] = z;
+        self
+    }
+
+    /// Turns the shape `radians` clockwise around its centre
+    pub fn with_rotation(mut self, radians: f32) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        let [x, y] = self.axis;
+        self.axis = [x * cos - y * sin, x * sin + y * cos];
+        self
+    }
+
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        use std::mem;
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<ShapeInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                // Location 0: Pos (vec3)
+                wgpu::VertexAttribute {
+                    offset: mem::offset_of!(ShapeInstance, pos) as wgpu::BufferAddress,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                // Location 1: Half Size (vec2)
+                wgpu::VertexAttribute {
+                    offset: mem::offset_of!(ShapeInstance, half_size) as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                // Location 2: Axis (vec2)
+                wgpu::VertexAttribute {
+                    offset: mem::offset_of!(ShapeInstance, axis) as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                // Location 3: Radius (f32)
+                wgpu::VertexAttribute {
+                    offset: mem::offset_of!(ShapeInstance, radius) as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                // Location 4: Stroke Width (f32)
+                wgpu::VertexAttribute {
+                    offset: mem::offset_of!(ShapeInstance, stroke_width) as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                // Location 5: Fill (vec4)
+                wgpu::VertexAttribute {
+                    offset: mem::offset_of!(ShapeInstance, fill) as wgpu::BufferAddress,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                // Location 6: Stroke (vec4)
+                wgpu::VertexAttribute {
+                    offset: mem::offset_of!(ShapeInstance, stroke) as wgpu::BufferAddress,
+                    shader_location: 6,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+/// Shapes drawn in one pass over the sprites: Circles, rounded rectangles,
+/// rings, capsules and lines, filled and stroked (See [`ShapeInstance`]).
+///
+/// They are anti-aliased, so all count as translucent: Drawn back to front
+/// (Ties in push order), tested against opaque sprites.
+pub struct ShapeBatch {
+    shapes: Vec<ShapeInstance>,
+    /// The uploaded shapes, back to front
+    sorted: Vec<ShapeInstance>,
+    gpu: UploadBuffer<ShapeInstance>,
+}
+
+impl ShapeBatch {
+    pub fn new(device: &wgpu::Device, initial_capacity: usize) -> Self {
+        Self {
+            shapes: Vec::with_capacity(initial_capacity),
+            sorted: Vec::new(),
+            gpu: UploadBuffer::new(device, "Shape Instance Buffer", wgpu::BufferUsages::VERTEX, initial_capacity),
+        }
+    }
+
+    /// Capacity, resizes and the last upload
+    pub fn metrics(&self) -> &BatchMetrics {
+        self.gpu.metrics()
+    }
+
+    pub fn push(&mut self, shape: ShapeInstance) {
+        self.shapes.push(shape);
+    }
+
+    /// Clear the batch (call at start of frame)
+    pub fn clear(&mut self) {
+        self.shapes.clear();
+    }
+
+    pub fn circle(&mut self, center: [f32; 2], radius: f32, color: [f32; 4]) {
+        self.push(ShapeInstance::circle(center, radius).with_fill(color));
+    }
+
+    pub fn rect(&mut self, position: [f32; 2], size: [f32; 2], color: [f32; 4]) {
+        self.push(ShapeInstance::rect(position, size).with_fill(color));
+    }
+
+    pub fn rounded_rect(&mut self, position: [f32; 2], size: [f32; 2], radius: f32, color: [f32; 4]) {
+        self.push(ShapeInstance::rounded_rect(position, size, radius).with_fill(color));
+    }
+
+    pub fn ring(&mut self, center: [f32; 2], radius: f32, width: f32, color: [f32; 4]) {
+        self.push(ShapeInstance::ring(center, radius, width).with_stroke(width, color));
+    }
+
+    pub fn capsule(&mut self, a: [f32; 2], b: [f32; 2], radius: f32, color: [f32; 4]) {
+        self.push(ShapeInstance::capsule(a, b, radius).with_fill(color));
+    }
+
+    pub fn line(&mut self, a: [f32; 2], b: [f32; 2], width: f32, color: [f32; 4]) {
+        self.push(ShapeInstance::line(a, b, width).with_fill(color));
+    }
+
+    /// Uploads the shapes that changed to the GPU, back to front
+    pub(crate) fn prepare(&mut self, device: &wgpu::Device, uploads: &mut FrameUploads) {
+        self.sorted.clear();
+        self.sorted.extend_from_slice(&self.shapes);
+        self.sorted.sort_by(|a, b| b.pos[2].total_cmp(&a.pos[2]));
+        self.gpu.upload(device, uploads, &self.sorted);
+    }
+
+    pub fn draw<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>) {
+        if self.sorted.is_empty() { return; }
+        rpass.set_vertex_buffer(0, self.gpu.slice());
+        // Draw 6 vertices (1 Quad) * N Instances
+        rpass.draw(0..6, 0..self.sorted.len() as u32);
+    }
+}
+
+/// The uploads of a frame: Every batch writes through one staging belt into
+/// one encoder, submitted ahead of the frame that draws them.
+pub(crate) struct FrameUploads {
+    belt: wgpu::util::StagingBelt,
+    /// Created by the first write since the last `finish`
+    encoder: Option<wgpu::CommandEncoder>,
+}
+
+impl FrameUploads {
+    pub fn new() -> Self {
+        Self {
+            belt: wgpu::util::StagingBelt::new(STAGING_CHUNK_SIZE),
+            encoder: None,
+        }
+    }
+
+    fn write(&mut self, device: &wgpu::Device, buffer: &wgpu::Buffer, offset: wgpu::BufferAddress, bytes: &[u8]) {
+        let encoder = self.encoder.get_or_insert_with(|| {
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Upload Encoder"),
+            })
+        });
+        let size = wgpu::BufferSize::new(bytes.len() as u64).expect("Ranges are not empty");
+        self.belt.write_buffer(encoder, buffer, offset, size, device).copy_from_slice(bytes);
+    }
+
+    /// The copies written since the last call, to submit before the frame
+    /// (None if no batch changed)
+    pub fn finish(&mut self) -> Option<wgpu::CommandBuffer> {
+        let encoder = self.encoder.take()?;
+        self.belt.finish();
+        Some(encoder.finish())
+    }
+
+    /// Call once the copies were submitted: Chunks are reused once the GPU
+    /// is done with them
+    pub fn recall(&mut self) {
+        self.belt.recall();
+    }
+}
+
+/// Buffers an [`UploadBuffer`] takes turns writing to, so a frame never
+/// writes to the buffer the GPU may still be drawing the last one from
+const UPLOAD_RING: usize = 2;
+
+/// One buffer of the ring, and what it holds
+struct RingBuffer<T> {
+    buffer: wgpu::Buffer,
+    capacity: usize,
+    uploaded: Vec<T>,
+}
+
+/// A GPU buffer of instances (Or vertices, or indices), uploaded through the
+/// frame's [`FrameUploads`].
+///
+/// Uploads go to the next of [`UPLOAD_RING`] buffers (Unless nothing
+/// changed), and only the blocks of [`UPLOAD_BLOCK`] elements that differ
+/// from what that buffer holds are written. The buffers at least double when
+/// they run out of room, and shrink back after [`SHRINK_AFTER_FRAMES`]
+/// frames of using a quarter of them (Never below the initial capacity).
+pub(crate) struct UploadBuffer<T> {
+    label: &'static str,
+    usage: wgpu::BufferUsages,
+    ring: Vec<RingBuffer<T>>,
+    /// The buffer of the last upload, drawn from
+    current: usize,
+    /// Never shrunk below
+    initial_capacity: usize,
+    /// Frames in a row using at most a quarter of the capacity, and the most
+    /// elements any of them had
+    low_use: (u32, usize),
+    metrics: BatchMetrics,
+}
+
+impl<T: Pod> UploadBuffer<T> {
+    /// A `VERTEX` or `INDEX` buffer (`usage`) of `initial_capacity` elements
+    pub fn new(device: &wgpu::Device, label: &'static str, usage: wgpu::BufferUsages, initial_capacity: usize) -> Self {
+        let ring = (0..UPLOAD_RING)
+            .map(|_| RingBuffer {
+                buffer: Self::create_buffer(device, label, usage, initial_capacity),
+                capacity: initial_capacity,
+                uploaded: Vec::new(),
+            })
+            .collect();
+        Self {
+            label,
+            usage,
+            ring,
+            current: 0,
+            initial_capacity,
+            low_use: (0, 0),
+            metrics: BatchMetrics { capacity: initial_capacity, ..Default::default() },
+        }
+    }
+
+    fn create_buffer(device: &wgpu::Device, label: &str, usage: wgpu::BufferUsages, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: (capacity * std::mem::size_of::<T>()) as wgpu::BufferAddress,
+            usage: usage | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// What the last `upload` wrote
+    pub fn uploaded(&self) -> &[T] {
+        &self.ring[self.current].uploaded
+    }
+
+    /// The uploaded elements
+    pub fn slice(&self) -> wgpu::BufferSlice<'_> {
+        let current = &self.ring[self.current];
+        current.buffer.slice(0..(current.uploaded.len() * std::mem::size_of::<T>()) as wgpu::BufferAddress)
+    }
+
+    pub fn metrics(&self) -> &BatchMetrics {
+        &self.metrics
+    }
+
+    /// Records the copies of `elements` into `uploads`, drawn from once they
+    /// are submitted
+    pub fn upload(&mut self, device: &wgpu::Device, uploads: &mut FrameUploads, elements: &[T]) {
+        self.metrics.uploaded_bytes = 0;
+        self.metrics.upload_ranges = 0;
+
+        // 1. Grow or shrink (Buffers of another size are replaced as the ring reaches them)
+        self.fit(elements.len());
+        let capacity = self.metrics.capacity;
+
+        // 2. Nothing changed: Draw from the same buffer again
+        let current = &self.ring[self.current];
+        if current.capacity == capacity && current.uploaded.len() == elements.len() && changed_ranges(&current.uploaded, elements).is_empty() {
+            return;
+        }
+
+        // 3. Move on to the next buffer, replaced (And uploaded whole) if its size is out of date
+        self.current = (self.current + 1) % UPLOAD_RING;
+        let next = &mut self.ring[self.current];
+        if next.capacity != capacity {
+            next.buffer = Self::create_buffer(device, self.label, self.usage, capacity);
+            next.capacity = capacity;
+            next.uploaded.clear();
+        }
+
+        // 4. Upload the blocks it holds differently
+        let ranges = changed_ranges(&next.uploaded, elements);
+        for range in &ranges {
+            let bytes: &[u8] = bytemuck::cast_slice(&elements[range.clone()]);
+            let offset = (range.start * std::mem::size_of::<T>()) as wgpu::BufferAddress;
+            uploads.write(device, &next.buffer, offset, bytes);
+            self.metrics.uploaded_bytes += bytes.len() as u64;
+        }
+        self.metrics.upload_ranges = ranges.len();
+        next.uploaded.clear();
+        next.uploaded.extend_from_slice(elements);
+    }
+
+    /// Sets the capacity for `len` elements if they outgrew it, or after a
+    /// long time using little of it
+    fn fit(&mut self, len: usize) {
+        let current = self.metrics.capacity;
+        let capacity = if len > current {
+            self.metrics.grows += 1;
+            len.max(current * 2)
+        } else if len <= current / 4 && current > self.initial_capacity {
+            let (frames, peak) = self.low_use;
+            self.low_use = (frames + 1, peak.max(len));
+            if self.low_use.0 < SHRINK_AFTER_FRAMES {
+                return;
+            }
+            self.metrics.shrinks += 1;
+            (self.low_use.1 * 2).max(self.initial_capacity)
+        } else {
+            self.low_use = (0, 0);
+            return;
+        };
+
+        self.low_use = (0, 0);
+        self.metrics.capacity = capacity;
+    }
+}
+
+/// The translucent sprites of layered batches, back to front across all of
+/// them: Runs of `(batch, instances)` to draw in order. Ties between batches
+/// go to the batch first in `batches`.
+pub(crate) fn translucent_runs(batches: &[SpriteBatch]) -> Vec<(usize, Range<u32>)> {
+    let mut next: Vec<usize> = batches.iter().map(|b| b.opaque).collect();
+    let mut runs: Vec<(usize, Range<u32>)> = Vec::new();
+    loop {
+        // The farthest sprite not drawn yet
+        let farthest = batches.iter().enumerate()
+            .filter_map(|(i, batch)| batch.layered.get(next[i]).map(|sprite| (i, sprite.pos[2])))
+            .reduce(|far, other| if other.1 > far.1 { other } else { far });
+        let Some((batch, _)) = farthest else { return runs };
+
+        let index = next[batch] as u32;
+        next[batch] += 1;
+        match runs.last_mut() {
+            Some((last, range)) if *last == batch => range.end = index + 1,
+            _ => runs.push((batch, index..index + 1)),
+        }
+    }
+}
+
+/// Ranges of `current` to upload over `uploaded`: The blocks of
+/// [`UPLOAD_BLOCK`] elements that differ, merged when adjacent
+fn changed_ranges<T: Pod>(uploaded: &[T], current: &[T]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for start in (0..current.len()).step_by(UPLOAD_BLOCK) {
+        let end = (start + UPLOAD_BLOCK).min(current.len());
+        let bytes = |elements| bytemuck::cast_slice::<T, u8>(elements);
+        let unchanged = uploaded.get(start..end).is_some_and(|old| bytes(old) == bytes(&current[start..end]));
+        if unchanged {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(last) if last.end == start => last.end = end,
+            _ => ranges.push(start..end),
+        }
     }
+    ranges
 }
@@ -0,0 +1,188 @@
+//! On-disk pipeline cache, so projects that loaded before start without
+//! recompiling their shaders in the driver.
+//!
+//! Backed by wgpu's `PipelineCache` where the backend supports it (Vulkan).
+//! There is one file per adapter, driver and project (By title), named
+//! after the fingerprint of the project's pipelines:
+//!
+//! ```text
+//! <dir>/wgpu_pipeline_cache_vulkan_<vendor>_<device>_<driver hash>_<project hash>_<fingerprint>.bin
+//! ```
+//!
+//! Saving the cache of a project removes its files of other fingerprints
+//! (Edits before a reload), and the files of older drivers for the same
+//! adapter. The hashes are fixed, so the names outlive the build that wrote
+//! them. wgpu validates the data again when it is loaded and starts empty
+//! if it does not match.
+//!
+//! The directory is `$HANGA_PIPELINE_CACHE` if set (Empty disables the
+//! cache), or else `hanga/pipelines` in the platform's cache directory.
+
+use siphasher::sip::SipHasher13;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// Name of the cache file for pipelines of `project` with `fingerprint` on
+/// `adapter`, `None` if the backend has no pipeline caches.
+pub fn file_name(adapter: &wgpu::AdapterInfo, project: &str, fingerprint: u64) -> Option<String> {
+    Some(format!("{}_{:016x}.bin", project_prefix(adapter, project)?, fingerprint))
+}
+
+/// The part of the file name fixed by the adapter and its driver
+fn adapter_prefix(adapter: &wgpu::AdapterInfo) -> Option<String> {
+    let key = wgpu::util::pipeline_cache_key(adapter)?;
+    Some(format!("{}_{:016x}", key, stable_hash((&adapter.name, &adapter.driver, &adapter.driver_info))))
+}
+
+/// The part of the file name fixed by the adapter, its driver and `project`
+fn project_prefix(adapter: &wgpu::AdapterInfo, project: &str) -> Option<String> {
+    Some(format!("{}_{:016x}", adapter_prefix(adapter)?, stable_hash(project)))
+}
+
+/// SipHash 1-3 with fixed keys: Unlike `DefaultHasher`, the same in every build
+fn stable_hash(value: impl Hash) -> u64 {
+    let mut hasher = SipHasher13::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The cache directory, `None` if disabled or unknown
+pub fn default_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("HANGA_PIPELINE_CACHE") {
+        return (!dir.is_empty()).then(|| PathBuf::from(dir));
+    }
+    let env_dir = |name: &str| std::env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+    let base = if cfg!(target_arch = "wasm32") {
+        None
+    } else if cfg!(windows) {
+        env_dir("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join("Library/Caches"))
+    } else {
+        env_dir("XDG_CACHE_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".cache")))
+    };
+    base.map(|base| base.join("hanga").join("pipelines"))
+}
+
+/// The cache files of one adapter in [`default_dir`].
+pub struct CacheFiles {
+    dir: PathBuf,
+    adapter: wgpu::AdapterInfo,
+}
+
+impl CacheFiles {
+    /// `None` if caching is disabled, or the backend has no pipeline caches
+    pub fn new(adapter: wgpu::AdapterInfo) -> Option<Self> {
+        adapter_prefix(&adapter)?;
+        Some(Self { dir: default_dir()?, adapter })
+    }
+
+    pub fn path(&self, project: &str, fingerprint: u64) -> PathBuf {
+        let name = file_name(&self.adapter, project, fingerprint).expect("Checked by `new`");
+        self.dir.join(name)
+    }
+
+    /// The cache saved for `project` with `fingerprint`, if any
+    pub fn read(&self, project: &str, fingerprint: u64) -> Option<Vec<u8>> {
+        std::fs::read(self.path(project, fingerprint)).ok()
+    }
+
+    /// Saves the cache of `project` with `fingerprint`, then removes the
+    /// caches it replaces
+    pub fn write(&self, project: &str, fingerprint: u64, data: &[u8]) -> anyhow::Result<()> {
+        let path = self.path(project, fingerprint);
+        Self::write_file(&path, data)?;
+        self.remove_stale(&path, project);
+        Ok(())
+    }
+
+    /// Writes through a temporary file, so a crash never leaves half a cache
+    fn write_file(path: &Path, data: &[u8]) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, data)?;
+        std::fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
+    /// Removes the other caches of `project` on this driver, and every cache
+    /// of the same adapter written by other drivers
+    fn remove_stale(&self, current: &Path, project: &str) {
+        let (Some(adapter), Some(project), Some(key)) = (
+            adapter_prefix(&self.adapter),
+            project_prefix(&self.adapter, project),
+            wgpu::util::pipeline_cache_key(&self.adapter),
+        ) else { return };
+        let Ok(entries) = std::fs::read_dir(&self.dir) else { return };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let other_driver = name.starts_with(&format!("{}_", key)) && !name.starts_with(&adapter);
+            let other_fingerprint = name.starts_with(&project) && entry.path() != current;
+            if other_driver || other_fingerprint {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+    }
+}
+
+/// The pipeline cache of the loaded project.
+pub(crate) struct PipelineCache {
+    /// `None` if caching is disabled or unsupported
+    files: Option<CacheFiles>,
+    /// Title of the project, fingerprint of its pipelines and the cache
+    current: Option<(String, u64, wgpu::PipelineCache)>,
+}
+
+impl PipelineCache {
+    pub(crate) fn new(adapter: &wgpu::Adapter, device: &wgpu::Device) -> Self {
+        let supported = device.features().contains(wgpu::Features::PIPELINE_CACHE);
+        Self {
+            files: CacheFiles::new(adapter.get_info()).filter(|_| supported),
+            current: None,
+        }
+    }
+
+    /// Switches to the cache for pipelines of `project` with `fingerprint`,
+    /// loaded from disk the first time (See [`PipelineCache::get`])
+    pub(crate) fn load(&mut self, device: &wgpu::Device, project: &str, fingerprint: u64) {
+        let Some(files) = &self.files else { return };
+        if self.current.as_ref().is_some_and(|(p, f, _)| p == project && *f == fingerprint) {
+            return;
+        }
+        let data = files.read(project, fingerprint);
+        // SAFETY: The data was written by `save` from `get_data` (wgpu
+        // checks its header and falls back to an empty cache)
+        let cache = unsafe {
+            device.create_pipeline_cache(&wgpu::PipelineCacheDescriptor {
+                label: Some("Hanga Pipeline Cache"),
+                data: data.as_deref(),
+                fallback: true,
+            })
+        };
+        let path = files.path(project, fingerprint);
+        match &data {
+            Some(_) => println!("📂 Pipeline cache: {}", path.display()),
+            None => println!("📝 New pipeline cache: {}", path.display()),
+        }
+        self.current = Some((project.to_string(), fingerprint, cache));
+    }
+
+    /// The cache of the last `load`, `None` if caching is disabled or
+    /// unsupported
+    pub(crate) fn get(&self) -> Option<&wgpu::PipelineCache> {
+        self.current.as_ref().map(|(_, _, cache)| cache)
+    }
+
+    /// Writes the current cache to disk, replacing the project's older
+    /// caches and those of older drivers. Failures only cost a recompilation
+    /// next time, so they are printed.
+    pub(crate) fn save(&self) {
+        let (Some(files), Some((project, fingerprint, cache))) = (&self.files, &self.current) else { return };
+        let Some(data) = cache.get_data() else { return };
+        if let Err(e) = files.write(project, *fingerprint, &data) {
+            println!("⚠️ Failed to write pipeline cache {}: {:#}", files.path(project, *fingerprint).display(), e);
+        }
+    }
+}
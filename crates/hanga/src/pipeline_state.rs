@@ -0,0 +1,68 @@
+//! Maps the pipeline state declared in the manifest onto wgpu.
+
+use gyo_core::pipeline::{BlendMode, CompareFunction, CullMode, DepthState, PipelineState, Topology};
+
+/// Format of the depth buffer of scenes that declare depth state
+pub(crate) const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+pub(crate) fn blend_state(blend: BlendMode) -> wgpu::BlendState {
+    let component = |src_factor, dst_factor| wgpu::BlendComponent {
+        src_factor,
+        dst_factor,
+        operation: wgpu::BlendOperation::Add,
+    };
+    match blend {
+        BlendMode::Replace => wgpu::BlendState::REPLACE,
+        BlendMode::Alpha => wgpu::BlendState::ALPHA_BLENDING,
+        BlendMode::Premultiplied => wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+        BlendMode::Additive => wgpu::BlendState {
+            color: component(wgpu::BlendFactor::SrcAlpha, wgpu::BlendFactor::One),
+            alpha: component(wgpu::BlendFactor::One, wgpu::BlendFactor::One),
+        },
+        BlendMode::Multiply => wgpu::BlendState {
+            color: component(wgpu::BlendFactor::Dst, wgpu::BlendFactor::Zero),
+            alpha: component(wgpu::BlendFactor::DstAlpha, wgpu::BlendFactor::Zero),
+        },
+    }
+}
+
+pub(crate) fn primitive_state(state: &PipelineState) -> wgpu::PrimitiveState {
+    wgpu::PrimitiveState {
+        topology: match state.topology {
+            Topology::PointList => wgpu::PrimitiveTopology::PointList,
+            Topology::LineList => wgpu::PrimitiveTopology::LineList,
+            Topology::LineStrip => wgpu::PrimitiveTopology::LineStrip,
+            Topology::TriangleList => wgpu::PrimitiveTopology::TriangleList,
+            Topology::TriangleStrip => wgpu::PrimitiveTopology::TriangleStrip,
+        },
+        strip_index_format: None, // Draws are not indexed
+        front_face: wgpu::FrontFace::Ccw,
+        cull_mode: match state.cull {
+            CullMode::None => None,
+            CullMode::Front => Some(wgpu::Face::Front),
+            CullMode::Back => Some(wgpu::Face::Back),
+        },
+        unclipped_depth: false,
+        polygon_mode: wgpu::PolygonMode::Fill,
+        conservative: false,
+    }
+}
+
+pub(crate) fn depth_stencil_state(depth: Option<DepthState>) -> Option<wgpu::DepthStencilState> {
+    depth.map(|depth| wgpu::DepthStencilState {
+        format: DEPTH_FORMAT,
+        depth_write_enabled: depth.write,
+        depth_compare: match depth.compare {
+            CompareFunction::Never => wgpu::CompareFunction::Never,
+            CompareFunction::Less => wgpu::CompareFunction::Less,
+            CompareFunction::Equal => wgpu::CompareFunction::Equal,
+            CompareFunction::LessEqual => wgpu::CompareFunction::LessEqual,
+            CompareFunction::Greater => wgpu::CompareFunction::Greater,
+            CompareFunction::NotEqual => wgpu::CompareFunction::NotEqual,
+            CompareFunction::GreaterEqual => wgpu::CompareFunction::GreaterEqual,
+            CompareFunction::Always => wgpu::CompareFunction::Always,
+        },
+        stencil: wgpu::StencilState::default(),
+        bias: wgpu::DepthBiasState::default(),
+    })
+}
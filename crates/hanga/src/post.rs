@@ -0,0 +1,432 @@
+//! Runs the post-processing effects of a project (See `gyo_core::post`).
+//!
+//! The scene draws into the first of two full-size HDR textures. Every
+//! effect reads one and writes the other, and the last one writes the
+//! screen. Bloom goes through two half-size textures: Bright parts, blurred
+//! across, blurred down, then added to the frame.
+
+use crate::bindings::ShaderLayout;
+use crate::channels::{self, Channels};
+use crate::loader::LoadedEffect;
+use crate::HangaEngine;
+use gyo_core::post::{Effect, ToneMapper};
+use gyo_core::texture::Texture;
+use gyo_core::PipelineState;
+use std::mem;
+use std::sync::Arc;
+
+/// Format of the textures between the scene and the screen
+pub(crate) const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// Settings of a built-in step (Matches `Params` in `post.wgsl`)
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    source_texel: [f32; 2],
+    target_texel: [f32; 2],
+    direction: [f32; 2],
+    _padding: [f32; 2],
+    values: [f32; 4],
+}
+
+/// A texture a step reads or draws into.
+#[derive(Clone, Copy, PartialEq)]
+enum Slot {
+    /// Full-size HDR texture
+    Frame(usize),
+    /// Half-size HDR texture
+    Bloom(usize),
+    /// Uploaded LUT
+    Lut(usize),
+    Screen,
+}
+
+enum Program {
+    /// A fragment entry point of `post.wgsl`
+    BuiltIn { params: Params, buffer: wgpu::Buffer },
+    /// The shader of a `Custom` effect, reading `source` as channel 0
+    Custom { layout: Arc<ShaderLayout> },
+}
+
+/// A full-screen draw of the chain.
+struct Step {
+    label: String,
+    /// Index of the effect the step belongs to
+    effect: usize,
+    /// Shared with the chain it was kept from on reload
+    pipeline: Arc<wgpu::RenderPipeline>,
+    program: Program,
+    source: Slot,
+    /// Blurred light or LUT of a built-in step
+    extra: Option<Slot>,
+    target: Slot,
+    bind_groups: Vec<wgpu::BindGroup>,
+}
+
+pub(crate) struct PostChain {
+    steps: Vec<Step>,
+    /// Of the effects the steps were built from
+    effects: Vec<u64>,
+    /// Layout of the built-in steps
+    layout: Arc<wgpu::BindGroupLayout>,
+    sampler: wgpu::Sampler,
+    frames: Vec<wgpu::Texture>,
+    blooms: Vec<wgpu::Texture>,
+    luts: Vec<wgpu::Texture>,
+    /// Bound as `extra` by steps without one
+    black: wgpu::Texture,
+}
+
+impl PostChain {
+    /// Builds the steps of `effects`, the last one drawing into the screen
+    /// `config` describes
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        config: &wgpu::SurfaceConfiguration,
+        effects: &[LoadedEffect],
+        uniform_buffer: &wgpu::Buffer,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> anyhow::Result<Self> {
+        Self::build(device, queue, config, effects, uniform_buffer, cache, None)
+    }
+
+    /// Like [`PostChain::new`], keeping the pipelines of the effects of
+    /// `self` with the same fingerprint in the same place (And drawing into
+    /// the screen if they did)
+    pub fn rebuild(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        config: &wgpu::SurfaceConfiguration,
+        effects: &[LoadedEffect],
+        uniform_buffer: &wgpu::Buffer,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> anyhow::Result<Self> {
+        Self::build(device, queue, config, effects, uniform_buffer, cache, Some(self))
+    }
+
+    fn build(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        config: &wgpu::SurfaceConfiguration,
+        effects: &[LoadedEffect],
+        uniform_buffer: &wgpu::Buffer,
+        cache: Option<&wgpu::PipelineCache>,
+        previous: Option<&PostChain>,
+    ) -> anyhow::Result<Self> {
+        let module = device.create_shader_module(wgpu::include_wgsl!("post.wgsl"));
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        // Kept pipelines were laid out with the layout of the chain they come from
+        let layout = previous.map(|chain| chain.layout.clone()).unwrap_or_else(|| Arc::new(device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post Bind Group Layout"),
+            entries: &[
+                texture_entry(0),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                texture_entry(2),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        })));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let built_in = |kept: Option<&Step>, label: &str, entry_point: &str, format, direction, values, source, extra, target| Step {
+            label: label.to_string(),
+            effect: 0,
+            pipeline: kept.map_or_else(
+                || Arc::new(Self::create_pipeline(device, &module, &pipeline_layout, entry_point, format, cache)),
+                |step| step.pipeline.clone(),
+            ),
+            program: Program::BuiltIn {
+                params: Params { direction, values, ..Default::default() },
+                buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some(label),
+                    size: mem::size_of::<Params>() as wgpu::BufferAddress,
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }),
+            },
+            source,
+            extra,
+            target,
+            bind_groups: Vec::new(),
+        };
+
+        // 1. Steps: Each effect reads the frame the one before it wrote
+        let mut steps = Vec::new();
+        let mut luts = Vec::new();
+        for (i, loaded) in effects.iter().enumerate() {
+            let last = i + 1 == effects.len();
+            let source = Slot::Frame(i % 2);
+            let (target, format) = if last { (Slot::Screen, config.format) } else { (Slot::Frame((i + 1) % 2), HDR_FORMAT) };
+            // Steps of an unchanged effect keep the pipelines of its steps in `previous`, in order
+            let unchanged = |chain: &&PostChain| {
+                chain.effects.get(i) == Some(&loaded.fingerprint()) && (i + 1 == chain.effects.len()) == last
+            };
+            let mut kept = previous
+                .filter(unchanged)
+                .into_iter()
+                .flat_map(|chain| chain.steps.iter().filter(move |step| step.effect == i));
+            let first = steps.len();
+            match &loaded.effect {
+                Effect::Bloom(bloom) => {
+                    let (across, down) = ([bloom.radius, 0.0], [0.0, bloom.radius]);
+                    steps.push(built_in(kept.next(), "Bloom Bright", "fs_bright", HDR_FORMAT, [0.0; 2], [bloom.threshold, 0.0, 0.0, 0.0], source, None, Slot::Bloom(0)));
+                    steps.push(built_in(kept.next(), "Bloom Blur Across", "fs_blur", HDR_FORMAT, across, [0.0; 4], Slot::Bloom(0), None, Slot::Bloom(1)));
+                    steps.push(built_in(kept.next(), "Bloom Blur Down", "fs_blur", HDR_FORMAT, down, [0.0; 4], Slot::Bloom(1), None, Slot::Bloom(0)));
+                    steps.push(built_in(kept.next(), "Bloom Composite", "fs_composite", format, [0.0; 2], [bloom.intensity, 0.0, 0.0, 0.0], source, Some(Slot::Bloom(0)), target));
+                }
+                Effect::ToneMap(tone_map) => {
+                    let aces = if tone_map.operator == ToneMapper::Aces { 1.0 } else { 0.0 };
+                    steps.push(built_in(kept.next(), "Tone Map", "fs_tone_map", format, [0.0; 2], [tone_map.exposure, aces, 0.0, 0.0], source, None, target));
+                }
+                Effect::Fxaa => steps.push(built_in(kept.next(), "FXAA", "fs_fxaa", format, [0.0; 2], [0.0; 4], source, None, target)),
+                Effect::ColorGrade { lut: id } => {
+                    let (lut, size) = loaded.lut.as_ref().expect("The loader decodes LUTs");
+                    luts.push(channels::upload(device, queue, id, lut, wgpu::TextureFormat::Rgba8Unorm));
+                    let extra = Some(Slot::Lut(luts.len() - 1));
+                    steps.push(built_in(kept.next(), "Color Grade", "fs_grade", format, [0.0; 2], [*size as f32, 0.0, 0.0, 0.0], source, extra, target));
+                }
+                Effect::Custom { asset_id } => {
+                    let (pipeline, layout) = match kept.next() {
+                        Some(Step { pipeline, program: Program::Custom { layout }, .. }) => (pipeline.clone(), layout.clone()),
+                        _ => {
+                            let shader = loaded.shader.as_ref().expect("The loader validates effect shaders");
+                            let layout = ShaderLayout::new(device, shader);
+                            let state = PipelineState::new(asset_id.as_str());
+                            let pipeline = HangaEngine::create_image_pipeline(device, format, shader, &state, &layout.layout, cache);
+                            (Arc::new(pipeline), Arc::new(layout))
+                        }
+                    };
+                    steps.push(Step {
+                        label: asset_id.clone(),
+                        effect: 0,
+                        pipeline,
+                        program: Program::Custom { layout },
+                        source,
+                        extra: None,
+                        target,
+                        bind_groups: Vec::new(),
+                    });
+                }
+            }
+            for step in &mut steps[first..] {
+                step.effect = i;
+            }
+        }
+
+        // 2. Textures and bind groups
+        let mut chain = Self {
+            steps,
+            effects: effects.iter().map(LoadedEffect::fingerprint).collect(),
+            layout,
+            sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("Post Sampler"),
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                ..Default::default()
+            }),
+            frames: Vec::new(),
+            blooms: Vec::new(),
+            luts,
+            black: channels::upload(device, queue, "Post Black", &Texture::new(1, 1, vec![0, 0, 0, 255]), wgpu::TextureFormat::Rgba8Unorm),
+        };
+        chain.resize(device, queue, uniform_buffer, config.width, config.height)?;
+        Ok(chain)
+    }
+
+    /// Uploads the LUTs of `effects` (The ones the chain was built from,
+    /// with new images) and rebinds the steps, keeping the pipelines
+    pub fn set_luts(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, effects: &[LoadedEffect], uniform_buffer: &wgpu::Buffer) -> anyhow::Result<()> {
+        let luts = effects.iter().filter_map(|loaded| match &loaded.effect {
+            Effect::ColorGrade { lut: id } => Some((id, loaded.lut.as_ref().expect("The loader decodes LUTs"))),
+            _ => None,
+        });
+        for (i, (id, (lut, size))) in luts.enumerate() {
+            self.luts[i] = channels::upload(device, queue, id, lut, wgpu::TextureFormat::Rgba8Unorm);
+            for step in self.steps.iter_mut().filter(|step| step.extra == Some(Slot::Lut(i))) {
+                if let Program::BuiltIn { params, .. } = &mut step.program {
+                    params.values[0] = *size as f32;
+                }
+            }
+        }
+        let (width, height) = (self.frames[0].width(), self.frames[0].height());
+        self.resize(device, queue, uniform_buffer, width, height)
+    }
+
+    /// Where the scene draws
+    pub fn scene_view(&self) -> wgpu::TextureView {
+        self.frames[0].create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    /// Recreates the textures for a new screen size
+    pub fn resize(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, uniform_buffer: &wgpu::Buffer, width: u32, height: u32) -> anyhow::Result<()> {
+        let create = |label: &str, (width, height): (u32, u32)| device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: HDR_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let full = (width, height);
+        let half = ((width / 2).max(1), (height / 2).max(1));
+        self.frames = vec![create("Post Frame 0", full), create("Post Frame 1", full)];
+        self.blooms = if self.steps.iter().any(|s| s.target == Slot::Bloom(0)) {
+            vec![create("Bloom 0", half), create("Bloom 1", half)]
+        } else {
+            Vec::new()
+        };
+
+        let size = |slot: Slot| match slot {
+            Slot::Frame(_) | Slot::Screen => full,
+            Slot::Bloom(_) => half,
+            Slot::Lut(i) => (self.luts[i].width(), self.luts[i].height()),
+        };
+        let texel = |slot: Slot| {
+            let (width, height) = size(slot);
+            [1.0 / width as f32, 1.0 / height as f32]
+        };
+        let mut bind_groups = Vec::with_capacity(self.steps.len());
+        for step in &self.steps {
+            let view = |slot: Slot| self.texture(slot).create_view(&wgpu::TextureViewDescriptor::default());
+            bind_groups.push(match &step.program {
+                Program::BuiltIn { params, buffer } => {
+                    let params = Params { source_texel: texel(step.source), target_texel: texel(step.target), ..*params };
+                    queue.write_buffer(buffer, 0, bytemuck::bytes_of(&params));
+                    let extra = step.extra.map_or_else(|| self.black.create_view(&wgpu::TextureViewDescriptor::default()), view);
+                    vec![device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: Some(&step.label),
+                        layout: &self.layout,
+                        entries: &[
+                            wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&view(step.source)) },
+                            wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(&self.sampler) },
+                            wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&extra) },
+                            wgpu::BindGroupEntry { binding: 3, resource: buffer.as_entire_binding() },
+                        ],
+                    })]
+                }
+                Program::Custom { layout } => {
+                    let channels = layout.uses_channels().then(|| Channels::from_views(device, queue, vec![view(step.source)]));
+                    layout.bind_groups(device, [Some(uniform_buffer), None], channels.as_ref(), None)?
+                }
+            });
+        }
+        for (step, bind_groups) in self.steps.iter_mut().zip(bind_groups) {
+            step.bind_groups = bind_groups;
+        }
+        Ok(())
+    }
+
+    /// Runs the effects on what the scene drew, the last one into `screen`
+    pub fn encode(&self, encoder: &mut wgpu::CommandEncoder, screen: &wgpu::TextureView) {
+        for step in &self.steps {
+            let target = (step.target != Slot::Screen)
+                .then(|| self.texture(step.target).create_view(&wgpu::TextureViewDescriptor::default()));
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some(&step.label),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target.as_ref().unwrap_or(screen),
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            rpass.set_pipeline(&step.pipeline);
+            for (group, bind_group) in step.bind_groups.iter().enumerate() {
+                rpass.set_bind_group(group as u32, bind_group, &[]);
+            }
+            rpass.draw(0..3, 0..1); // Full-screen triangle
+        }
+    }
+
+    fn texture(&self, slot: Slot) -> &wgpu::Texture {
+        match slot {
+            Slot::Frame(i) => &self.frames[i],
+            Slot::Bloom(i) => &self.blooms[i],
+            Slot::Lut(i) => &self.luts[i],
+            Slot::Screen => unreachable!("Steps never read the screen"),
+        }
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        module: &wgpu::ShaderModule,
+        layout: &wgpu::PipelineLayout,
+        entry_point: &str,
+        format: wgpu::TextureFormat,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(entry_point),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module,
+                entry_point: "vs_main",
+                buffers: &[], // Full-screen triangle
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module,
+                entry_point,
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1, mask: !0, alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache,
+        })
+    }
+}
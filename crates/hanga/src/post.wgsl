@@ -0,0 +1,137 @@
+// crates/hanga/src/post.wgsl
+// Built-in post-processing effects. Each step reads `source` (And `extra`:
+// The blurred light of a bloom, or a LUT) and draws a full-screen triangle.
+
+struct Params {
+    source_texel: vec2<f32>, // 1 / size of `source`
+    target_texel: vec2<f32>, // 1 / size of what the step draws
+    direction: vec2<f32>,    // Blur step, in source texels
+    values: vec4<f32>,       // Settings of the effect
+};
+
+@group(0) @binding(0) var source: texture_2d<f32>;
+@group(0) @binding(1) var bilinear: sampler;
+@group(0) @binding(2) var extra: texture_2d<f32>;
+@group(0) @binding(3) var<uniform> params: Params;
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> @builtin(position) vec4<f32> {
+    // (-1, -1), (3, -1), (-1, 3) covers the screen
+    let uv = vec2<f32>(f32((in_vertex_index << 1u) & 2u), f32(in_vertex_index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+fn luma(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// --- BLOOM ---
+
+// values.x: Threshold
+@fragment
+fn fs_bright(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let color = textureSample(source, bilinear, position.xy * params.target_texel).rgb;
+    // The light above the threshold, keeping its hue
+    let bright = max(luma(color) - params.values.x, 0.0);
+    return vec4<f32>(color * bright / max(luma(color), 1e-4), 1.0);
+}
+
+// 9-tap Gaussian along `direction`
+@fragment
+fn fs_blur(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let uv = position.xy * params.target_texel;
+    let step = params.direction * params.source_texel;
+    var weights = array<f32, 5>(0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+    var color = textureSample(source, bilinear, uv).rgb * weights[0];
+    for (var i = 1; i < 5; i++) {
+        let offset = step * f32(i);
+        color += textureSample(source, bilinear, uv + offset).rgb * weights[i];
+        color += textureSample(source, bilinear, uv - offset).rgb * weights[i];
+    }
+    return vec4<f32>(color, 1.0);
+}
+
+// values.x: Intensity
+@fragment
+fn fs_composite(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let uv = position.xy * params.target_texel;
+    let color = textureSample(source, bilinear, uv);
+    let light = textureSample(extra, bilinear, uv).rgb;
+    return vec4<f32>(color.rgb + light * params.values.x, color.a);
+}
+
+// --- TONE MAPPING ---
+
+// values.x: Exposure, values.y: 0 for Reinhard, 1 for ACES
+@fragment
+fn fs_tone_map(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let color = textureSample(source, bilinear, position.xy * params.target_texel);
+    let c = max(color.rgb * params.values.x, vec3<f32>(0.0));
+    let reinhard = c / (1.0 + c);
+    let aces = clamp((c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14), vec3<f32>(0.0), vec3<f32>(1.0));
+    return vec4<f32>(select(reinhard, aces, params.values.y > 0.5), color.a);
+}
+
+// --- FXAA ---
+
+// Luma-edge FXAA (The "console" variant of FXAA 3.11)
+@fragment
+fn fs_fxaa(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let uv = position.xy * params.target_texel;
+    let texel = params.source_texel;
+    let center = textureSample(source, bilinear, uv);
+    let luma_nw = luma(sqrt(saturate(textureSample(source, bilinear, uv + vec2<f32>(-0.5, -0.5) * texel).rgb)));
+    let luma_ne = luma(sqrt(saturate(textureSample(source, bilinear, uv + vec2<f32>(0.5, -0.5) * texel).rgb)));
+    let luma_sw = luma(sqrt(saturate(textureSample(source, bilinear, uv + vec2<f32>(-0.5, 0.5) * texel).rgb)));
+    let luma_se = luma(sqrt(saturate(textureSample(source, bilinear, uv + vec2<f32>(0.5, 0.5) * texel).rgb)));
+    let luma_m = luma(sqrt(saturate(center.rgb)));
+
+    // 1. Direction across the edge
+    var dir = vec2<f32>(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+    let reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * (1.0 / 8.0), 1.0 / 128.0);
+    let scale = 1.0 / (min(abs(dir.x), abs(dir.y)) + reduce);
+    dir = clamp(dir * scale, vec2<f32>(-8.0), vec2<f32>(8.0)) * texel;
+
+    // 2. Blend along it, falling back to the narrower blend if the wider
+    // one crosses into another edge
+    let a = 0.5 * (
+        textureSample(source, bilinear, uv + dir * (1.0 / 3.0 - 0.5)).rgb +
+        textureSample(source, bilinear, uv + dir * (2.0 / 3.0 - 0.5)).rgb
+    );
+    let b = a * 0.5 + 0.25 * (
+        textureSample(source, bilinear, uv - dir * 0.5).rgb +
+        textureSample(source, bilinear, uv + dir * 0.5).rgb
+    );
+    let luma_b = luma(sqrt(saturate(b)));
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+    return vec4<f32>(select(b, a, luma_b < luma_min || luma_b > luma_max), center.a);
+}
+
+// --- COLOUR GRADING ---
+
+fn to_srgb(c: vec3<f32>) -> vec3<f32> {
+    return select(1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055, c * 12.92, c <= vec3<f32>(0.0031308));
+}
+
+fn to_linear(c: vec3<f32>) -> vec3<f32> {
+    return select(pow((c + 0.055) / 1.055, vec3<f32>(2.4)), c / 12.92, c <= vec3<f32>(0.04045));
+}
+
+// `extra` is the LUT strip (Sampled as stored, in sRGB). values.x: Its size
+@fragment
+fn fs_grade(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let color = textureSample(source, bilinear, position.xy * params.target_texel);
+    let size = params.values.x;
+    let c = to_srgb(saturate(color.rgb)) * (size - 1.0);
+
+    // Red and green within a square, blue between the two nearest squares
+    let slice = min(floor(c.b), size - 2.0);
+    let texel = (c.rg + 0.5) / vec2<f32>(size * size, size);
+    let low = textureSample(extra, bilinear, texel + vec2<f32>(slice / size, 0.0)).rgb;
+    let high = textureSample(extra, bilinear, texel + vec2<f32>((slice + 1.0) / size, 0.0)).rgb;
+    return vec4<f32>(to_linear(mix(low, high, c.b - slice)), color.a);
+}
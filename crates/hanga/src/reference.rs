@@ -0,0 +1,956 @@
+//! CPU reference renderer for fragment shaders.
+//!
+//! Parses a `SumiSource` with naga and interprets the fragment entry point for
+//! every pixel, feeding it the same [`Uniform`] values `HangaEngine` uploads.
+//! It is slow and supports a subset of WGSL (No textures or derivatives), but
+//! needs no GPU at all: use it as ground truth when testing the runtime or to
+//! validate exports on minimal CI machines.
+
+use crate::uniform::Uniform;
+use anyhow::{bail, Context, Result};
+use gyo_core::validate::{self, ValidatedShader};
+use gyo_core::texture::Texture;
+use naga::{
+    BinaryOperator, Binding, BuiltIn, Expression, Handle, MathFunction, Module, RelationalFunction,
+    ScalarKind, Statement, SwitchValue, TypeInner, UnaryOperator,
+};
+
+/// Upper bound of iterations for a single loop, guards against hangs.
+const MAX_LOOP_ITERATIONS: usize = 1 << 20;
+
+/// Interprets a fragment shader on the CPU.
+pub struct ReferenceRenderer {
+    module: Module,
+    entry_point: usize,
+}
+
+impl ReferenceRenderer {
+    /// Parses and validates `source_code`.
+    /// Image shaders (`main_image`) are wrapped in the runtime prelude first.
+    pub fn new(source_code: &str) -> Result<Self> {
+        Self::from_shader(validate::validate_asset("<source>", source_code)?)
+    }
+
+    /// Interprets a shader validated by `ProjectLoader` or `validate::validate`.
+    pub fn from_shader(shader: ValidatedShader) -> Result<Self> {
+        let module = shader.module;
+
+        let entry_point = module.entry_points.iter()
+            .position(|ep| ep.stage == naga::ShaderStage::Fragment)
+            .context("Shader has no fragment entry point")?;
+
+        Ok(Self { module, entry_point })
+    }
+
+    /// Runs the fragment entry point for one pixel.
+    /// `frag_coord` is the pixel centre in framebuffer coordinates (Origin top-left).
+    pub fn shade(&self, uniform: &Uniform, frag_coord: [f32; 2]) -> Result<[f32; 4]> {
+        let mut machine = Machine::new(&self.module, uniform)?;
+        let entry = &self.module.entry_points[self.entry_point];
+        let position = Value::vector_f32(&[frag_coord[0], frag_coord[1], 0.0, 1.0]);
+
+        let arguments = entry.function.arguments.iter()
+            .map(|arg| machine.entry_input(arg.binding.as_ref(), arg.ty, &position))
+            .collect::<Result<Vec<_>>>()?;
+
+        let result = match machine.call(&entry.function, arguments, Vec::new())?.0 {
+            Some(value) => value,
+            None => bail!("Fragment entry point returned no value"), // Discarded
+        };
+
+        let color = match &entry.function.result {
+            Some(naga::FunctionResult { binding: Some(_), .. }) => result,
+            Some(naga::FunctionResult { ty, binding: None }) => {
+                // Struct output: Find the member written to location 0
+                let TypeInner::Struct { members, .. } = &self.module.types[*ty].inner else {
+                    bail!("Unsupported fragment output");
+                };
+                let index = members.iter()
+                    .position(|m| matches!(m.binding, Some(Binding::Location { location: 0, .. })))
+                    .context("Fragment output has no @location(0)")?;
+                result.component(index)?
+            }
+            None => bail!("Fragment entry point has no output"),
+        };
+
+        let channels = color.components()?;
+        let mut rgba = [0.0, 0.0, 0.0, 1.0];
+        for (out, channel) in rgba.iter_mut().zip(channels) {
+            *out = channel.as_f32()?;
+        }
+        Ok(rgba)
+    }
+
+    /// Renders a full frame, encoded like the engine's sRGB render targets.
+    pub fn render(&self, uniform: &Uniform, width: u32, height: u32) -> Result<Texture> {
+        let mut uniform = *uniform;
+        uniform.resolution = [width as f32, height as f32];
+
+        let mut data = Vec::with_capacity((width * height * 4) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let color = self.shade(&uniform, [x as f32 + 0.5, y as f32 + 0.5])
+                    .with_context(|| format!("While shading pixel ({}, {})", x, y))?;
+                data.extend_from_slice(&[
+                    encode_srgb(color[0]),
+                    encode_srgb(color[1]),
+                    encode_srgb(color[2]),
+                    (color[3].clamp(0.0, 1.0) * 255.0).round() as u8,
+                ]);
+            }
+        }
+
+        Ok(Texture::new(width, height, data))
+    }
+}
+
+fn encode_srgb(linear: f32) -> u8 {
+    let c = linear.clamp(0.0, 1.0);
+    let srgb = if c <= 0.003_130_8 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 };
+    (srgb * 255.0).round() as u8
+}
+
+// --- Values ---
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Root {
+    Local(Handle<naga::LocalVariable>),
+    Global(Handle<naga::GlobalVariable>),
+    /// A value the caller passed by pointer (Index into `Frame::references`)
+    Reference(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Bool(bool),
+    I32(i32),
+    U32(u32),
+    F32(f32),
+    Vector(Vec<Value>),
+    /// Columns, each a `Vector`
+    Matrix(Vec<Value>),
+    /// Arrays and structs
+    Composite(Vec<Value>),
+    Pointer { root: Root, path: Vec<usize> },
+    /// Textures and samplers: Not interpretable
+    Opaque,
+}
+
+impl Value {
+    fn vector_f32(values: &[f32]) -> Self {
+        Value::Vector(values.iter().map(|&v| Value::F32(v)).collect())
+    }
+
+    fn as_f32(&self) -> Result<f32> {
+        match self {
+            Value::F32(v) => Ok(*v),
+            other => bail!("Expected f32, found {:?}", other),
+        }
+    }
+
+    fn as_bool(&self) -> Result<bool> {
+        match self {
+            Value::Bool(v) => Ok(*v),
+            other => bail!("Expected bool, found {:?}", other),
+        }
+    }
+
+    fn as_index(&self) -> Result<usize> {
+        match self {
+            Value::I32(v) => Ok((*v).max(0) as usize),
+            Value::U32(v) => Ok(*v as usize),
+            other => bail!("Expected an index, found {:?}", other),
+        }
+    }
+
+    fn components(&self) -> Result<&[Value]> {
+        match self {
+            Value::Vector(c) | Value::Matrix(c) | Value::Composite(c) => Ok(c),
+            other => bail!("Expected a composite, found {:?}", other),
+        }
+    }
+
+    fn component(&self, index: usize) -> Result<Value> {
+        let components = self.components()?;
+        // Out of bounds accesses are clamped, like on the GPU
+        components.get(index.min(components.len().saturating_sub(1)))
+            .cloned()
+            .context("Access into an empty composite")
+    }
+
+    fn component_mut(&mut self, index: usize) -> Result<&mut Value> {
+        match self {
+            Value::Vector(c) | Value::Matrix(c) | Value::Composite(c) => {
+                let last = c.len().saturating_sub(1);
+                c.get_mut(index.min(last)).context("Access into an empty composite")
+            }
+            other => bail!("Expected a composite, found {:?}", other),
+        }
+    }
+
+    /// Applies `f` to every scalar of a scalar or vector
+    fn map(&self, f: &dyn Fn(&Value) -> Result<Value>) -> Result<Value> {
+        match self {
+            Value::Vector(c) => Ok(Value::Vector(c.iter().map(f).collect::<Result<_>>()?)),
+            scalar => f(scalar),
+        }
+    }
+
+    /// Applies `f` component-wise, broadcasting scalars against vectors
+    fn zip(&self, other: &Value, f: &dyn Fn(&Value, &Value) -> Result<Value>) -> Result<Value> {
+        match (self, other) {
+            (Value::Vector(a), Value::Vector(b)) => Ok(Value::Vector(
+                a.iter().zip(b).map(|(a, b)| f(a, b)).collect::<Result<_>>()?,
+            )),
+            (Value::Vector(a), b) => Ok(Value::Vector(a.iter().map(|a| f(a, b)).collect::<Result<_>>()?)),
+            (a, Value::Vector(b)) => Ok(Value::Vector(b.iter().map(|b| f(a, b)).collect::<Result<_>>()?)),
+            (a, b) => f(a, b),
+        }
+    }
+
+    fn zip3(&self, b: &Value, c: &Value, f: &dyn Fn(&Value, &Value, &Value) -> Result<Value>) -> Result<Value> {
+        let size = [self, b, c].iter().find_map(|v| match v {
+            Value::Vector(c) => Some(c.len()),
+            _ => None,
+        });
+        match size {
+            None => f(self, b, c),
+            Some(size) => {
+                let pick = |v: &Value, i: usize| match v {
+                    Value::Vector(c) => c[i].clone(),
+                    scalar => scalar.clone(),
+                };
+                Ok(Value::Vector(
+                    (0..size).map(|i| f(&pick(self, i), &pick(b, i), &pick(c, i))).collect::<Result<_>>()?,
+                ))
+            }
+        }
+    }
+
+    fn floats(&self) -> Result<Vec<f32>> {
+        match self {
+            Value::Vector(c) => c.iter().map(Value::as_f32).collect(),
+            scalar => Ok(vec![scalar.as_f32()?]),
+        }
+    }
+}
+
+fn float_fn(f: fn(f32) -> f32) -> impl Fn(&Value) -> Result<Value> {
+    move |v| Ok(Value::F32(f(v.as_f32()?)))
+}
+
+// --- Interpreter ---
+
+enum Flow {
+    Next,
+    Break,
+    Continue,
+    Return(Option<Value>),
+    Kill,
+}
+
+struct Frame<'a> {
+    expressions: &'a naga::Arena<Expression>,
+    arguments: Vec<Value>,
+    values: Vec<Option<Value>>,
+    locals: Vec<Value>,
+    /// Copies of the caller's variables passed by pointer, written back on return
+    references: Vec<Value>,
+}
+
+struct Machine<'a> {
+    module: &'a Module,
+    globals: Vec<Value>,
+}
+
+impl<'a> Machine<'a> {
+    fn new(module: &'a Module, uniform: &Uniform) -> Result<Self> {
+        let mut machine = Self { module, globals: Vec::new() };
+        let mut globals = Vec::with_capacity(module.global_variables.len());
+
+        for (_, global) in module.global_variables.iter() {
+            let value = match global.space {
+                naga::AddressSpace::Uniform => machine.uniform_value(global.ty, uniform)?,
+                naga::AddressSpace::Handle => Value::Opaque,
+                _ => match global.init {
+                    Some(init) => machine.eval_const(init)?,
+                    None => machine.zero_value(global.ty)?,
+                },
+            };
+            globals.push(value);
+        }
+
+        machine.globals = globals;
+        Ok(machine)
+    }
+
+    /// Builds the uniform block by matching member names against the Uniform fields
+    fn uniform_value(&self, ty: Handle<naga::Type>, uniform: &Uniform) -> Result<Value> {
+        let TypeInner::Struct { members, .. } = &self.module.types[ty].inner else {
+            return self.zero_value(ty);
+        };
+
+        members.iter()
+            .map(|member| Ok(match member.name.as_deref() {
+                Some("cursor") => Value::vector_f32(&uniform.cursor),
+                Some("mouse_down") => Value::U32(uniform.mouse_down),
+                Some("mouse_press") => Value::vector_f32(&uniform.mouse_press),
+                Some("mouse_release") => Value::vector_f32(&uniform.mouse_release),
+                Some("resolution") => Value::vector_f32(&uniform.resolution),
+                Some("time") => Value::F32(uniform.time),
+                _ => self.zero_value(member.ty)?,
+            }))
+            .collect::<Result<_>>()
+            .map(Value::Composite)
+    }
+
+    fn entry_input(&self, binding: Option<&Binding>, ty: Handle<naga::Type>, position: &Value) -> Result<Value> {
+        match binding {
+            Some(Binding::BuiltIn(BuiltIn::Position { .. })) => Ok(position.clone()),
+            Some(Binding::BuiltIn(BuiltIn::FrontFacing)) => Ok(Value::Bool(true)),
+            Some(other) => bail!("Unsupported fragment input {:?}", other),
+            None => match &self.module.types[ty].inner {
+                TypeInner::Struct { members, .. } => members.iter()
+                    .map(|m| self.entry_input(m.binding.as_ref(), m.ty, position))
+                    .collect::<Result<_>>()
+                    .map(Value::Composite),
+                _ => bail!("Fragment input without binding"),
+            },
+        }
+    }
+
+    fn zero_value(&self, ty: Handle<naga::Type>) -> Result<Value> {
+        Ok(match &self.module.types[ty].inner {
+            TypeInner::Scalar(scalar) => zero_scalar(scalar.kind)?,
+            TypeInner::Vector { size, scalar } => Value::Vector(vec![zero_scalar(scalar.kind)?; *size as usize]),
+            TypeInner::Matrix { columns, rows, scalar } => Value::Matrix(
+                vec![Value::Vector(vec![zero_scalar(scalar.kind)?; *rows as usize]); *columns as usize],
+            ),
+            TypeInner::Array { base, size: naga::ArraySize::Constant(len), .. } => {
+                Value::Composite(vec![self.zero_value(*base)?; len.get() as usize])
+            }
+            TypeInner::Struct { members, .. } => Value::Composite(
+                members.iter().map(|m| self.zero_value(m.ty)).collect::<Result<_>>()?,
+            ),
+            TypeInner::Image { .. } | TypeInner::Sampler { .. } => Value::Opaque,
+            other => bail!("Unsupported type {:?}", other),
+        })
+    }
+
+    fn eval_const(&self, handle: Handle<Expression>) -> Result<Value> {
+        let mut frame = Frame {
+            expressions: &self.module.global_expressions,
+            arguments: Vec::new(),
+            values: vec![None; self.module.global_expressions.len()],
+            locals: Vec::new(),
+            references: Vec::new(),
+        };
+        self.eval(&self.module.global_expressions, &mut frame, handle)
+    }
+
+    /// Runs `function`, returning its result and the final `references`.
+    fn call(
+        &mut self,
+        function: &naga::Function,
+        arguments: Vec<Value>,
+        references: Vec<Value>,
+    ) -> Result<(Option<Value>, Vec<Value>)> {
+        let mut frame = Frame {
+            expressions: &function.expressions,
+            arguments,
+            values: vec![None; function.expressions.len()],
+            locals: Vec::with_capacity(function.local_variables.len()),
+            references,
+        };
+
+        for (_, local) in function.local_variables.iter() {
+            let value = match local.init {
+                Some(init) => self.eval(&function.expressions, &mut frame, init)?,
+                None => self.zero_value(local.ty)?,
+            };
+            frame.locals.push(value);
+        }
+
+        let value = match self.run_block(&mut frame, &function.body)? {
+            Flow::Return(value) => value,
+            _ => None,
+        };
+        Ok((value, frame.references))
+    }
+
+    fn run_block(&mut self, frame: &mut Frame, block: &naga::Block) -> Result<Flow> {
+        for statement in block.iter() {
+            match self.run_statement(frame, statement)? {
+                Flow::Next => {}
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn run_statement(&mut self, frame: &mut Frame, statement: &Statement) -> Result<Flow> {
+        let expressions = frame.expressions;
+        match statement {
+            Statement::Emit(range) => {
+                // Evaluate in order so loads observe the stores before them
+                for handle in range.clone() {
+                    frame.values[handle.index()] = None;
+                    self.eval(expressions, frame, handle)?;
+                }
+            }
+            Statement::Block(block) => return self.run_block(frame, block),
+            Statement::If { condition, accept, reject } => {
+                let condition = self.eval(expressions, frame, *condition)?.as_bool()?;
+                return self.run_block(frame, if condition { accept } else { reject });
+            }
+            Statement::Switch { selector, cases } => {
+                let selector = self.eval(expressions, frame, *selector)?;
+                let start = cases.iter().position(|case| match (&case.value, &selector) {
+                    (SwitchValue::I32(v), Value::I32(s)) => v == s,
+                    (SwitchValue::U32(v), Value::U32(s)) => v == s,
+                    _ => false,
+                }).or_else(|| cases.iter().position(|case| case.value == SwitchValue::Default));
+
+                if let Some(start) = start {
+                    for case in &cases[start..] {
+                        match self.run_block(frame, &case.body)? {
+                            Flow::Next if case.fall_through => continue,
+                            Flow::Next | Flow::Break => break,
+                            flow => return Ok(flow),
+                        }
+                    }
+                }
+            }
+            Statement::Loop { body, continuing, break_if } => {
+                for _ in 0..MAX_LOOP_ITERATIONS {
+                    match self.run_block(frame, body)? {
+                        Flow::Break => return Ok(Flow::Next),
+                        Flow::Next | Flow::Continue => {}
+                        flow => return Ok(flow),
+                    }
+                    match self.run_block(frame, continuing)? {
+                        Flow::Break => return Ok(Flow::Next),
+                        Flow::Next | Flow::Continue => {}
+                        flow => return Ok(flow),
+                    }
+                    if let Some(condition) = break_if {
+                        if self.eval(expressions, frame, *condition)?.as_bool()? {
+                            return Ok(Flow::Next);
+                        }
+                    }
+                }
+                bail!("Loop exceeded {} iterations", MAX_LOOP_ITERATIONS);
+            }
+            Statement::Break => return Ok(Flow::Break),
+            Statement::Continue => return Ok(Flow::Continue),
+            Statement::Return { value } => {
+                let value = value.map(|v| self.eval(expressions, frame, v)).transpose()?;
+                return Ok(Flow::Return(value));
+            }
+            Statement::Kill => return Ok(Flow::Kill),
+            Statement::Barrier(_) => {}
+            Statement::Store { pointer, value } => {
+                let pointer = self.eval(expressions, frame, *pointer)?;
+                let value = self.eval(expressions, frame, *value)?;
+                *self.place(frame, &pointer)? = value;
+            }
+            Statement::Call { function, arguments, result } => {
+                let mut arguments = arguments.iter()
+                    .map(|a| self.eval(expressions, frame, *a))
+                    .collect::<Result<Vec<_>>>()?;
+
+                // Pointers into this frame (GLSL `out`/`inout`) are copied in and out
+                let mut pointers = Vec::new();
+                let mut references = Vec::new();
+                for argument in &mut arguments {
+                    if matches!(argument, Value::Pointer { root: Root::Local(_) | Root::Reference(_), .. }) {
+                        references.push(self.load(frame, argument)?);
+                        let reference = Value::Pointer { root: Root::Reference(pointers.len()), path: Vec::new() };
+                        pointers.push(std::mem::replace(argument, reference));
+                    }
+                }
+
+                let module = self.module;
+                let (value, references) = self.call(&module.functions[*function], arguments, references)?;
+                for (pointer, value) in pointers.iter().zip(references) {
+                    *self.place(frame, pointer)? = value;
+                }
+                if let (Some(result), Some(value)) = (result, value) {
+                    frame.values[result.index()] = Some(value);
+                }
+            }
+            other => bail!("Unsupported statement {:?}", other),
+        }
+        Ok(Flow::Next)
+    }
+
+    /// Resolves a pointer to the variable (or part of it) it points at
+    fn place<'m>(&'m mut self, frame: &'m mut Frame, pointer: &Value) -> Result<&'m mut Value> {
+        let Value::Pointer { root, path } = pointer else {
+            bail!("Expected a pointer, found {:?}", pointer);
+        };
+        let mut place = match root {
+            Root::Local(handle) => &mut frame.locals[handle.index()],
+            Root::Global(handle) => &mut self.globals[handle.index()],
+            Root::Reference(index) => &mut frame.references[*index],
+        };
+        for &index in path {
+            place = place.component_mut(index)?;
+        }
+        Ok(place)
+    }
+
+    fn load(&self, frame: &Frame, pointer: &Value) -> Result<Value> {
+        let Value::Pointer { root, path } = pointer else {
+            bail!("Expected a pointer, found {:?}", pointer);
+        };
+        let mut value = match root {
+            Root::Local(handle) => &frame.locals[handle.index()],
+            Root::Global(handle) => &self.globals[handle.index()],
+            Root::Reference(index) => &frame.references[*index],
+        };
+        for &index in path {
+            let components = value.components()?;
+            value = &components[index.min(components.len().saturating_sub(1))];
+        }
+        Ok(value.clone())
+    }
+
+    fn eval(&self, arena: &naga::Arena<Expression>, frame: &mut Frame, handle: Handle<Expression>) -> Result<Value> {
+        if let Some(value) = &frame.values[handle.index()] {
+            return Ok(value.clone());
+        }
+
+        let eval = |h: Handle<Expression>, frame: &mut Frame| self.eval(arena, frame, h);
+
+        let value = match &arena[handle] {
+            Expression::Literal(literal) => literal_value(literal)?,
+            Expression::Constant(constant) => self.eval_const(self.module.constants[*constant].init)?,
+            Expression::ZeroValue(ty) => self.zero_value(*ty)?,
+            Expression::Compose { ty, components } => {
+                let components = components.iter().map(|c| eval(*c, frame)).collect::<Result<Vec<_>>>()?;
+                match &self.module.types[*ty].inner {
+                    // Vectors may be composed from smaller vectors
+                    TypeInner::Vector { .. } => Value::Vector(
+                        components.into_iter()
+                            .flat_map(|c| match c {
+                                Value::Vector(inner) => inner,
+                                scalar => vec![scalar],
+                            })
+                            .collect(),
+                    ),
+                    TypeInner::Matrix { .. } => Value::Matrix(components),
+                    _ => Value::Composite(components),
+                }
+            }
+            Expression::Access { base, index } => {
+                let index = eval(*index, frame)?.as_index()?;
+                access(eval(*base, frame)?, index)?
+            }
+            Expression::AccessIndex { base, index } => access(eval(*base, frame)?, *index as usize)?,
+            Expression::Splat { size, value } => Value::Vector(vec![eval(*value, frame)?; *size as usize]),
+            Expression::Swizzle { size, vector, pattern } => {
+                let vector = eval(*vector, frame)?;
+                Value::Vector(
+                    pattern[..*size as usize].iter()
+                        .map(|c| vector.component(*c as usize))
+                        .collect::<Result<_>>()?,
+                )
+            }
+            Expression::FunctionArgument(index) => frame.arguments[*index as usize].clone(),
+            Expression::GlobalVariable(global) => {
+                let var = &self.module.global_variables[*global];
+                if var.space == naga::AddressSpace::Handle {
+                    Value::Opaque
+                } else {
+                    Value::Pointer { root: Root::Global(*global), path: Vec::new() }
+                }
+            }
+            Expression::LocalVariable(local) => Value::Pointer { root: Root::Local(*local), path: Vec::new() },
+            Expression::Load { pointer } => {
+                let pointer = eval(*pointer, frame)?;
+                self.load(frame, &pointer)?
+            }
+            Expression::Unary { op, expr } => {
+                let value = eval(*expr, frame)?;
+                unary(*op, &value)?
+            }
+            Expression::Binary { op, left, right } => {
+                let left = eval(*left, frame)?;
+                let right = eval(*right, frame)?;
+                binary(*op, &left, &right)?
+            }
+            Expression::Select { condition, accept, reject } => {
+                let condition = eval(*condition, frame)?;
+                let accept = eval(*accept, frame)?;
+                let reject = eval(*reject, frame)?;
+                condition.zip3(&accept, &reject, &|c, a, r| Ok(if c.as_bool()? { a.clone() } else { r.clone() }))?
+            }
+            Expression::Relational { fun, argument } => {
+                let argument = eval(*argument, frame)?;
+                relational(*fun, &argument)?
+            }
+            Expression::Math { fun, arg, arg1, arg2, arg3 } => {
+                let mut args = vec![eval(*arg, frame)?];
+                for extra in [arg1, arg2, arg3].into_iter().flatten() {
+                    args.push(eval(*extra, frame)?);
+                }
+                math(*fun, &args)?
+            }
+            Expression::As { expr, kind, convert } => {
+                let value = eval(*expr, frame)?;
+                value.map(&|v| cast(v, *kind, convert.is_some()))?
+            }
+            Expression::CallResult(_) => bail!("Call result read before the call"),
+            Expression::ImageSample { .. } | Expression::ImageLoad { .. } | Expression::ImageQuery { .. } => {
+                bail!("Textures are not supported by the reference renderer")
+            }
+            Expression::Derivative { .. } => bail!("Derivatives are not supported by the reference renderer"),
+            other => bail!("Unsupported expression {:?}", other),
+        };
+
+        frame.values[handle.index()] = Some(value.clone());
+        Ok(value)
+    }
+}
+
+fn zero_scalar(kind: ScalarKind) -> Result<Value> {
+    Ok(match kind {
+        ScalarKind::Bool => Value::Bool(false),
+        ScalarKind::Sint => Value::I32(0),
+        ScalarKind::Uint => Value::U32(0),
+        ScalarKind::Float => Value::F32(0.0),
+        other => bail!("Unsupported scalar kind {:?}", other),
+    })
+}
+
+fn literal_value(literal: &naga::Literal) -> Result<Value> {
+    Ok(match *literal {
+        naga::Literal::F32(v) => Value::F32(v),
+        naga::Literal::F64(v) | naga::Literal::AbstractFloat(v) => Value::F32(v as f32),
+        naga::Literal::U32(v) => Value::U32(v),
+        naga::Literal::I32(v) => Value::I32(v),
+        naga::Literal::I64(v) | naga::Literal::AbstractInt(v) => Value::I32(v as i32),
+        naga::Literal::U64(v) => Value::U32(v as u32),
+        naga::Literal::Bool(v) => Value::Bool(v),
+    })
+}
+
+/// Indexes a value, or extends a pointer's path
+fn access(base: Value, index: usize) -> Result<Value> {
+    match base {
+        Value::Pointer { root, mut path } => {
+            path.push(index);
+            Ok(Value::Pointer { root, path })
+        }
+        value => value.component(index),
+    }
+}
+
+fn unary(op: UnaryOperator, value: &Value) -> Result<Value> {
+    value.map(&|v| Ok(match (op, v) {
+        (UnaryOperator::Negate, Value::F32(x)) => Value::F32(-x),
+        (UnaryOperator::Negate, Value::I32(x)) => Value::I32(x.wrapping_neg()),
+        (UnaryOperator::LogicalNot, Value::Bool(x)) => Value::Bool(!x),
+        (UnaryOperator::BitwiseNot, Value::I32(x)) => Value::I32(!x),
+        (UnaryOperator::BitwiseNot, Value::U32(x)) => Value::U32(!x),
+        (op, v) => bail!("Unsupported unary {:?} on {:?}", op, v),
+    }))
+}
+
+fn binary(op: BinaryOperator, left: &Value, right: &Value) -> Result<Value> {
+    if op == BinaryOperator::Multiply {
+        match (left, right) {
+            (Value::Matrix(columns), Value::Vector(v)) => {
+                // Linear combination of the columns
+                let mut out = vec![0.0; columns[0].components()?.len()];
+                for (column, scale) in columns.iter().zip(v) {
+                    for (o, c) in out.iter_mut().zip(column.floats()?) {
+                        *o += c * scale.as_f32()?;
+                    }
+                }
+                return Ok(Value::vector_f32(&out));
+            }
+            (Value::Vector(v), Value::Matrix(columns)) => {
+                let v = Value::Vector(v.clone());
+                return columns.iter()
+                    .map(|column| math(MathFunction::Dot, &[v.clone(), column.clone()]))
+                    .collect::<Result<_>>()
+                    .map(Value::Vector);
+            }
+            (Value::Matrix(_), Value::Matrix(columns)) => {
+                return columns.iter()
+                    .map(|column| binary(op, left, column))
+                    .collect::<Result<_>>()
+                    .map(Value::Matrix);
+            }
+            (Value::Matrix(columns), scalar) | (scalar, Value::Matrix(columns)) => {
+                return columns.iter()
+                    .map(|column| binary(op, column, scalar))
+                    .collect::<Result<_>>()
+                    .map(Value::Matrix);
+            }
+            _ => {}
+        }
+    }
+    if let (Value::Matrix(a), Value::Matrix(b)) = (left, right) {
+        return a.iter().zip(b)
+            .map(|(a, b)| binary(op, a, b))
+            .collect::<Result<_>>()
+            .map(Value::Matrix);
+    }
+
+    left.zip(right, &|a, b| scalar_binary(op, a, b))
+}
+
+fn scalar_binary(op: BinaryOperator, a: &Value, b: &Value) -> Result<Value> {
+    use BinaryOperator as B;
+    Ok(match (a, b) {
+        (Value::F32(a), Value::F32(b)) => {
+            let (a, b) = (*a, *b);
+            match op {
+                B::Add => Value::F32(a + b),
+                B::Subtract => Value::F32(a - b),
+                B::Multiply => Value::F32(a * b),
+                B::Divide => Value::F32(a / b),
+                // WGSL `%` on floats truncates (Like Rust)
+                B::Modulo => Value::F32(a % b),
+                B::Equal => Value::Bool(a == b),
+                B::NotEqual => Value::Bool(a != b),
+                B::Less => Value::Bool(a < b),
+                B::LessEqual => Value::Bool(a <= b),
+                B::Greater => Value::Bool(a > b),
+                B::GreaterEqual => Value::Bool(a >= b),
+                _ => bail!("Unsupported float operator {:?}", op),
+            }
+        }
+        (Value::I32(a), Value::I32(b)) => {
+            let (a, b) = (*a, *b);
+            match op {
+                B::Add => Value::I32(a.wrapping_add(b)),
+                B::Subtract => Value::I32(a.wrapping_sub(b)),
+                B::Multiply => Value::I32(a.wrapping_mul(b)),
+                B::Divide => Value::I32(a.checked_div(b).unwrap_or(a)),
+                B::Modulo => Value::I32(a.checked_rem(b).unwrap_or(0)),
+                B::Equal => Value::Bool(a == b),
+                B::NotEqual => Value::Bool(a != b),
+                B::Less => Value::Bool(a < b),
+                B::LessEqual => Value::Bool(a <= b),
+                B::Greater => Value::Bool(a > b),
+                B::GreaterEqual => Value::Bool(a >= b),
+                B::And => Value::I32(a & b),
+                B::ExclusiveOr => Value::I32(a ^ b),
+                B::InclusiveOr => Value::I32(a | b),
+                _ => bail!("Unsupported integer operator {:?}", op),
+            }
+        }
+        (Value::I32(a), Value::U32(b)) => match op {
+            B::ShiftLeft => Value::I32(a.wrapping_shl(*b)),
+            B::ShiftRight => Value::I32(a.wrapping_shr(*b)),
+            _ => bail!("Unsupported integer operator {:?}", op),
+        },
+        (Value::U32(a), Value::U32(b)) => {
+            let (a, b) = (*a, *b);
+            match op {
+                B::Add => Value::U32(a.wrapping_add(b)),
+                B::Subtract => Value::U32(a.wrapping_sub(b)),
+                B::Multiply => Value::U32(a.wrapping_mul(b)),
+                B::Divide => Value::U32(a.checked_div(b).unwrap_or(a)),
+                B::Modulo => Value::U32(a.checked_rem(b).unwrap_or(0)),
+                B::Equal => Value::Bool(a == b),
+                B::NotEqual => Value::Bool(a != b),
+                B::Less => Value::Bool(a < b),
+                B::LessEqual => Value::Bool(a <= b),
+                B::Greater => Value::Bool(a > b),
+                B::GreaterEqual => Value::Bool(a >= b),
+                B::And => Value::U32(a & b),
+                B::ExclusiveOr => Value::U32(a ^ b),
+                B::InclusiveOr => Value::U32(a | b),
+                B::ShiftLeft => Value::U32(a.wrapping_shl(b)),
+                B::ShiftRight => Value::U32(a.wrapping_shr(b)),
+                _ => bail!("Unsupported integer operator {:?}", op),
+            }
+        }
+        (Value::Bool(a), Value::Bool(b)) => match op {
+            B::Equal => Value::Bool(a == b),
+            B::NotEqual => Value::Bool(a != b),
+            B::And | B::LogicalAnd => Value::Bool(*a && *b),
+            B::InclusiveOr | B::LogicalOr => Value::Bool(*a || *b),
+            _ => bail!("Unsupported boolean operator {:?}", op),
+        },
+        (a, b) => bail!("Unsupported operands {:?} {:?} {:?}", a, op, b),
+    })
+}
+
+fn relational(fun: RelationalFunction, argument: &Value) -> Result<Value> {
+    match fun {
+        RelationalFunction::All => Ok(Value::Bool(
+            argument.components()?.iter().map(Value::as_bool).collect::<Result<Vec<_>>>()?.iter().all(|b| *b),
+        )),
+        RelationalFunction::Any => Ok(Value::Bool(
+            argument.components()?.iter().map(Value::as_bool).collect::<Result<Vec<_>>>()?.iter().any(|b| *b),
+        )),
+        RelationalFunction::IsNan => argument.map(&|v| Ok(Value::Bool(v.as_f32()?.is_nan()))),
+        RelationalFunction::IsInf => argument.map(&|v| Ok(Value::Bool(v.as_f32()?.is_infinite()))),
+    }
+}
+
+fn cast(value: &Value, kind: ScalarKind, convert: bool) -> Result<Value> {
+    if !convert {
+        // Bitcast
+        let bits = match value {
+            Value::F32(v) => v.to_bits(),
+            Value::I32(v) => *v as u32,
+            Value::U32(v) => *v,
+            other => bail!("Cannot bitcast {:?}", other),
+        };
+        return Ok(match kind {
+            ScalarKind::Float => Value::F32(f32::from_bits(bits)),
+            ScalarKind::Sint => Value::I32(bits as i32),
+            ScalarKind::Uint => Value::U32(bits),
+            other => bail!("Cannot bitcast to {:?}", other),
+        });
+    }
+
+    let as_f32 = match value {
+        Value::F32(v) => *v,
+        Value::I32(v) => *v as f32,
+        Value::U32(v) => *v as f32,
+        Value::Bool(v) => *v as u32 as f32,
+        other => bail!("Cannot convert {:?}", other),
+    };
+    Ok(match (kind, value) {
+        (ScalarKind::Float, _) => Value::F32(as_f32),
+        (ScalarKind::Sint, Value::U32(v)) => Value::I32(*v as i32),
+        (ScalarKind::Sint, Value::I32(v)) => Value::I32(*v),
+        (ScalarKind::Sint, _) => Value::I32(as_f32 as i32), // Saturating, like WGSL
+        (ScalarKind::Uint, Value::I32(v)) => Value::U32(*v as u32),
+        (ScalarKind::Uint, Value::U32(v)) => Value::U32(*v),
+        (ScalarKind::Uint, _) => Value::U32(as_f32 as u32),
+        (ScalarKind::Bool, _) => Value::Bool(as_f32 != 0.0),
+        (other, _) => bail!("Cannot convert to {:?}", other),
+    })
+}
+
+fn math(fun: MathFunction, args: &[Value]) -> Result<Value> {
+    use MathFunction as M;
+    let arg = |i: usize| args.get(i).with_context(|| format!("{:?} is missing argument {}", fun, i));
+    let a = arg(0)?;
+
+    Ok(match fun {
+        // comparison
+        M::Abs => a.map(&|v| Ok(match v {
+            Value::F32(x) => Value::F32(x.abs()),
+            Value::I32(x) => Value::I32(x.wrapping_abs()),
+            other => other.clone(),
+        }))?,
+        M::Min => a.zip(arg(1)?, &|x, y| min_max(x, y, true))?,
+        M::Max => a.zip(arg(1)?, &|x, y| min_max(x, y, false))?,
+        M::Clamp => a.zip3(arg(1)?, arg(2)?, &|x, lo, hi| min_max(&min_max(x, lo, false)?, hi, true))?,
+        M::Saturate => a.map(&float_fn(|x| x.clamp(0.0, 1.0)))?,
+        // trigonometry
+        M::Cos => a.map(&float_fn(f32::cos))?,
+        M::Cosh => a.map(&float_fn(f32::cosh))?,
+        M::Sin => a.map(&float_fn(f32::sin))?,
+        M::Sinh => a.map(&float_fn(f32::sinh))?,
+        M::Tan => a.map(&float_fn(f32::tan))?,
+        M::Tanh => a.map(&float_fn(f32::tanh))?,
+        M::Acos => a.map(&float_fn(f32::acos))?,
+        M::Asin => a.map(&float_fn(f32::asin))?,
+        M::Atan => a.map(&float_fn(f32::atan))?,
+        M::Atan2 => a.zip(arg(1)?, &|y, x| Ok(Value::F32(y.as_f32()?.atan2(x.as_f32()?))))?,
+        M::Asinh => a.map(&float_fn(f32::asinh))?,
+        M::Acosh => a.map(&float_fn(f32::acosh))?,
+        M::Atanh => a.map(&float_fn(f32::atanh))?,
+        M::Radians => a.map(&float_fn(f32::to_radians))?,
+        M::Degrees => a.map(&float_fn(f32::to_degrees))?,
+        // decomposition
+        M::Ceil => a.map(&float_fn(f32::ceil))?,
+        M::Floor => a.map(&float_fn(f32::floor))?,
+        M::Round => a.map(&float_fn(f32::round_ties_even))?,
+        M::Fract => a.map(&float_fn(|x| x - x.floor()))?,
+        M::Trunc => a.map(&float_fn(f32::trunc))?,
+        // exponent
+        M::Exp => a.map(&float_fn(f32::exp))?,
+        M::Exp2 => a.map(&float_fn(f32::exp2))?,
+        M::Log => a.map(&float_fn(f32::ln))?,
+        M::Log2 => a.map(&float_fn(f32::log2))?,
+        M::Pow => a.zip(arg(1)?, &|x, y| Ok(Value::F32(x.as_f32()?.powf(y.as_f32()?))))?,
+        // geometry
+        M::Dot => {
+            let (x, y) = (a.floats()?, arg(1)?.floats()?);
+            Value::F32(x.iter().zip(&y).map(|(x, y)| x * y).sum())
+        }
+        M::Cross => {
+            let (x, y) = (a.floats()?, arg(1)?.floats()?);
+            Value::vector_f32(&[
+                x[1] * y[2] - x[2] * y[1],
+                x[2] * y[0] - x[0] * y[2],
+                x[0] * y[1] - x[1] * y[0],
+            ])
+        }
+        M::Length => Value::F32(length(&a.floats()?)),
+        M::Distance => {
+            let (x, y) = (a.floats()?, arg(1)?.floats()?);
+            let d: Vec<f32> = x.iter().zip(&y).map(|(x, y)| x - y).collect();
+            Value::F32(length(&d))
+        }
+        M::Normalize => {
+            let x = a.floats()?;
+            let len = length(&x);
+            Value::vector_f32(&x.iter().map(|c| c / len).collect::<Vec<_>>())
+        }
+        M::FaceForward => {
+            let (n, i, r) = (a, arg(1)?, arg(2)?);
+            let d = math(M::Dot, &[r.clone(), i.clone()])?.as_f32()?;
+            if d < 0.0 { n.clone() } else { unary(UnaryOperator::Negate, n)? }
+        }
+        M::Reflect => {
+            let (i, n) = (a, arg(1)?);
+            let d = math(M::Dot, &[n.clone(), i.clone()])?.as_f32()?;
+            i.zip(n, &|i, n| Ok(Value::F32(i.as_f32()? - 2.0 * d * n.as_f32()?)))?
+        }
+        // computational
+        M::Sign => a.map(&|v| Ok(match v {
+            Value::F32(x) => Value::F32(if *x > 0.0 { 1.0 } else if *x < 0.0 { -1.0 } else { 0.0 }),
+            Value::I32(x) => Value::I32(x.signum()),
+            other => other.clone(),
+        }))?,
+        M::Fma => a.zip3(arg(1)?, arg(2)?, &|x, y, z| Ok(Value::F32(x.as_f32()?.mul_add(y.as_f32()?, z.as_f32()?))))?,
+        M::Mix => a.zip3(arg(1)?, arg(2)?, &|x, y, t| {
+            let (x, y, t) = (x.as_f32()?, y.as_f32()?, t.as_f32()?);
+            Ok(Value::F32(x * (1.0 - t) + y * t))
+        })?,
+        M::Step => a.zip(arg(1)?, &|edge, x| Ok(Value::F32(if x.as_f32()? < edge.as_f32()? { 0.0 } else { 1.0 })))?,
+        M::SmoothStep => a.zip3(arg(1)?, arg(2)?, &|e0, e1, x| {
+            let (e0, e1, x) = (e0.as_f32()?, e1.as_f32()?, x.as_f32()?);
+            let t = ((x - e0) / (e1 - e0)).clamp(0.0, 1.0);
+            Ok(Value::F32(t * t * (3.0 - 2.0 * t)))
+        })?,
+        M::Sqrt => a.map(&float_fn(f32::sqrt))?,
+        M::InverseSqrt => a.map(&float_fn(|x| 1.0 / x.sqrt()))?,
+        M::Transpose => {
+            let Value::Matrix(columns) = a else { bail!("transpose() expects a matrix") };
+            let rows = columns[0].components()?.len();
+            Value::Matrix(
+                (0..rows)
+                    .map(|r| Ok(Value::Vector(columns.iter().map(|c| c.component(r)).collect::<Result<_>>()?)))
+                    .collect::<Result<_>>()?,
+            )
+        }
+        other => bail!("Unsupported math function {:?}", other),
+    })
+}
+
+fn length(values: &[f32]) -> f32 {
+    values.iter().map(|v| v * v).sum::<f32>().sqrt()
+}
+
+fn min_max(a: &Value, b: &Value, min: bool) -> Result<Value> {
+    Ok(match (a, b) {
+        (Value::F32(a), Value::F32(b)) => Value::F32(if min { a.min(*b) } else { a.max(*b) }),
+        (Value::I32(a), Value::I32(b)) => Value::I32(if min { *a.min(b) } else { *a.max(b) }),
+        (Value::U32(a), Value::U32(b)) => Value::U32(if min { *a.min(b) } else { *a.max(b) }),
+        (a, b) => bail!("Unsupported min/max operands {:?} {:?}", a, b),
+    })
+}
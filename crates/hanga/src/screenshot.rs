@@ -0,0 +1,250 @@
+use gyo_core::texture::Texture;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+
+/// A pending capture of a presented frame.
+/// Resolves once the GPU has finished copying the frame back to the CPU.
+pub struct Screenshot {
+    receiver: Receiver<anyhow::Result<Texture>>,
+}
+
+impl Screenshot {
+    /// Returns the captured image if the readback has completed (Non-blocking)
+    pub fn try_take(&self) -> Option<anyhow::Result<Texture>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(anyhow::anyhow!("Screenshot was dropped by the engine"))),
+        }
+    }
+
+    /// Blocks until the capture is available.
+    /// Do NOT call this on the render thread: the engine completes captures while rendering.
+    pub fn wait(self) -> anyhow::Result<Texture> {
+        self.receiver.recv()
+            .map_err(|_| anyhow::anyhow!("Screenshot was dropped by the engine"))?
+    }
+
+    /// Writes the capture to a PNG file from a background thread once it is ready.
+    pub fn save_png_in_background(self, path: impl Into<PathBuf>) -> std::thread::JoinHandle<anyhow::Result<()>> {
+        let path = path.into();
+        std::thread::spawn(move || self.wait()?.save_png(&path))
+    }
+}
+
+/// A frame copy that has been recorded but not yet read back.
+struct InFlight {
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+    format: wgpu::TextureFormat,
+    /// Set once the copy has been submitted and the buffer mapping requested
+    mapped: Option<Receiver<Result<(), wgpu::BufferAsyncError>>>,
+    senders: Vec<Sender<anyhow::Result<Texture>>>,
+}
+
+/// Tracks requested and in-flight screenshots for an engine.
+#[derive(Default)]
+pub(crate) struct ScreenshotQueue {
+    requested: Vec<Sender<anyhow::Result<Texture>>>,
+    in_flight: Vec<InFlight>,
+}
+
+impl ScreenshotQueue {
+    pub fn request(&mut self) -> Screenshot {
+        let (sender, receiver) = mpsc::channel();
+        self.requested.push(sender);
+        Screenshot { receiver }
+    }
+
+    /// Fails every outstanding request (e.g. the surface cannot be copied from)
+    pub fn reject(&mut self, reason: &str) {
+        for sender in self.requested.drain(..) {
+            let _ = sender.send(Err(anyhow::anyhow!("{}", reason)));
+        }
+    }
+
+    /// Records a copy of `texture` into a readback buffer.
+    /// Every request made before this frame is answered by the same copy.
+    pub fn encode_copy(&mut self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder, texture: &wgpu::Texture) {
+        if self.requested.is_empty() { return; }
+
+        let format = texture.format();
+        if !is_capturable(format) {
+            self.reject(&format!("Cannot capture frames of format {:?}", format));
+            return;
+        }
+
+        let width = texture.width();
+        let height = texture.height();
+        let padded_bytes_per_row = padded_bytes_per_row(width);
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Screenshot Readback Buffer"),
+            size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+
+        self.in_flight.push(InFlight {
+            buffer,
+            width,
+            height,
+            padded_bytes_per_row,
+            format,
+            mapped: None,
+            senders: std::mem::take(&mut self.requested),
+        });
+    }
+
+    /// Requests mapping of every copy recorded since the last submit.
+    /// Must be called AFTER the encoder holding the copies was submitted.
+    pub fn submitted(&mut self) {
+        for capture in self.in_flight.iter_mut().filter(|c| c.mapped.is_none()) {
+            let (sender, receiver) = mpsc::channel();
+            capture.buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+            capture.mapped = Some(receiver);
+        }
+    }
+
+    /// Hands finished captures back to their requesters (Non-blocking)
+    pub fn poll(&mut self, device: &wgpu::Device) {
+        if self.in_flight.is_empty() { return; }
+        device.poll(wgpu::Maintain::Poll);
+
+        let mut index = 0;
+        while index < self.in_flight.len() {
+            let status = match &self.in_flight[index].mapped {
+                Some(mapped) => mapped.try_recv(),
+                None => Err(TryRecvError::Empty),
+            };
+
+            match status {
+                Err(TryRecvError::Empty) => index += 1,
+                Ok(result) => {
+                    let capture = self.in_flight.swap_remove(index);
+                    let image = result
+                        .map_err(anyhow::Error::from)
+                        .map(|_| capture.read());
+                    capture.answer(image);
+                }
+                Err(TryRecvError::Disconnected) => {
+                    let capture = self.in_flight.swap_remove(index);
+                    capture.answer(Err(anyhow::anyhow!("Screenshot readback was cancelled")));
+                }
+            }
+        }
+    }
+}
+
+impl InFlight {
+    /// Strips the row padding and converts the mapped pixels to RGBA
+    fn read(&self) -> Texture {
+        let data = {
+            let mapped = self.buffer.slice(..).get_mapped_range();
+            unpad_rows(&mapped, self.width, self.height, self.padded_bytes_per_row, self.format)
+        };
+        self.buffer.unmap();
+
+        Texture::new(self.width, self.height, data)
+    }
+
+    fn answer(self, image: anyhow::Result<Texture>) {
+        match image {
+            Ok(image) => {
+                for sender in &self.senders {
+                    let _ = sender.send(Ok(image.clone()));
+                }
+            }
+            Err(err) => {
+                for sender in &self.senders {
+                    let _ = sender.send(Err(anyhow::anyhow!("{:#}", err)));
+                }
+            }
+        }
+    }
+}
+
+/// Rows of a buffer copy must be aligned to `COPY_BYTES_PER_ROW_ALIGNMENT` (256 bytes)
+pub(crate) fn padded_bytes_per_row(width: u32) -> u32 {
+    let unpadded = width * 4;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    unpadded.div_ceil(align) * align
+}
+
+/// Copies the visible part of every padded row, swapping BGRA pixels to RGBA
+fn unpad_rows(mapped: &[u8], width: u32, height: u32, padded_bytes_per_row: u32, format: wgpu::TextureFormat) -> Vec<u8> {
+    let unpadded_bytes_per_row = (width * 4) as usize;
+    let mut data = Vec::with_capacity(unpadded_bytes_per_row * height as usize);
+    for row in mapped.chunks(padded_bytes_per_row as usize).take(height as usize) {
+        data.extend_from_slice(&row[..unpadded_bytes_per_row]);
+    }
+
+    if is_bgra(format) {
+        for pixel in data.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+    data
+}
+
+fn is_bgra(format: wgpu::TextureFormat) -> bool {
+    matches!(format, wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb)
+}
+
+fn is_capturable(format: wgpu::TextureFormat) -> bool {
+    is_bgra(format) || matches!(format, wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_are_padded_to_the_copy_alignment() {
+        assert_eq!(padded_bytes_per_row(64), 256, "Already aligned");
+        assert_eq!(padded_bytes_per_row(100), 512, "400 bytes round up to the next 256");
+        assert_eq!(padded_bytes_per_row(1), 256);
+    }
+
+    #[test]
+    fn padding_is_stripped_and_bgra_is_swapped() {
+        let (width, height) = (100, 3);
+        let padded = padded_bytes_per_row(width);
+
+        // Every pixel is (B, G, R, A) = (row, 1, 2, 3); the padding is filled with garbage
+        let mut mapped = vec![0xEE; (padded * height) as usize];
+        for (y, row) in mapped.chunks_mut(padded as usize).enumerate() {
+            for pixel in row[..(width * 4) as usize].chunks_exact_mut(4) {
+                pixel.copy_from_slice(&[y as u8, 1, 2, 3]);
+            }
+        }
+
+        let bgra = unpad_rows(&mapped, width, height, padded, wgpu::TextureFormat::Bgra8Unorm);
+        assert_eq!(bgra.len(), (width * height * 4) as usize);
+        for (index, pixel) in bgra.chunks_exact(4).enumerate() {
+            let y = index as u32 / width;
+            assert_eq!(pixel, [2, 1, y as u8, 3], "Pixel {} was not swapped to RGBA", index);
+        }
+
+        let rgba = unpad_rows(&mapped, width, height, padded, wgpu::TextureFormat::Rgba8Unorm);
+        assert!(rgba.chunks_exact(4).all(|pixel| pixel[1..] == [1, 2, 3]), "RGBA rows are copied as is");
+        assert!(!rgba.contains(&0xEE), "Row padding leaked into the image");
+    }
+}
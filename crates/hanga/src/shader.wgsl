@@ -11,8 +11,20 @@ struct InstanceInput {
     @location(1) size: vec2<f32>,
     @location(2) uv_rect: vec4<f32>,
     @location(3) color: vec4<f32>,
+    @location(4) pivot: vec2<f32>,
+    @location(5) transform: vec4<f32>, // Columns of a mat2x2
 };
 
+struct Camera {
+    view_proj: mat4x4<f32>, // World to clip space (Pan, Zoom, Rotation, Virtual size)
+};
+
+@group(0) @binding(1) var<uniform> camera: Camera;
+
+// The atlas of the batch (A white texel for batches without one)
+@group(2) @binding(0) var atlas: texture_2d<f32>;
+@group(2) @binding(1) var atlas_sampler: sampler;
+
 @vertex
 fn vs_main(
     @builtin(vertex_index) in_vertex_index: u32,
@@ -26,24 +38,24 @@ fn vs_main(
     );
     let local_pos = corners[in_vertex_index];
     
-    // Scale and Translate
-    let world_x = instance.pos.x + (local_pos.x * instance.size.x);
-    let world_y = instance.pos.y + (local_pos.y * instance.size.y);
+    // Scale around the pivot, Transform (Rotate, Skew...) and Translate
+    let transform = mat2x2<f32>(instance.transform.xy, instance.transform.zw);
+    let world = instance.pos.xy + transform * ((local_pos - instance.pivot) * instance.size);
     
-    // Convert to NDC (Normalized Device Coordinates: -1.0 to 1.0)
-    // TODO: Pass screen size via Uniforms. Hardcoded to 1280x720 for Prototype.
-    let ndc_x = (world_x / 1280.0) * 2.0 - 1.0;
-    let ndc_y = (world_y / 720.0) * -2.0 + 1.0; // Flip Y for WGPU
+    // Convert to NDC (Normalized Device Coordinates: -1.0 to 1.0) through the camera
+    let ndc = camera.view_proj * vec4<f32>(world, 0.0, 1.0);
 
     var out: VertexOutput;
-    out.clip_position = vec4<f32>(ndc_x, ndc_y, 0.0, 1.0);
-    out.uv = local_pos; 
+    // The layer is the depth (0 in front, 1 at the back)
+    out.clip_position = vec4<f32>(ndc.xy, clamp(instance.pos.z, 0.0, 1.0), 1.0);
+    // The sprite's region of the atlas (Offset + Scale)
+    out.uv = instance.uv_rect.xy + local_pos * instance.uv_rect.zw;
     out.color = instance.color;
     return out;
 }
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    // Just return the instance color for now (Flat shading)
-    return in.color;
+    // The atlas tinted by the instance color
+    return textureSample(atlas, atlas_sampler, in.uv) * in.color;
 }
@@ -0,0 +1,92 @@
+//! The shapes sprite scenes draw over their sprites (See
+//! `pipeline_2d::ShapeBatch`).
+
+use crate::camera::CameraView;
+use crate::pipeline_2d::{ShapeBatch, ShapeInstance};
+use crate::pipeline_state;
+use gyo_core::pipeline::{BlendMode, CompareFunction, DepthState};
+
+/// What `shapes.wgsl` sees of the camera (Matches `View`)
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ViewRaw {
+    view_proj: [[f32; 4]; 4],
+    /// World units across a pixel, then padding
+    pixel: [f32; 4],
+}
+
+pub(crate) struct ShapeLayer {
+    pipeline: wgpu::RenderPipeline,
+    view_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl ShapeLayer {
+    /// Drawn in `format`, in a pass with a depth buffer: Tested against the
+    /// opaque sprites, without writing to it
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, cache: Option<&wgpu::PipelineCache>) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shapes.wgsl"));
+        let depth = DepthState { write: false, compare: CompareFunction::LessEqual };
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Shape Pipeline"),
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[ShapeInstance::desc()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    // The shader blends the stroke over the fill
+                    blend: Some(pipeline_state::blend_state(BlendMode::Premultiplied)),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: pipeline_state::depth_stencil_state(Some(depth)),
+            multisample: wgpu::MultisampleState {
+                count: 1, mask: !0, alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache,
+        });
+        let view_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Shape View Buffer"),
+            size: std::mem::size_of::<ViewRaw>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Shape Bind Group"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[wgpu::BindGroupEntry { binding: 0, resource: view_buffer.as_entire_binding() }],
+        });
+        Self { pipeline, view_buffer, bind_group }
+    }
+
+    /// Sees the shapes through the sprites' camera
+    pub fn prepare(&self, queue: &wgpu::Queue, view: &CameraView) {
+        let raw = ViewRaw { view_proj: view.view_proj(), pixel: [view.pixel_size(), 0.0, 0.0, 0.0] };
+        queue.write_buffer(&self.view_buffer, 0, bytemuck::bytes_of(&raw));
+    }
+
+    pub fn draw<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>, batch: &'a ShapeBatch) {
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        batch.draw(rpass);
+    }
+}
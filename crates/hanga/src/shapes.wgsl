@@ -0,0 +1,79 @@
+// crates/hanga/src/shapes.wgsl
+// Shapes by their signed distance: A rounded rectangle per instance, on a
+// quad padded by the stroke and a pixel of anti-aliasing.
+
+struct View {
+    view_proj: mat4x4<f32>, // The camera of the sprites (See `shader.wgsl`)
+    pixel: vec4<f32>,       // x: World units across a pixel
+};
+
+struct ShapeInput {
+    @location(0) pos: vec3<f32>,       // Centre, layer
+    @location(1) half_size: vec2<f32>,
+    @location(2) axis: vec2<f32>,      // Cosine and sine of the turn
+    @location(3) radius: f32,          // Of the corners
+    @location(4) stroke_width: f32,
+    @location(5) fill: vec4<f32>,
+    @location(6) stroke: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) local: vec2<f32>,     // From the centre, before the turn
+    @location(1) half_size: vec2<f32>,
+    @location(2) shape: vec2<f32>,     // Radius, stroke width
+    @location(3) fill: vec4<f32>,
+    @location(4) stroke: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> view: View;
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) in_vertex_index: u32,
+    shape: ShapeInput,
+) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2(-1.0, -1.0), vec2(-1.0, 1.0), vec2(1.0, 1.0),
+        vec2(-1.0, -1.0), vec2(1.0, 1.0), vec2(1.0, -1.0)
+    );
+    // Room for the outer half of the stroke and the anti-aliasing
+    let pad = shape.stroke_width / 2.0 + view.pixel.x * 1.5;
+    let local = corners[in_vertex_index] * (shape.half_size + vec2(pad));
+    let c = shape.axis;
+    let world = shape.pos.xy + vec2(local.x * c.x - local.y * c.y, local.x * c.y + local.y * c.x);
+    let ndc = view.view_proj * vec4<f32>(world, 0.0, 1.0);
+
+    var out: VertexOutput;
+    // The layer is the depth, like sprites
+    out.clip_position = vec4<f32>(ndc.xy, clamp(shape.pos.z, 0.0, 1.0), 1.0);
+    out.local = local;
+    out.half_size = shape.half_size;
+    out.shape = vec2(shape.radius, shape.stroke_width);
+    out.fill = shape.fill;
+    out.stroke = shape.stroke;
+    return out;
+}
+
+fn sd_rounded_box(p: vec2<f32>, half_size: vec2<f32>, radius: f32) -> f32 {
+    let r = clamp(radius, 0.0, min(half_size.x, half_size.y));
+    let q = abs(p) - half_size + r;
+    return length(max(q, vec2(0.0))) + min(max(q.x, q.y), 0.0) - r;
+}
+
+// How much of a pixel `aa` wide lies where the distance is negative
+fn coverage(d: f32, aa: f32) -> f32 {
+    return clamp(0.5 - d / aa, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let d = sd_rounded_box(in.local, in.half_size, in.shape.x);
+    let aa = max(fwidth(d), 1e-5);
+    let fill = in.fill.a * coverage(d, aa);
+    let stroke = in.stroke.a * coverage(abs(d) - in.shape.y / 2.0, aa) * step(1e-6, in.shape.y);
+
+    // The stroke over the fill, premultiplied
+    let rgb = in.stroke.rgb * stroke + in.fill.rgb * fill * (1.0 - stroke);
+    return vec4<f32>(rgb, stroke + fill * (1.0 - stroke));
+}
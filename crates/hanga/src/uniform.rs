@@ -16,3 +16,6 @@ impl Uniform {
         bytemuck::bytes_of(self)
     }
 }
+
+// Must match the prelude's `Uniforms` block
+const _: () = assert!(std::mem::size_of::<Uniform>() == gyo_core::shader::UNIFORMS_SIZE as usize);
@@ -0,0 +1,288 @@
+//! Vector paths (See `gyo_core::vector`), tessellated into triangles with
+//! lyon and drawn by sprite scenes over their sprites and particles.
+//!
+//! Fills and strokes are tessellated once into a [`VectorMesh`], which a
+//! [`VectorBatch`] draws as often as it is pushed. Edges are not
+//! anti-aliased (The scene has no multisampling): Use shapes (See
+//! `pipeline_2d::ShapeBatch`) where soft edges matter.
+
+use crate::pipeline_2d::{BatchMetrics, FrameUploads, UploadBuffer};
+use crate::pipeline_state;
+use gyo_core::pipeline::{BlendMode, CompareFunction, DepthState};
+use gyo_core::vector::{FillRule, LineCap, LineJoin, Path, PathCommand, StrokeStyle};
+use lyon_tessellation::math::point;
+use lyon_tessellation::{
+    BuffersBuilder, FillOptions, FillTessellator, FillVertex, StrokeOptions, StrokeTessellator, StrokeVertex,
+    VertexBuffers,
+};
+
+/// Largest distance between a curve and its tessellation, in world units
+pub const TOLERANCE: f32 = 0.05;
+
+/// A corner of a tessellated triangle (Matches `VertexInput` in `vector.wgsl`).
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct VectorVertex {
+    /// Position (x, y) + Depth/Layer (z, from 0 in front to 1 at the back)
+    pub pos: [f32; 3],
+    /// Linear RGBA
+    pub color: [f32; 4],
+}
+
+impl VectorVertex {
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        use std::mem;
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<VectorVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                // Location 0: Pos (vec3)
+                wgpu::VertexAttribute {
+                    offset: mem::offset_of!(VectorVertex, pos) as wgpu::BufferAddress,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                // Location 1: Color (vec4)
+                wgpu::VertexAttribute {
+                    offset: mem::offset_of!(VectorVertex, color) as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+/// Triangles of a filled or stroked path.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VectorMesh {
+    pub vertices: Vec<VectorVertex>,
+    /// Three per triangle, into `vertices`
+    pub indices: Vec<u32>,
+}
+
+impl VectorMesh {
+    /// The inside of `path` by `rule`, in `color` (Open subpaths are closed)
+    pub fn fill(path: &Path, rule: FillRule, color: [f32; 4]) -> anyhow::Result<Self> {
+        let rule = match rule {
+            FillRule::NonZero => lyon_tessellation::FillRule::NonZero,
+            FillRule::EvenOdd => lyon_tessellation::FillRule::EvenOdd,
+        };
+        let options = FillOptions::tolerance(TOLERANCE).with_fill_rule(rule);
+        let mut buffers = VertexBuffers::new();
+        FillTessellator::new()
+            .tessellate_path(&to_lyon(path)?, &options, &mut BuffersBuilder::new(&mut buffers, |v: FillVertex| {
+                VectorVertex { pos: [v.position().x, v.position().y, 0.0], color }
+            }))
+            .map_err(|e| anyhow::anyhow!("Failed to fill the path: {:?}", e))?;
+        Ok(Self { vertices: buffers.vertices, indices: buffers.indices })
+    }
+
+    /// The outline of `path` in `style`, in `color`
+    pub fn stroke(path: &Path, style: &StrokeStyle, color: [f32; 4]) -> anyhow::Result<Self> {
+        style.check()?;
+        let join = match style.join {
+            LineJoin::Miter => lyon_tessellation::LineJoin::Miter,
+            LineJoin::Round => lyon_tessellation::LineJoin::Round,
+            LineJoin::Bevel => lyon_tessellation::LineJoin::Bevel,
+        };
+        let cap = match style.cap {
+            LineCap::Butt => lyon_tessellation::LineCap::Butt,
+            LineCap::Square => lyon_tessellation::LineCap::Square,
+            LineCap::Round => lyon_tessellation::LineCap::Round,
+        };
+        let options = StrokeOptions::tolerance(TOLERANCE)
+            .with_line_width(style.width)
+            .with_line_join(join)
+            .with_line_cap(cap)
+            .with_miter_limit(style.miter_limit);
+        let mut buffers = VertexBuffers::new();
+        StrokeTessellator::new()
+            .tessellate_path(&to_lyon(path)?, &options, &mut BuffersBuilder::new(&mut buffers, |v: StrokeVertex| {
+                VectorVertex { pos: [v.position().x, v.position().y, 0.0], color }
+            }))
+            .map_err(|e| anyhow::anyhow!("Failed to stroke the path: {:?}", e))?;
+        Ok(Self { vertices: buffers.vertices, indices: buffers.indices })
+    }
+
+    pub fn with_layer(mut self, z: f32) -> Self {
+        for vertex in &mut self.vertices {
+            vertex.pos[2] = z;
+        }
+        self
+    }
+
+    /// Adds the triangles of `other`, drawn after these (A stroke over its
+    /// fill)
+    pub fn append(&mut self, other: &VectorMesh) {
+        let base = self.vertices.len() as u32;
+        self.vertices.extend_from_slice(&other.vertices);
+        self.indices.extend(other.indices.iter().map(|i| base + i));
+    }
+}
+
+/// The path as lyon builds it
+fn to_lyon(path: &Path) -> anyhow::Result<lyon_tessellation::path::Path> {
+    path.check()?;
+    let p = |[x, y]: [f32; 2]| point(x, y);
+    let mut builder = lyon_tessellation::path::Path::builder();
+    let mut open = false;
+    for command in &path.commands {
+        match *command {
+            PathCommand::MoveTo(to) => {
+                if open {
+                    builder.end(false);
+                }
+                builder.begin(p(to));
+                open = true;
+            }
+            PathCommand::LineTo(to) => {
+                builder.line_to(p(to));
+            }
+            PathCommand::QuadTo { ctrl, to } => {
+                builder.quadratic_bezier_to(p(ctrl), p(to));
+            }
+            PathCommand::CubicTo { ctrl1, ctrl2, to } => {
+                builder.cubic_bezier_to(p(ctrl1), p(ctrl2), p(to));
+            }
+            PathCommand::Close => {
+                builder.end(true);
+                open = false;
+            }
+        }
+    }
+    if open {
+        builder.end(false);
+    }
+    Ok(builder.build())
+}
+
+/// Vector meshes drawn in one pass, in push order (Tested against opaque
+/// sprites, by the layer of their vertices).
+pub struct VectorBatch {
+    mesh: VectorMesh,
+    vertices: UploadBuffer<VectorVertex>,
+    indices: UploadBuffer<u32>,
+    /// Indices uploaded by the last `prepare`
+    drawn: u32,
+}
+
+impl VectorBatch {
+    pub fn new(device: &wgpu::Device, initial_vertices: usize) -> Self {
+        Self {
+            mesh: VectorMesh::default(),
+            vertices: UploadBuffer::new(device, "Vector Vertex Buffer", wgpu::BufferUsages::VERTEX, initial_vertices),
+            indices: UploadBuffer::new(device, "Vector Index Buffer", wgpu::BufferUsages::INDEX, initial_vertices * 3),
+            drawn: 0,
+        }
+    }
+
+    /// Capacity, resizes and the last upload of the vertex and index buffers
+    pub fn metrics(&self) -> [&BatchMetrics; 2] {
+        [self.vertices.metrics(), self.indices.metrics()]
+    }
+
+    pub fn push(&mut self, mesh: &VectorMesh) {
+        self.mesh.append(mesh);
+    }
+
+    /// Clear the batch (call at start of frame)
+    pub fn clear(&mut self) {
+        self.mesh = VectorMesh::default();
+    }
+
+    /// Tessellates and pushes the inside of `path` (See [`VectorMesh::fill`])
+    pub fn fill(&mut self, path: &Path, rule: FillRule, color: [f32; 4]) -> anyhow::Result<()> {
+        self.push(&VectorMesh::fill(path, rule, color)?);
+        Ok(())
+    }
+
+    /// Tessellates and pushes the outline of `path` (See [`VectorMesh::stroke`])
+    pub fn stroke(&mut self, path: &Path, style: &StrokeStyle, color: [f32; 4]) -> anyhow::Result<()> {
+        self.push(&VectorMesh::stroke(path, style, color)?);
+        Ok(())
+    }
+
+    /// Uploads the vertices and indices that changed to the GPU
+    pub(crate) fn prepare(&mut self, device: &wgpu::Device, uploads: &mut FrameUploads) {
+        self.vertices.upload(device, uploads, &self.mesh.vertices);
+        self.indices.upload(device, uploads, &self.mesh.indices);
+        self.drawn = self.mesh.indices.len() as u32;
+    }
+
+    pub fn draw<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>) {
+        if self.drawn == 0 { return; }
+        rpass.set_vertex_buffer(0, self.vertices.slice());
+        rpass.set_index_buffer(self.indices.slice(), wgpu::IndexFormat::Uint32);
+        rpass.draw_indexed(0..self.drawn, 0, 0..1);
+    }
+}
+
+/// The pipeline sprite scenes draw vector batches with.
+pub(crate) struct VectorLayer {
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+}
+
+impl VectorLayer {
+    /// Drawn in `format` through the sprites' camera (`camera_buffer`), in
+    /// a pass with a depth buffer: Tested against the opaque sprites,
+    /// without writing to it
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        camera_buffer: &wgpu::Buffer,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("vector.wgsl"));
+        let depth = DepthState { write: false, compare: CompareFunction::LessEqual };
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Vector Pipeline"),
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[VectorVertex::desc()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(pipeline_state::blend_state(BlendMode::Alpha)),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None, // Tessellated triangles wind either way
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: pipeline_state::depth_stencil_state(Some(depth)),
+            multisample: wgpu::MultisampleState {
+                count: 1, mask: !0, alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Vector Bind Group"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[wgpu::BindGroupEntry { binding: 0, resource: camera_buffer.as_entire_binding() }],
+        });
+        Self { pipeline, bind_group }
+    }
+
+    pub fn draw<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>, batch: &'a VectorBatch) {
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        batch.draw(rpass);
+    }
+}
@@ -0,0 +1,35 @@
+// crates/hanga/src/vector.wgsl
+// Tessellated vector paths: Triangles of flat colour, seen through the
+// sprites' camera.
+
+struct Camera {
+    view_proj: mat4x4<f32>, // See `shader.wgsl`
+};
+
+struct VertexInput {
+    @location(0) pos: vec3<f32>,   // World position, layer
+    @location(1) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+
+@vertex
+fn vs_main(vertex: VertexInput) -> VertexOutput {
+    let ndc = camera.view_proj * vec4<f32>(vertex.pos.xy, 0.0, 1.0);
+
+    var out: VertexOutput;
+    // The layer is the depth, like sprites
+    out.clip_position = vec4<f32>(ndc.xy, clamp(vertex.pos.z, 0.0, 1.0), 1.0);
+    out.color = vertex.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
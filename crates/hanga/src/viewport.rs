@@ -1,5 +1,5 @@
 /// Specifies the viewport region. [Read more](wgpu::RenderPass::set_viewport).
-#[derive(Clone, Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub struct Viewport {
     pub x: f32,
     pub y: f32,
@@ -0,0 +1,116 @@
+//! Hot reload: Watches a `.gyo` file or an unpacked project directory.
+//!
+//! Changes are debounced, so an editor saving several files (Or writing a file
+//! in chunks) triggers a single reload. Feed the result of
+//! [`ProjectWatcher::poll`] to [`crate::HangaEngine::reload_project`].
+
+use anyhow::Context;
+use gyo_core::pack::{self, ProjectPacker};
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+/// Quiet period after the last change before a reload is reported
+pub const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Where a project is loaded from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProjectSource {
+    /// A packed `.gyo` file
+    File(PathBuf),
+    /// An unpacked project, packed on every load (See `ProjectPacker::from_dir`)
+    Directory(PathBuf),
+}
+
+impl ProjectSource {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_path_buf();
+        if path.is_dir() {
+            Self::Directory(path)
+        } else {
+            Self::File(path)
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::File(path) | Self::Directory(path) => path,
+        }
+    }
+
+    /// Reads the project as `.gyo` bytes.
+    ///
+    /// Directories are packed without validation: `ProjectLoader` validates
+    /// anyway and the engine reports the diagnostics.
+    pub fn read(&self) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Self::File(path) => std::fs::read(path)
+                .with_context(|| format!("Failed to read {}", path.display())),
+            Self::Directory(path) => {
+                let mut buffer = Cursor::new(Vec::new());
+                ProjectPacker::from_dir(path)?.write_unchecked(&mut buffer)?;
+                Ok(buffer.into_inner())
+            }
+        }
+    }
+
+    /// True if a change of `changed` affects this project
+    fn is_affected_by(&self, changed: &Path) -> bool {
+        match self {
+            Self::File(path) => changed.file_name() == path.file_name(),
+            Self::Directory(_) => changed.file_name().is_some_and(|name| name == pack::DECLARATIONS_FILE)
+                || changed.extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| matches!(ext, "wgsl" | "glsl" | "frag" | "spv" | "png" | "svgpath")),
+        }
+    }
+}
+
+/// Watches a [`ProjectSource`] for changes.
+pub struct ProjectWatcher {
+    source: ProjectSource,
+    receiver: Receiver<DebounceEventResult>,
+    _debouncer: Debouncer<RecommendedWatcher>,
+}
+
+impl ProjectWatcher {
+    pub fn new(source: ProjectSource) -> anyhow::Result<Self> {
+        let (sender, receiver) = mpsc::channel();
+        let mut debouncer = new_debouncer(DEBOUNCE, sender)
+            .context("Failed to create file watcher")?;
+
+        // Watch the parent of files: Editors often save by replacing the file,
+        // which would end a watch on the file itself
+        let watched = match &source {
+            ProjectSource::File(path) => path.parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+                .unwrap_or(Path::new(".")),
+            ProjectSource::Directory(path) => path,
+        };
+        debouncer.watcher().watch(watched, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {}", watched.display()))?;
+
+        println!("👀 Watching {}", source.path().display());
+        Ok(Self { source, receiver, _debouncer: debouncer })
+    }
+
+    pub fn source(&self) -> &ProjectSource {
+        &self.source
+    }
+
+    /// Returns the new project bytes if the project changed since the last
+    /// call. Never blocks, call it once per frame.
+    pub fn poll(&self) -> Option<anyhow::Result<Vec<u8>>> {
+        let mut changed = false;
+        for result in self.receiver.try_iter() {
+            match result {
+                Ok(events) => changed |= events.iter().any(|e| self.source.is_affected_by(&e.path)),
+                Err(e) => return Some(Err(anyhow::anyhow!("File watcher failed: {}", e))),
+            }
+        }
+        changed.then(|| self.source.read())
+    }
+}
@@ -0,0 +1,84 @@
+//! Sprite batches sampling texture atlases.
+
+mod common;
+
+use common::{check_frames, HEIGHT, WIDTH};
+use gyo_core::background::Background;
+use gyo_core::{AssetKind, ProjectPacker};
+use hanga::pipeline_2d::InstanceRaw;
+
+#[test]
+fn sprite_atlases() {
+    common::require_adapter!();
+
+    // Tiles: Red, green, blue and a checker in the quadrants of one atlas
+    let tiles: Vec<u8> = (0..64 * 64)
+        .flat_map(|i| {
+            let (x, y) = (i % 64, i / 64);
+            match (x / 32, y / 32) {
+                (0, 0) => [230, 40, 40, 255],
+                (1, 0) => [40, 200, 70, 255],
+                (0, 1) => [50, 80, 230, 255],
+                _ if (x / 8 + y / 8) % 2 == 0 => [255, 255, 255, 255],
+                _ => [0, 0, 0, 255],
+            }
+        })
+        .collect();
+    // Dots: A white disc on transparent texels
+    let dot: Vec<u8> = (0..32 * 32)
+        .flat_map(|i| {
+            let (x, y) = ((i % 32) as f32 - 15.5, (i / 32) as f32 - 15.5);
+            let alpha = if x * x + y * y < 14.0 * 14.0 { 255 } else { 0 };
+            [255, 255, 255, alpha]
+        })
+        .collect();
+    let png = |texture: gyo_core::texture::Texture| {
+        let mut png = Vec::new();
+        texture.write_png(&mut png).unwrap();
+        png
+    };
+
+    let shader = include_str!("../src/shader.wgsl");
+    let mut packer = ProjectPacker::new("Atlases", "hanga tests");
+    packer.add_asset("main.wgsl", AssetKind::SumiSource, shader.as_bytes())
+        .add_asset("tiles.png", AssetKind::TexturePng, &png(gyo_core::texture::Texture::new(64, 64, tiles)))
+        .add_asset("dot.png", AssetKind::TexturePng, &png(gyo_core::texture::Texture::new(32, 32, dot)));
+    packer.background = Background::None;
+    let mut engine = common::headless_engine(&packer.to_bytes().unwrap(), WIDTH, HEIGHT);
+    assert!(engine.atlas_batch("missing.png").is_err());
+
+    // Flat colours (No atlas) under both atlases
+    let batch = engine.get_batch();
+    batch.clear();
+    batch.push(InstanceRaw { pos: [40.0, 40.0, 0.0], size: [1200.0, 640.0], uv_rect: [0.0, 0.0, 1.0, 1.0], color: [0.2, 0.2, 0.25, 1.0], ..Default::default() });
+
+    // Each quadrant of the tiles on its own, then the whole atlas tinted
+    let batch = engine.atlas_batch("tiles.png").unwrap();
+    batch.clear();
+    for (i, (u, v)) in [(0.0, 0.0), (0.5, 0.0), (0.0, 0.5), (0.5, 0.5)].into_iter().enumerate() {
+        batch.push(InstanceRaw {
+            pos: [80.0 + i as f32 * 240.0, 80.0, 0.0],
+            size: [200.0, 200.0],
+            uv_rect: [u, v, 0.5, 0.5],
+            color: [1.0, 1.0, 1.0, 1.0],
+            ..Default::default()
+        });
+    }
+    batch.push(InstanceRaw { pos: [1040.0, 80.0, 0.0], size: [200.0, 200.0], uv_rect: [0.0, 0.0, 1.0, 1.0], color: [1.0, 0.9, 0.3, 1.0], ..Default::default() });
+
+    // Tinted dots, blending through their transparent corners
+    let batch = engine.atlas_batch("dot.png").unwrap();
+    batch.clear();
+    for i in 0..6 {
+        batch.push(InstanceRaw {
+            pos: [80.0 + i as f32 * 150.0, 380.0, 0.0],
+            size: [260.0, 260.0],
+            uv_rect: [0.0, 0.0, 1.0, 1.0],
+            color: [1.0 - i as f32 * 0.15, 0.4, 0.2 + i as f32 * 0.15, 0.8],
+            ..Default::default()
+        });
+    }
+    engine.prepare_frame();
+
+    check_frames(&mut engine, &[("sprite_atlases", 0.0, &|_| {})]);
+}
@@ -0,0 +1,60 @@
+//! Declared background layers behind the scene.
+
+mod common;
+
+use common::{check_frames, HEIGHT, WIDTH};
+use gyo_core::background::Background;
+use gyo_core::{AssetKind, ProjectPacker};
+use hanga::pipeline_2d::InstanceRaw;
+use hanga::HangaEngine;
+
+/// A background shader: Bands scrolling up over time
+const BANDS_SHADER: &str = "\
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    let uv = frag_coord / u.resolution;
+    let band = 0.5 + 0.5 * sin((uv.y - u.time * 0.25) * 18.0);
+    return vec4<f32>(0.1, 0.2 + 0.3 * band, 0.3 + 0.4 * uv.x, 1.0);
+}
+";
+
+#[test]
+fn backgrounds_replace_the_sky() {
+    common::require_adapter!();
+
+    let shader = include_str!("../src/shader.wgsl");
+    let project = |background: Background| {
+        let mut packer = ProjectPacker::new("Backgrounds", "hanga tests");
+        packer.add_asset("main.wgsl", AssetKind::SumiSource, shader.as_bytes())
+            .add_asset("bands.wgsl", AssetKind::SumiSource, BANDS_SHADER.as_bytes());
+        packer.background = background;
+        packer.to_bytes().unwrap()
+    };
+    let mut engine = common::headless_engine(&project(Background::None), WIDTH, HEIGHT);
+    let batch = engine.get_batch();
+    batch.clear();
+    for i in 0..8 {
+        batch.push(InstanceRaw {
+            pos: [160.0 + i as f32 * 140.0, 360.0, 0.0],
+            size: [40.0, 240.0],
+            uv_rect: [0.0, 0.0, 1.0, 1.0],
+            color: [1.0, 0.8, 0.3, 1.0],
+            ..Default::default()
+        });
+    }
+    engine.prepare_frame();
+
+    // Solid backgrounds only clear the frame
+    let corner = |engine: &mut HangaEngine| common::render_at(engine, 0.0).data[..4].to_vec();
+    assert_eq!(corner(&mut engine), [0, 0, 0, 255]);
+    engine.reload_project(&project(Background::Color([0.2, 0.4, 0.6, 1.0]))).unwrap();
+    assert_eq!(corner(&mut engine), [124, 170, 203, 255]);
+
+    // Gradients and shaders are drawn, and shaders animate
+    engine.reload_project(&project(Background::Gradient { bottom: [0.0, 0.0, 0.1, 1.0], top: [0.9, 0.4, 0.1, 1.0] })).unwrap();
+    check_frames(&mut engine, &[("background_gradient", 0.0, &|_| {})]);
+    engine.reload_project(&project(Background::Shader { asset_id: "bands.wgsl".to_string() })).unwrap();
+    check_frames(&mut engine, &[
+        ("background_shader_t0", 0.0, &|_| {}),
+        ("background_shader_t1", 1.0, &|_| {}),
+    ]);
+}
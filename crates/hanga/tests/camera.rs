@@ -0,0 +1,114 @@
+//! Fitting the 2D camera to windows, converting between its spaces, and
+//! drawing sprites through it.
+
+mod common;
+
+use common::{check_frames, HEIGHT, WIDTH};
+use gyo_core::background::Background;
+use gyo_core::{AssetKind, ProjectPacker};
+use hanga::camera::{Camera2D, ScalePolicy};
+use hanga::pipeline_2d::InstanceRaw;
+use hanga::viewport::Viewport;
+use hanga_traits::Runtime;
+
+fn assert_close(actual: [f32; 2], expected: [f32; 2]) {
+    assert!(actual.iter().zip(expected).all(|(a, e)| (a - e).abs() < 1e-3), "{:?} != {:?}", actual, expected);
+}
+
+#[test]
+fn default_camera_maps_the_canvas_to_the_window() {
+    let view = Camera2D::default().view([1280, 720], 1.0);
+    assert_close(view.world_to_ndc([0.0, 0.0]), [-1.0, 1.0]);
+    assert_close(view.world_to_ndc([1280.0, 720.0]), [1.0, -1.0]);
+    assert_close(view.world_to_screen([320.0, 180.0]), [320.0, 180.0]);
+    assert_eq!(view.view_proj()[3], [-1.0, 1.0, 0.0, 1.0]);
+}
+
+#[test]
+fn policies_fit_other_aspect_ratios() {
+    let camera = Camera2D::default();
+
+    // Bars above and below a square window
+    let letterbox = camera.view([800, 800], 1.0);
+    assert_eq!(*letterbox.viewport(), Viewport { x: 0.0, y: 175.0, width: 800.0, height: 450.0, min_depth: 0.0, max_depth: 1.0 });
+    assert_close(letterbox.world_to_screen([0.0, 0.0]), [0.0, 175.0]);
+    assert_close(letterbox.world_to_screen([1280.0, 720.0]), [800.0, 625.0]);
+
+    // The canvas fills the window, squashed
+    let stretch = camera.with_policy(ScalePolicy::Stretch).view([800, 800], 1.0);
+    assert_eq!(stretch.viewport().height, 800.0);
+    assert_close(stretch.world_to_screen([1280.0, 720.0]), [800.0, 800.0]);
+
+    // The same scale as letterboxing, showing the world beyond the canvas
+    let expand = camera.with_policy(ScalePolicy::Expand).view([800, 800], 1.0);
+    assert_close(expand.world_to_screen([0.0, 0.0]), [0.0, 175.0]);
+    assert_close(expand.screen_to_world([0.0, 0.0]), [0.0, -280.0]);
+}
+
+#[test]
+fn screen_coordinates_are_logical_pixels() {
+    // A 1280x720 window at twice the density
+    let view = Camera2D::default().view([2560, 1440], 2.0);
+    assert_close(view.world_to_screen([1280.0, 720.0]), [1280.0, 720.0]);
+    assert_close(view.screen_to_world(view.physical_to_screen([2560.0, 0.0])), [1280.0, 0.0]);
+}
+
+#[test]
+fn pan_zoom_and_rotation_round_trip() {
+    let mut camera = Camera2D::default().with_zoom(2.0);
+    camera.pan([-320.0, -180.0]);
+    let view = camera.view([1280, 720], 1.0);
+    // The camera looks at its position, zoomed in twice
+    assert_close(view.world_to_screen([320.0, 180.0]), [640.0, 360.0]);
+    assert_close(view.world_to_screen([0.0, 0.0]), [0.0, 0.0]);
+
+    // Turning the view clockwise turns the world the other way
+    let turned = camera.with_rotation(std::f32::consts::FRAC_PI_2).view([1280, 720], 1.0);
+    let right_of_centre = turned.world_to_screen([330.0, 180.0]);
+    assert_close(right_of_centre, [640.0, 340.0]);
+    for world in [[0.0, 0.0], [100.0, 250.0], [-40.0, 900.0]] {
+        assert_close(turned.screen_to_world(turned.world_to_screen(world)), world);
+        assert_close(turned.ndc_to_world(turned.world_to_ndc(world)), world);
+    }
+}
+
+#[test]
+fn sprite_camera() {
+    common::require_adapter!();
+
+    let shader = include_str!("../src/shader.wgsl");
+    let mut packer = ProjectPacker::new("Camera", "hanga tests");
+    packer.add_asset("main.wgsl", AssetKind::SumiSource, shader.as_bytes());
+    packer.background = Background::Color([0.0, 0.0, 0.0, 1.0]);
+    let mut engine = common::headless_engine(&packer.to_bytes().unwrap(), WIDTH, HEIGHT);
+
+    // The canvas at the back, a grid of squares over it and a marker at its centre
+    let batch = engine.get_batch();
+    batch.clear();
+    batch.push(InstanceRaw::new([0.0, 0.0, 1.0], [1280.0, 720.0]).with_color([0.15, 0.2, 0.35, 1.0]));
+    for row in 0..4 {
+        for column in 0..8 {
+            let (x, y) = (column as f32 * 160.0 + 40.0, row as f32 * 180.0 + 50.0);
+            let color = [column as f32 / 7.0, row as f32 / 3.0, 0.5, 1.0];
+            batch.push(InstanceRaw::new([x, y, 0.5], [80.0, 80.0]).with_color(color));
+        }
+    }
+    batch.push(InstanceRaw::new([640.0, 360.0, 0.0], [60.0, 60.0]).with_pivot([0.5, 0.5]).with_color([1.0, 1.0, 1.0, 1.0]));
+    // Off the canvas: Only seen when the camera shows more of the world
+    batch.push(InstanceRaw::new([0.0, -200.0, 0.5], [1280.0, 120.0]).with_color([0.9, 0.5, 0.1, 1.0]));
+    batch.push(InstanceRaw::new([0.0, 800.0, 0.5], [1280.0, 120.0]).with_color([0.9, 0.5, 0.1, 1.0]));
+    engine.prepare_frame();
+
+    // A square window: Bars above and below the canvas, or more of the world
+    engine.resize(winit::dpi::PhysicalSize::new(HEIGHT, HEIGHT));
+    check_frames(&mut engine, &[
+        ("camera_letterbox", 0.0, &|engine| engine.set_camera(Camera2D::default())),
+        ("camera_expand", 0.0, &|engine| engine.set_camera(Camera2D::default().with_policy(ScalePolicy::Expand))),
+    ]);
+
+    // Panned towards the top-left square, zoomed and turned
+    engine.resize(winit::dpi::PhysicalSize::new(WIDTH, HEIGHT));
+    check_frames(&mut engine, &[("camera_zoom", 0.0, &|engine| {
+        engine.set_camera(Camera2D::default().with_position([320.0, 180.0]).with_zoom(2.0).with_rotation(0.3));
+    })]);
+}
@@ -0,0 +1,225 @@
+//! Shared helpers for the rendering tests: headless engines, perceptual
+//! image comparison and golden reference handling.
+#![allow(dead_code)]
+
+use gyo_core::texture::Texture;
+use gyo_core::ProjectPacker;
+use hanga::HangaEngine;
+use std::path::{Path, PathBuf};
+
+/// Set to `1` to overwrite the reference images with the current output.
+pub const BLESS_ENV: &str = "GYO_BLESS";
+
+/// Per-pixel YIQ distance (0..1) above which two pixels count as different.
+/// Same scale as pixelmatch: 0.1 tolerates anti-aliasing and driver rounding.
+pub const PIXEL_THRESHOLD: f32 = 0.1;
+
+/// Fraction of pixels allowed to differ before a comparison fails.
+pub const MAX_DIFF_RATIO: f32 = 0.005;
+
+pub fn workspace_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../..")
+}
+
+pub fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+/// Where failed comparisons write their actual and diff images.
+pub fn output_dir() -> PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden")
+}
+
+/// True if a GPU (hardware or software) is reachable.
+/// Rendering tests are skipped on machines without one.
+pub fn has_adapter() -> bool {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        ..Default::default()
+    });
+    pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default())).is_some()
+}
+
+/// Size of the frames the rendering tests compare
+pub const WIDTH: u32 = 256;
+pub const HEIGHT: u32 = 144;
+
+/// Packs `examples/examples/<name>`.
+pub fn example_project(name: &str) -> Vec<u8> {
+    let dir = workspace_dir().join("examples/examples").join(name);
+    ProjectPacker::from_dir(&dir)
+        .and_then(|packer| packer.to_bytes())
+        .unwrap_or_else(|e| panic!("Failed to pack {}: {:?}", dir.display(), e))
+}
+
+/// Returns from the calling test, with a message, on machines without a GPU
+/// adapter (See [`has_adapter`])
+macro_rules! require_adapter {
+    () => {
+        if !$crate::common::has_adapter() {
+            eprintln!("Skipping: no GPU adapter available");
+            return;
+        }
+    };
+}
+pub(crate) use require_adapter;
+
+/// A headless engine of the project, without a pipeline cache (Tests never
+/// write to the user's cache directory)
+pub fn headless_engine(project_bytes: &[u8], width: u32, height: u32) -> HangaEngine {
+    std::env::set_var("HANGA_PIPELINE_CACHE", "");
+    pollster::block_on(HangaEngine::new_headless(project_bytes, width, height))
+        .expect("Failed to create headless engine")
+}
+
+/// Renders the engine at a fixed shader time.
+pub fn render_at(engine: &mut HangaEngine, seconds: f32) -> Texture {
+    engine.pause();
+    engine.set_time(seconds);
+    engine.capture_frame().expect("Failed to capture frame")
+}
+
+pub struct Comparison {
+    pub differing: usize,
+    pub total: usize,
+    /// Red where pixels differ, a faded copy of the expected image elsewhere
+    pub diff: Texture,
+}
+
+impl Comparison {
+    pub fn ratio(&self) -> f32 {
+        self.differing as f32 / self.total.max(1) as f32
+    }
+
+    pub fn passed(&self) -> bool {
+        self.ratio() <= MAX_DIFF_RATIO
+    }
+}
+
+/// Perceptual comparison of two RGBA images of the same size.
+pub fn compare(expected: &Texture, actual: &Texture) -> Comparison {
+    assert_eq!(
+        (expected.width, expected.height),
+        (actual.width, actual.height),
+        "Image sizes differ"
+    );
+
+    // Maximum possible YIQ delta (Black vs White)
+    let max_delta = 35215.0 * PIXEL_THRESHOLD * PIXEL_THRESHOLD;
+
+    let mut differing = 0;
+    let mut diff = Vec::with_capacity(expected.data.len());
+    for (e, a) in expected.data.chunks_exact(4).zip(actual.data.chunks_exact(4)) {
+        if color_delta(e, a) > max_delta {
+            differing += 1;
+            diff.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let gray = (luma(e) * 0.1 + 255.0 * 0.9) as u8;
+            diff.extend_from_slice(&[gray, gray, gray, 255]);
+        }
+    }
+
+    Comparison {
+        differing,
+        total: expected.data.len() / 4,
+        diff: Texture::new(expected.width, expected.height, diff),
+    }
+}
+
+/// Squared YIQ distance of two pixels, blended over white
+/// (Kotsarenko & Ramos, "Measuring perceived color difference using YIQ NTSC")
+fn color_delta(a: &[u8], b: &[u8]) -> f32 {
+    let (y1, i1, q1) = yiq(a);
+    let (y2, i2, q2) = yiq(b);
+    let (y, i, q) = (y1 - y2, i1 - i2, q1 - q2);
+    0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q
+}
+
+fn blend(pixel: &[u8]) -> [f32; 3] {
+    let alpha = pixel[3] as f32 / 255.0;
+    [0, 1, 2].map(|c| 255.0 + (pixel[c] as f32 - 255.0) * alpha)
+}
+
+fn yiq(pixel: &[u8]) -> (f32, f32, f32) {
+    let [r, g, b] = blend(pixel);
+    (
+        r * 0.298_895 + g * 0.586_622 + b * 0.114_482,
+        r * 0.595_978 - g * 0.274_176 - b * 0.321_802,
+        r * 0.211_470 - g * 0.522_617 + b * 0.311_147,
+    )
+}
+
+fn luma(pixel: &[u8]) -> f32 {
+    yiq(pixel).0
+}
+
+fn load_png(path: &Path) -> Option<Texture> {
+    let file = std::fs::File::open(path).ok()?;
+    Texture::read_png(std::io::BufReader::new(file)).ok()
+}
+
+/// Compares `actual` against `tests/golden/<name>.png`.
+///
+/// With `GYO_BLESS=1` the reference is (re)written instead. On failure the
+/// actual and diff images are written to the test output directory.
+/// Returns a description of the failure, if any.
+pub fn check_golden(name: &str, actual: &Texture) -> Result<(), String> {
+    let reference = golden_dir().join(format!("{}.png", name));
+
+    if std::env::var(BLESS_ENV).is_ok_and(|v| v == "1") {
+        actual.save_png(&reference).map_err(|e| format!("{}: failed to bless: {:?}", name, e))?;
+        println!("Blessed {}", reference.display());
+        return Ok(());
+    }
+
+    let Some(expected) = load_png(&reference) else {
+        return Err(format!(
+            "{}: missing reference {} (run with {}=1 to create it)",
+            name, reference.display(), BLESS_ENV
+        ));
+    };
+
+    if (expected.width, expected.height) != (actual.width, actual.height) {
+        return Err(format!(
+            "{}: size {}x{} does not match reference {}x{}",
+            name, actual.width, actual.height, expected.width, expected.height
+        ));
+    }
+
+    let comparison = compare(&expected, actual);
+    if comparison.passed() {
+        return Ok(());
+    }
+
+    let out = output_dir();
+    std::fs::create_dir_all(&out).map_err(|e| e.to_string())?;
+    let actual_path = out.join(format!("{}.actual.png", name));
+    let diff_path = out.join(format!("{}.diff.png", name));
+    actual.save_png(&actual_path).map_err(|e| e.to_string())?;
+    comparison.diff.save_png(&diff_path).map_err(|e| e.to_string())?;
+
+    Err(format!(
+        "{}: {:.2}% of pixels differ (max {:.2}%)\n  actual: {}\n  diff:   {}",
+        name,
+        comparison.ratio() * 100.0,
+        MAX_DIFF_RATIO * 100.0,
+        actual_path.display(),
+        diff_path.display()
+    ))
+}
+
+/// A reference name, the shader time and input applied before rendering
+pub type Frame<'a> = (&'a str, f32, &'a dyn Fn(&mut HangaEngine));
+
+/// Renders every frame and reports all mismatches at once.
+pub fn check_frames(engine: &mut HangaEngine, frames: &[Frame]) {
+    let failures: Vec<String> = frames.iter()
+        .filter_map(|(name, seconds, setup)| {
+            setup(engine);
+            let frame = render_at(engine, *seconds);
+            check_golden(name, &frame).err()
+        })
+        .collect();
+
+    assert!(failures.is_empty(), "Golden images differ:\n{}", failures.join("\n"));
+}
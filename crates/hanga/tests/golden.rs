@@ -0,0 +1,426 @@
+//! Golden-image regression tests for `.gyo` rendering: The examples, shader
+//! languages, variants and reloads.
+//!
+//! Each case packs a project, renders it headlessly at fixed times and compares
+//! the frames against `tests/golden/*.png`. The rendering tests of the other
+//! files do the same. Bless new references with:
+//!
+//! ```bash
+//! GYO_BLESS=1 cargo test -p hanga
+//! ```
+
+mod common;
+
+use common::{check_frames, example_project, HEIGHT, WIDTH};
+use gyo_core::background::Background;
+use gyo_core::graph::{Pass, PassInput, PassTarget, Persistence, TargetFormat};
+use gyo_core::post::Effect;
+use gyo_core::{AssetEntry, AssetKind, EntryPoint, GyoshoFile, Manifest, ProjectPacker, ShaderStage, ShaderVariant};
+use hanga::pipeline_2d::InstanceRaw;
+use hanga::HangaEngine;
+
+/// Packs a single shader without validating it (`ProjectPacker` refuses broken ones).
+fn unchecked_project(id: &str, source: &str) -> Vec<u8> {
+    let manifest = Manifest {
+        title: "Unchecked".to_string(),
+        author: "hanga tests".to_string(),
+        timestamp: 0,
+        assets: vec![AssetEntry {
+            id: id.to_string(),
+            kind: AssetKind::SumiSource,
+            offset: 0,
+            size: source.len() as u64,
+        }],
+        compute_kernels: vec![],
+        variants: vec![],
+        pipelines: vec![],
+        passes: vec![],
+        effects: vec![],
+        background: Default::default(),
+        particles: vec![],
+    };
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    GyoshoFile::write_new(&mut buffer, &manifest, source.as_bytes()).unwrap();
+    buffer.into_inner()
+}
+
+const BROKEN_SHADER: &str = "\
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    return vec4<f32>(frag_coord / u.resolution, missing, 1.0);
+}
+";
+
+#[test]
+fn default_example() {
+    common::require_adapter!();
+
+    let mut engine = common::headless_engine(&example_project("default"), WIDTH, HEIGHT);
+    check_frames(&mut engine, &[
+        ("default_t0", 0.0, &|_| {}),
+        ("default_t1", 1.0, &|_| {}),
+        ("default_t2_5", 2.5, &|_| {}),
+    ]);
+}
+
+#[test]
+fn imports_example() {
+    common::require_adapter!();
+
+    // `clouds.wgsl` imports `noise.wgsl`, which imports `hash.wgsl`
+    let mut engine = common::headless_engine(&example_project("imports"), WIDTH, HEIGHT);
+    check_frames(&mut engine, &[
+        ("imports_t1", 1.0, &|_| {}),
+    ]);
+}
+
+#[test]
+fn texture_examples() {
+    common::require_adapter!();
+
+    // `test.png` is channel 0, `test1.png` channel 1
+    let mut engine = common::headless_engine(&example_project("texture"), WIDTH, HEIGHT);
+    check_frames(&mut engine, &[("texture", 0.0, &|_| {})]);
+    let mut engine = common::headless_engine(&example_project("two_textures"), WIDTH, HEIGHT);
+    check_frames(&mut engine, &[("two_textures", 0.0, &|_| {})]);
+}
+
+const FRAG_COORD_SHADER: &str = "\
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    return vec4<f32>(frag_coord / u.resolution, 0.0, 1.0);
+}
+";
+
+#[test]
+fn frag_coord_starts_at_the_bottom_left() {
+    common::require_adapter!();
+
+    let mut engine = common::headless_engine(&unchecked_project("uv.wgsl", FRAG_COORD_SHADER), WIDTH, HEIGHT);
+    let frame = common::render_at(&mut engine, 0.0);
+    let pixel = |x: u32, y: u32| {
+        let i = ((y * WIDTH + x) * 4) as usize;
+        [frame.data[i], frame.data[i + 1]]
+    };
+
+    // Rows are stored top to bottom, `frag_coord.y` grows upwards. The
+    // surface is sRGB, so the half-pixel offset reads as more than 1/255.
+    let [red, green] = pixel(0, HEIGHT - 1);
+    assert!(red < 16 && green < 16, "Bottom-left is {:?}", [red, green]);
+    let [red, green] = pixel(WIDTH - 1, 0);
+    assert!(red > 250 && green > 250, "Top-right is {:?}", [red, green]);
+}
+
+/// Stripes whose count and colour depend on the variant
+const VARIANT_SHADER: &str = "\
+#define STRIPES 4.0
+
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    let uv = frag_coord / u.resolution;
+    let stripe = step(0.5, fract(uv.x * STRIPES));
+#ifdef COLOR
+    return vec4<f32>(stripe, uv.y, 1.0 - stripe, 1.0);
+#else
+    return vec4<f32>(vec3<f32>(stripe), 1.0);
+#endif
+}
+";
+
+#[test]
+fn variants_switch_at_runtime() {
+    common::require_adapter!();
+
+    let mut packer = ProjectPacker::new("Variants", "hanga tests");
+    packer.add_asset("stripes.wgsl", AssetKind::SumiSource, VARIANT_SHADER.as_bytes())
+        .add_variant(ShaderVariant::new("low", [("STRIPES", "2.0")]))
+        .add_variant(ShaderVariant::new("high", [("STRIPES", "8.0"), ("COLOR", "")]));
+    let mut engine = common::headless_engine(&packer.to_bytes().unwrap(), WIDTH, HEIGHT);
+    assert_eq!(engine.variants(), ["low", "high"]);
+
+    check_frames(&mut engine, &[
+        ("variants_low", 0.0, &|_| {}),
+        ("variants_high", 0.0, &|engine| engine.set_variant(Some("high")).unwrap()),
+    ]);
+    assert_eq!(engine.variant(), Some("high"));
+
+    // Unknown variants keep the current one
+    assert!(engine.set_variant(Some("ultra")).is_err());
+    assert_eq!(engine.variant(), Some("high"));
+}
+
+#[test]
+fn mouse_input_example() {
+    common::require_adapter!();
+
+    let mut engine = common::headless_engine(&example_project("mouse_input"), WIDTH, HEIGHT);
+    check_frames(&mut engine, &[
+        ("mouse_input_released", 0.0, &|engine| engine.update_mouse_release()),
+        ("mouse_input_pressed", 0.0, &|engine| {
+            engine.update_cursor([96.0, 64.0]);
+            engine.update_mouse_press();
+        }),
+    ]);
+}
+
+#[test]
+fn sprite_rain() {
+    common::require_adapter!();
+
+    let shader = include_str!("../src/shader.wgsl");
+    let mut packer = ProjectPacker::new("Rain", "hanga tests");
+    packer.add_asset("main.wgsl", AssetKind::SumiSource, shader.as_bytes());
+    let mut engine = common::headless_engine(&packer.to_bytes().unwrap(), WIDTH, HEIGHT);
+
+    // A deterministic curtain of drops (The shader works in 1280x720 space)
+    let batch = engine.get_batch();
+    batch.clear();
+    for i in 0..200 {
+        let x = (i * 37 % 1280) as f32;
+        let y = (i * 91 % 720) as f32;
+        batch.push(InstanceRaw {
+            pos: [x, y, 0.0],
+            size: [6.0, 60.0],
+            uv_rect: [0.0, 0.0, 1.0, 1.0],
+            color: [0.6, 0.7 + (i % 5) as f32 * 0.05, 1.0, 0.6],
+            ..Default::default()
+        });
+    }
+    engine.prepare_frame();
+
+    check_frames(&mut engine, &[("sprite_rain", 0.0, &|_| {})]);
+}
+
+#[test]
+fn shader_error_overlay() {
+    common::require_adapter!();
+
+    let mut engine = common::headless_engine(&unchecked_project("broken.wgsl", BROKEN_SHADER), WIDTH, HEIGHT);
+    let message = engine.error_message().expect("Expected a shader error").to_string();
+    assert!(message.contains("broken.wgsl:2:"), "{}", message);
+
+    check_frames(&mut engine, &[("shader_error", 0.0, &|_| {})]);
+}
+
+#[test]
+fn failed_reload_keeps_last_good_scene() {
+    common::require_adapter!();
+
+    let good = example_project("default");
+    let mut engine = common::headless_engine(&good, WIDTH, HEIGHT);
+
+    assert!(engine.reload_project(&unchecked_project("broken.wgsl", BROKEN_SHADER)).is_err());
+    assert!(engine.error_message().is_some());
+    check_frames(&mut engine, &[("reload_failed", 1.0, &|_| {})]);
+
+    engine.reload_project(&good).unwrap();
+    assert!(engine.error_message().is_none());
+    check_frames(&mut engine, &[("default_t1", 1.0, &|_| {})]);
+}
+
+#[test]
+fn texture_edits_reload_in_place() {
+    common::require_adapter!();
+
+    let png = |rgba: [u8; 4]| {
+        let mut png = Vec::new();
+        gyo_core::texture::Texture::new(1, 1, rgba.to_vec()).write_png(&mut png).unwrap();
+        png
+    };
+    let center = |engine: &mut HangaEngine| {
+        let frame = common::render_at(engine, 0.0);
+        let i = ((HEIGHT / 2 * WIDTH + WIDTH / 2) * 4) as usize;
+        [frame.data[i], frame.data[i + 1], frame.data[i + 2]]
+    };
+    let (red, green) = ([255, 0, 0, 255], [0, 255, 0, 255]);
+
+    // A channel of an image shader
+    let shader = "\
+void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+    fragColor = vec4(texture(iChannel0, fragCoord / iResolution.xy).rgb, 1.0);
+}
+";
+    let project = |rgba| {
+        let mut packer = ProjectPacker::new("Channel Reload", "hanga tests");
+        packer.add_asset("main.glsl", AssetKind::GlslSource, shader.as_bytes())
+            .add_asset("color.png", AssetKind::TexturePng, &png(rgba));
+        packer.to_bytes().unwrap()
+    };
+    let mut engine = common::headless_engine(&project(red), WIDTH, HEIGHT);
+    assert_eq!(center(&mut engine), [255, 0, 0]);
+    engine.reload_project(&project(green)).unwrap();
+    assert!(engine.error_message().is_none());
+    assert_eq!(center(&mut engine), [0, 255, 0]);
+
+    // The atlas of a sprite batch
+    let project = |rgba| {
+        let mut packer = ProjectPacker::new("Atlas Reload", "hanga tests");
+        packer.add_asset("main.wgsl", AssetKind::SumiSource, include_str!("../src/shader.wgsl").as_bytes())
+            .add_asset("color.png", AssetKind::TexturePng, &png(rgba));
+        packer.background = Background::None;
+        packer.to_bytes().unwrap()
+    };
+    let mut engine = common::headless_engine(&project(red), WIDTH, HEIGHT);
+    let batch = engine.atlas_batch("color.png").unwrap();
+    batch.clear();
+    batch.push(InstanceRaw { pos: [0.0, 0.0, 0.0], size: [1280.0, 720.0], uv_rect: [0.0, 0.0, 1.0, 1.0], color: [1.0; 4], ..Default::default() });
+    engine.prepare_frame();
+    assert_eq!(center(&mut engine), [255, 0, 0]);
+    engine.reload_project(&project(green)).unwrap();
+    assert!(engine.error_message().is_none());
+    assert_eq!(center(&mut engine), [0, 255, 0]);
+}
+
+/// Buffer A: One step brighter every frame, up to white
+const BRIGHTEN_SHADER: &str = "\
+@group(1) @binding(0) var previous: texture_2d<f32>;
+@group(1) @binding(1) var previous_sampler: sampler;
+
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    return min(image(previous, previous_sampler, frag_coord / u.resolution) + vec4<f32>(0.25), vec4<f32>(1.0));
+}
+";
+
+/// Screen and effect: What channel 0 holds
+const COPY_SHADER: &str = "\
+@group(1) @binding(0) var source: texture_2d<f32>;
+@group(1) @binding(1) var source_sampler: sampler;
+
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    return vec4<f32>(image(source, source_sampler, frag_coord / u.resolution).rgb, 1.0);
+}
+";
+
+#[test]
+fn reloads_rebuild_only_changed_pipelines() {
+    common::require_adapter!();
+
+    let project = |copy: &str, step: &str| {
+        let mut packer = ProjectPacker::new("Partial Reload", "hanga tests");
+        packer.add_asset("screen.wgsl", AssetKind::SumiSource, COPY_SHADER.as_bytes())
+            .add_asset("brighten.wgsl", AssetKind::SumiSource, BRIGHTEN_SHADER.replace("0.25", step).as_bytes())
+            .add_asset("copy.wgsl", AssetKind::SumiSource, copy.as_bytes())
+            .add_pass(Pass::new("Image", "screen.wgsl").with_input(PassInput::Pass("Buffer A".to_string())))
+            .add_pass(Pass::new("Buffer A", "brighten.wgsl")
+                .with_target(PassTarget::new(TargetFormat::Rgba16Float).with_persistence(Persistence::PingPong))
+                .with_input(PassInput::PreviousFrame("Buffer A".to_string())))
+            .add_effect(Effect::Custom { asset_id: "copy.wgsl".to_string() });
+        packer.to_bytes().unwrap()
+    };
+    let center = |engine: &mut HangaEngine| {
+        let frame = common::render_at(engine, 0.0);
+        frame.data[((HEIGHT / 2 * WIDTH + WIDTH / 2) * 4) as usize]
+    };
+    let mut engine = common::headless_engine(&project(COPY_SHADER, "0.25"), WIDTH, HEIGHT);
+    for _ in 0..4 {
+        center(&mut engine);
+    }
+    assert_eq!(center(&mut engine), 255);
+
+    // 1. Another effect: The graph is kept, with what its targets built up
+    let darker = COPY_SHADER.replace(".rgb, 1.0", ".rgb * 0.5, 1.0");
+    engine.reload_project(&project(&darker, "0.25")).unwrap();
+    let half = center(&mut engine);
+    assert!((180..200).contains(&half), "{}", half);
+
+    // 2. Another pass: The graph starts over
+    engine.reload_project(&project(&darker, "0.125")).unwrap();
+    let dim = center(&mut engine);
+    assert!(dim < 120, "{}", dim);
+}
+
+#[test]
+fn failed_reloads_keep_the_declarations() {
+    common::require_adapter!();
+
+    let mut engine = common::headless_engine(&example_project("default"), WIDTH, HEIGHT);
+    assert!(engine.variants().is_empty());
+
+    // Loads, but without the `vs_main` entry point sprite pipelines need
+    let source = "@fragment fn fs_main() -> @location(0) vec4<f32> { return vec4<f32>(1.0); }";
+    let mut manifest = GyoshoFile::read_manifest(&mut std::io::Cursor::new(unchecked_project("sprites.wgsl", source))).unwrap();
+    manifest.variants.push(ShaderVariant::new("fast", [("FAST", "")]));
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    GyoshoFile::write_new(&mut bytes, &manifest, source.as_bytes()).unwrap();
+
+    let error = engine.reload_project(bytes.get_ref()).expect_err("Expected a pipeline error");
+    assert!(format!("{:#}", error).contains("Failed to create pipeline"), "{:#}", error);
+    assert!(engine.variants().is_empty(), "{:?}", engine.variants());
+}
+
+#[test]
+fn pipeline_errors_are_captured() {
+    common::require_adapter!();
+
+    // Valid WGSL, but without the `vs_main` entry point sprite pipelines need
+    let source = "@fragment fn fs_main() -> @location(0) vec4<f32> { return vec4<f32>(1.0); }";
+    let engine = common::headless_engine(&unchecked_project("sprites.wgsl", source), WIDTH, HEIGHT);
+    let message = engine.error_message().expect("Expected a pipeline error");
+    assert!(message.contains("Failed to create pipeline"), "{}", message);
+}
+
+#[test]
+fn glsl_matches_wgsl() {
+    common::require_adapter!();
+
+    // The Shadertoy default shader in GLSL renders like its WGSL port
+    let mut engine = common::headless_engine(&example_project("shadertoy"), WIDTH, HEIGHT);
+    check_frames(&mut engine, &[
+        ("default_t0", 0.0, &|_| {}),
+        ("default_t2_5", 2.5, &|_| {}),
+    ]);
+}
+
+#[test]
+fn glsl_mouse_and_channels() {
+    common::require_adapter!();
+
+    let shader = "\
+void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+    vec2 uv = fragCoord / iResolution.xy;
+    vec3 col = texture(iChannel0, uv * 2.0).rgb;
+    if (iMouse.z > 0.0) {
+        col = mix(col, vec3(1.0, 0.5, 0.0), step(length(fragCoord - iMouse.xy), 24.0));
+    }
+    fragColor = vec4(col, 1.0);
+}
+";
+    // 2x2 checker: Red, green / blue, white
+    let checker = gyo_core::texture::Texture::new(2, 2, vec![
+        255, 0, 0, 255,   0, 255, 0, 255,
+        0, 0, 255, 255,   255, 255, 255, 255,
+    ]);
+    let mut png = Vec::new();
+    checker.write_png(&mut png).unwrap();
+
+    let mut packer = ProjectPacker::new("Channels", "hanga tests");
+    packer.add_asset("main.glsl", AssetKind::GlslSource, shader.as_bytes());
+    packer.add_asset("checker.png", AssetKind::TexturePng, &png);
+    let mut engine = common::headless_engine(&packer.to_bytes().unwrap(), WIDTH, HEIGHT);
+
+    check_frames(&mut engine, &[
+        ("glsl_channels", 0.0, &|_| {}),
+        ("glsl_channels_pressed", 0.0, &|engine| {
+            engine.update_cursor([64.0, 32.0]);
+            engine.update_mouse_press();
+        }),
+    ]);
+}
+
+#[test]
+fn spirv_matches_wgsl() {
+    common::require_adapter!();
+
+    // The default image shader, precompiled
+    let shader = gyo_core::validate::validate_asset("default.wgsl", gyo_core::shader::DEFAULT_FRAGMENT).unwrap();
+    let words = naga::back::spv::write_vec(&shader.module, &shader.info, &Default::default(), None).unwrap();
+    let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+
+    let mut packer = ProjectPacker::new("SPIR-V", "hanga tests");
+    packer.add_asset("default.spv", AssetKind::SpirV {
+        entry_points: vec![EntryPoint::new("main", ShaderStage::Fragment)],
+    }, &bytes);
+    let mut engine = common::headless_engine(&packer.to_bytes().unwrap(), WIDTH, HEIGHT);
+
+    check_frames(&mut engine, &[
+        ("default_t0", 0.0, &|_| {}),
+        ("default_t1", 1.0, &|_| {}),
+    ]);
+}
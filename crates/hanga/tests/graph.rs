@@ -0,0 +1,76 @@
+//! Render graphs of buffer passes.
+
+mod common;
+
+use common::{check_frames, HEIGHT, WIDTH};
+use gyo_core::graph::{Pass, PassInput, PassTarget, Persistence, TargetFormat};
+use gyo_core::{AssetKind, ProjectPacker};
+
+/// Buffer A: A dot circling the screen, leaving a fading trail
+const TRAIL_SHADER: &str = "\
+@group(1) @binding(0) var previous: texture_2d<f32>;
+@group(1) @binding(1) var previous_sampler: sampler;
+
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    let uv = frag_coord / u.resolution;
+    let center = vec2<f32>(0.5) + 0.3 * vec2<f32>(cos(u.time * 3.0), sin(u.time * 3.0));
+    let dot = 1.0 - smoothstep(0.03, 0.05, length((uv - center) * vec2<f32>(u.resolution.x / u.resolution.y, 1.0)));
+    return max(image(previous, previous_sampler, uv) * 0.85, vec4<f32>(dot, dot * 0.6, 0.2 * dot, 1.0));
+}
+";
+
+/// Buffer B: The trail at half size, in cold colours
+const TINT_SHADER: &str = "\
+@group(1) @binding(0) var trail: texture_2d<f32>;
+@group(1) @binding(1) var trail_sampler: sampler;
+
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    return image(trail, trail_sampler, frag_coord / u.resolution).bgra;
+}
+";
+
+/// Screen: Both buffers side by side
+const SPLIT_SHADER: &str = "\
+@group(1) @binding(0) var trail: texture_2d<f32>;
+@group(1) @binding(1) var trail_sampler: sampler;
+@group(1) @binding(2) var tinted: texture_2d<f32>;
+@group(1) @binding(3) var tinted_sampler: sampler;
+
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    let uv = frag_coord / u.resolution;
+    if (uv.x < 0.5) {
+        return image(trail, trail_sampler, uv);
+    }
+    return image(tinted, tinted_sampler, uv);
+}
+";
+
+#[test]
+fn render_graph_feedback() {
+    common::require_adapter!();
+
+    // Declared screen first: The graph runs it after the buffers it reads
+    let mut packer = ProjectPacker::new("Render Graph", "hanga tests");
+    packer.add_asset("split.wgsl", AssetKind::SumiSource, SPLIT_SHADER.as_bytes())
+        .add_asset("trail.wgsl", AssetKind::SumiSource, TRAIL_SHADER.as_bytes())
+        .add_asset("tint.wgsl", AssetKind::SumiSource, TINT_SHADER.as_bytes())
+        .add_pass(Pass::new("Image", "split.wgsl")
+            .with_input(PassInput::Pass("Buffer A".to_string()))
+            .with_input(PassInput::Pass("Buffer B".to_string())))
+        .add_pass(Pass::new("Buffer B", "tint.wgsl")
+            .with_target(PassTarget::new(TargetFormat::Rgba8Unorm).with_scale(0.5))
+            .with_input(PassInput::Pass("Buffer A".to_string())))
+        .add_pass(Pass::new("Buffer A", "trail.wgsl")
+            .with_target(PassTarget::new(TargetFormat::Rgba16Float).with_persistence(Persistence::PingPong))
+            .with_input(PassInput::PreviousFrame("Buffer A".to_string())));
+    let mut engine = common::headless_engine(&packer.to_bytes().unwrap(), WIDTH, HEIGHT);
+
+    // The trail builds up over the frames before the captured one
+    check_frames(&mut engine, &[
+        ("render_graph_trail", 1.0, &|engine| {
+            for i in 0..20 {
+                common::render_at(engine, i as f32 * 0.05);
+            }
+        }),
+    ]);
+}
@@ -0,0 +1,86 @@
+//! Particle emitters simulated in a compute pass.
+
+mod common;
+
+use common::{check_frames, example_project, Frame, HEIGHT, WIDTH};
+use gyo_core::background::Background;
+use gyo_core::particles::{Emitter, Forces};
+use gyo_core::{AssetKind, ProjectPacker};
+
+#[test]
+fn gpu_particles() {
+    common::require_adapter!();
+
+    // Sparks declared in the manifest: Thrown up, falling back and fading
+    let shader = include_str!("../src/shader.wgsl");
+    let mut packer = ProjectPacker::new("Particles", "hanga tests");
+    packer.add_asset("main.wgsl", AssetKind::SumiSource, shader.as_bytes());
+    packer.background = Background::Color([0.02, 0.02, 0.05, 1.0]);
+    packer.add_emitter(Emitter::new("sparks", 2_000)
+        .with_rate(600.0)
+        .with_area([600.0, 600.0, 80.0, 20.0])
+        .with_velocity([-250.0, -700.0], [250.0, -400.0])
+        .with_lifetime(1.0, 2.0)
+        .with_size([12.0, 12.0])
+        .with_scale(0.5, 1.5)
+        .with_color([1.0, 0.8, 0.3, 1.0], [1.0, 0.2, 0.1, 0.0])
+        .with_forces(Forces::default().with_gravity([0.0, 500.0]).with_noise(200.0, 80.0)));
+    let mut engine = common::headless_engine(&packer.to_bytes().unwrap(), WIDTH, HEIGHT);
+
+    // Rain added in code: Streaks along the velocity, pushed by the wind
+    let rain = Emitter::new("rain", 4_000)
+        .with_rate(2_000.0)
+        .with_area([-200.0, -60.0, 1680.0, 20.0])
+        .with_velocity([0.0, 900.0], [0.0, 1200.0])
+        .with_lifetime(1.0, 1.0)
+        .with_size([3.0, 40.0])
+        .with_color([0.6, 0.7, 1.0, 0.5], [0.6, 0.7, 1.0, 0.5])
+        .with_align(true)
+        .with_layer(0.5)
+        .with_forces(Forces::default().with_wind([400.0, 1000.0], 2.0));
+    engine.add_emitter(rain.clone()).unwrap();
+    assert!(engine.add_emitter(rain.with_lifetime(2.0, 1.0)).is_err());
+    engine.prepare_frame();
+
+    let frames: [Frame; 3] = [
+        ("particles_t0", 0.0, &|_| {}),
+        ("particles_t1", 1.0, &|_| {}),
+        ("particles_t2", 2.5, &|_| {}),
+    ];
+    check_frames(&mut engine, &frames);
+
+    // Going back in time restarts them, the same way
+    check_frames(&mut engine, &frames[..2]);
+}
+
+#[test]
+fn particles_wait_outside_sprite_scenes() {
+    common::require_adapter!();
+
+    let mut packer = ProjectPacker::new("Particles", "hanga tests");
+    packer.add_asset("main.wgsl", AssetKind::SumiSource, include_str!("../src/shader.wgsl").as_bytes());
+    packer.background = Background::None;
+    let sprites = packer.to_bytes().unwrap();
+    let sparks = Emitter::new("sparks", 2_000)
+        .with_rate(600.0)
+        .with_area([600.0, 300.0, 80.0, 20.0])
+        .with_velocity([-250.0, -300.0], [250.0, 300.0])
+        .with_lifetime(1.0, 2.0)
+        .with_size([12.0, 12.0]);
+    let engine = || {
+        let mut engine = common::headless_engine(&sprites, WIDTH, HEIGHT);
+        engine.add_emitter(sparks.clone()).unwrap();
+        common::render_at(&mut engine, 0.5);
+        engine
+    };
+
+    // An image scene in between: The particles catch up once sprites are back,
+    // as if the frames in between had been skipped
+    let mut switched = engine();
+    switched.reload_project(&example_project("default")).unwrap();
+    common::render_at(&mut switched, 1.0);
+    common::render_at(&mut switched, 1.5);
+    switched.reload_project(&sprites).unwrap();
+    let mut skipped = engine();
+    assert!(common::render_at(&mut switched, 2.0).data == common::render_at(&mut skipped, 2.0).data);
+}
@@ -0,0 +1,267 @@
+//! The sprite instance layout, its transforms and layers, and uploading batches.
+
+mod common;
+
+use common::{check_frames, HEIGHT, WIDTH};
+use gyo_core::background::Background;
+use gyo_core::pipeline::DepthState;
+use gyo_core::validate::{self, ShaderError};
+use gyo_core::{reflect, AssetEntry, AssetKind, PipelineState, ProjectPacker};
+use hanga::loader::ProjectLoader;
+use hanga::pipeline_2d::{InstanceRaw, ShapeInstance, SHRINK_AFTER_FRAMES};
+use hanga::HangaEngine;
+use hanga_traits::Runtime;
+
+const SHADER: &str = include_str!("../src/shader.wgsl");
+
+fn assert_close(actual: [f32; 4], expected: [f32; 4]) {
+    assert!(actual.iter().zip(expected).all(|(a, e)| (a - e).abs() < 1e-6), "{:?} != {:?}", actual, expected);
+}
+
+#[test]
+fn shader_reads_the_whole_instance_layout() {
+    let shader = validate::validate_asset("main.wgsl", SHADER).unwrap();
+    let asset = AssetEntry { id: "main.wgsl".to_string(), kind: AssetKind::SumiSource, offset: 0, size: 0 };
+    let layout = InstanceRaw::desc();
+    reflect::check_vertex_inputs(&asset, &shader, "vs_main", layout.attributes).unwrap();
+
+    // Every attribute is read, and the attributes cover the whole struct
+    let inputs = reflect::vertex_inputs(&shader, "vs_main");
+    assert_eq!(inputs.len(), layout.attributes.len());
+    let bytes: u64 = layout.attributes.iter().map(|a| a.format.size()).sum();
+    assert_eq!(bytes, layout.array_stride);
+    assert_eq!(layout.array_stride, std::mem::size_of::<InstanceRaw>() as u64);
+}
+
+#[test]
+fn shaders_out_of_sync_fail_to_load() {
+    let stale = SHADER.replace("@location(5) transform: vec4<f32>", "@location(5) transform: vec2<f32>")
+        .replace("instance.transform.xy, instance.transform.zw", "instance.transform, instance.transform.yx");
+    let mut packer = ProjectPacker::new("Stale", "hanga tests");
+    packer.add_asset("main.wgsl", AssetKind::SumiSource, stale.as_bytes());
+
+    let error = ProjectLoader::load(&packer.to_bytes().unwrap()).err().unwrap();
+    let error = error.downcast_ref::<ShaderError>().expect("A shader error");
+    assert!(error.diagnostics[0].message.contains("`transform` (@location(5)) is a `vec2<f32>`"), "{}", error);
+}
+
+#[test]
+fn transforms_compose_around_the_pivot() {
+    let sprite = InstanceRaw::new([100.0, 50.0, 0.0], [10.0, 20.0]).with_pivot([0.5, 0.5]);
+    assert_eq!(sprite.transform, InstanceRaw::IDENTITY);
+
+    // A quarter turn, then a horizontal skew of what it turned
+    let turned = sprite.with_rotation(std::f32::consts::FRAC_PI_2);
+    assert_close(turned.transform, [0.0, 1.0, -1.0, 0.0]);
+    assert_close(turned.with_skew(0.5, 0.0).transform, [0.5, 1.0, -1.0, 0.0]);
+    assert_eq!(turned.pos, sprite.pos);
+
+    // Affine transforms move the pivot too
+    let moved = sprite.with_affine([2.0, 0.0, 0.0, 2.0, 5.0, -5.0]);
+    assert_eq!(moved.pos, [205.0, 95.0, 0.0]);
+    assert_eq!(moved.transform, [2.0, 0.0, 0.0, 2.0]);
+}
+
+#[test]
+fn shapes_are_turned_rounded_rectangles() {
+    // Centred between the ends, reaching the radius past them
+    let capsule = ShapeInstance::capsule([0.0, 0.0], [100.0, 0.0], 10.0);
+    assert_eq!((capsule.pos, capsule.half_size, capsule.radius), ([50.0, 0.0, 0.0], [60.0, 10.0], 10.0));
+
+    // Lines have square ends, along the segment
+    let line = ShapeInstance::line([10.0, 0.0], [10.0, 40.0], 4.0);
+    assert_eq!((line.pos, line.half_size, line.radius), ([10.0, 20.0, 0.0], [20.0, 2.0], 0.0));
+    assert_close([line.axis[0], line.axis[1], 0.0, 0.0], [0.0, 1.0, 0.0, 0.0]);
+    let turned = line.with_rotation(std::f32::consts::FRAC_PI_2);
+    assert_close([turned.axis[0], turned.axis[1], 0.0, 0.0], [-1.0, 0.0, 0.0, 0.0]);
+
+    // Rings are strokes without fill
+    let ring = ShapeInstance::ring([0.0, 0.0], 50.0, 6.0);
+    assert_eq!((ring.half_size, ring.radius, ring.stroke_width, ring.fill[3]), ([50.0, 50.0], 50.0, 6.0, 0.0));
+}
+
+fn sprite_engine() -> HangaEngine {
+    let mut packer = ProjectPacker::new("Batches", "hanga tests");
+    packer.add_asset("main.wgsl", AssetKind::SumiSource, SHADER.as_bytes());
+    common::headless_engine(&packer.to_bytes().unwrap(), WIDTH, HEIGHT)
+}
+
+/// Off screen sprites, then one covering the canvas in `color`
+fn push_sprites(engine: &mut HangaEngine, count: usize, color: [f32; 4]) {
+    let batch = engine.get_batch();
+    batch.clear();
+    for _ in 1..count {
+        batch.push(InstanceRaw::new([-100.0, -100.0, 0.0], [10.0, 10.0]));
+    }
+    batch.push(InstanceRaw { color, ..InstanceRaw::new([0.0, 0.0, 0.0], [1280.0, 720.0]) });
+}
+
+fn centre_pixel(engine: &mut HangaEngine) -> [u8; 4] {
+    let frame = common::render_at(engine, 0.0);
+    let i = ((frame.height / 2 * frame.width + frame.width / 2) * 4) as usize;
+    frame.data[i..i + 4].try_into().unwrap()
+}
+
+#[test]
+fn batches_upload_the_sprites_that_changed() {
+    common::require_adapter!();
+    let size = std::mem::size_of::<InstanceRaw>() as u64;
+    let mut engine = sprite_engine();
+
+    // 1. The first frame uploads everything
+    push_sprites(&mut engine, 200, [1.0, 0.0, 0.0, 1.0]);
+    engine.prepare_frame();
+    let metrics = *engine.get_batch().metrics();
+    assert_eq!((metrics.uploaded_bytes, metrics.upload_ranges), (200 * size, 1));
+    let red = centre_pixel(&mut engine);
+    assert!(red[0] > 200 && red[2] < 50, "{:?}", red);
+
+    // 2. Nothing changed, nothing to upload
+    push_sprites(&mut engine, 200, [1.0, 0.0, 0.0, 1.0]);
+    engine.prepare_frame();
+    assert_eq!(engine.get_batch().metrics().uploaded_bytes, 0);
+
+    // 3. A change goes to the other buffer of the ring, empty until now
+    push_sprites(&mut engine, 200, [0.0, 0.0, 1.0, 1.0]);
+    engine.prepare_frame();
+    assert_eq!(engine.get_batch().metrics().uploaded_bytes, 200 * size);
+    let blue = centre_pixel(&mut engine);
+    assert!(blue[2] > 200 && blue[0] < 50, "{:?}", blue);
+
+    // 4. Back to the first, where only the block of the last sprite differs (Sprites 192 to 199)
+    push_sprites(&mut engine, 200, [0.0, 1.0, 0.0, 1.0]);
+    engine.prepare_frame();
+    assert_eq!(engine.get_batch().metrics().uploaded_bytes, 8 * size);
+    let green = centre_pixel(&mut engine);
+    assert!(green[1] > 200 && green[2] < 50, "{:?}", green);
+}
+
+#[test]
+fn batches_draw_what_was_prepared() {
+    common::require_adapter!();
+    // Declared depth state draws whole batches, in push order
+    let mut packer = ProjectPacker::new("Unprepared", "hanga tests");
+    packer.add_asset("main.wgsl", AssetKind::SumiSource, SHADER.as_bytes())
+        .add_pipeline(PipelineState::new("main.wgsl").with_depth(DepthState::default()));
+    let mut engine = common::headless_engine(&packer.to_bytes().unwrap(), WIDTH, HEIGHT);
+
+    // 1. Nothing prepared yet: Nothing drawn
+    push_sprites(&mut engine, 1, [1.0, 0.0, 0.0, 1.0]);
+    let sky = centre_pixel(&mut engine);
+    assert!(sky[0] < 200, "{:?}", sky);
+
+    // 2. More sprites than the last upload: Only the uploaded ones
+    engine.prepare_frame();
+    push_sprites(&mut engine, 200, [0.0, 0.0, 1.0, 1.0]);
+    let red = centre_pixel(&mut engine);
+    assert!(red[0] > 200 && red[2] < 50, "{:?}", red);
+}
+
+#[test]
+fn batches_grow_then_shrink_after_low_use() {
+    common::require_adapter!();
+    let mut engine = sprite_engine();
+    let initial = engine.get_batch().metrics().capacity;
+
+    // 1. Outgrowing the buffer doubles it (At least)
+    push_sprites(&mut engine, initial * 3, [1.0, 0.0, 0.0, 1.0]);
+    engine.prepare_frame();
+    let metrics = *engine.get_batch().metrics();
+    assert_eq!((metrics.capacity, metrics.grows, metrics.shrinks), (initial * 3, 1, 0));
+
+    // 2. A quarter of it is kept until the low use lasts
+    for _ in 1..SHRINK_AFTER_FRAMES {
+        push_sprites(&mut engine, 100, [0.0, 1.0, 0.0, 1.0]);
+        engine.prepare_frame();
+    }
+    assert_eq!(engine.get_batch().metrics().capacity, initial * 3);
+
+    // 3. Then it shrinks, never below the initial capacity, and draws
+    push_sprites(&mut engine, 100, [0.0, 1.0, 0.0, 1.0]);
+    engine.prepare_frame();
+    let metrics = *engine.get_batch().metrics();
+    assert_eq!((metrics.capacity, metrics.grows, metrics.shrinks), (initial, 1, 1));
+    let green = centre_pixel(&mut engine);
+    assert!(green[1] > 200 && green[0] < 50, "{:?}", green);
+}
+
+#[test]
+fn sprite_transforms() {
+    common::require_adapter!();
+
+    let shader = include_str!("../src/shader.wgsl");
+    let mut packer = ProjectPacker::new("Transforms", "hanga tests");
+    packer.add_asset("main.wgsl", AssetKind::SumiSource, shader.as_bytes());
+    packer.background = Background::None;
+    let mut engine = common::headless_engine(&packer.to_bytes().unwrap(), WIDTH, HEIGHT);
+
+    let batch = engine.get_batch();
+    batch.clear();
+    for i in 0..6 {
+        let angle = i as f32 * std::f32::consts::PI / 10.0;
+        let x = 140.0 + i as f32 * 200.0;
+        // Top row: Turning around their centres
+        batch.push(InstanceRaw::new([x, 140.0, 0.0], [120.0, 60.0])
+            .with_color([1.0, 0.5, 0.2, 1.0])
+            .with_pivot([0.5, 0.5])
+            .with_rotation(angle));
+        // Middle row: Turning around their top-left corners (Marked white)
+        batch.push(InstanceRaw::new([x - 40.0, 320.0, 0.0], [120.0, 60.0])
+            .with_color([0.3, 0.8, 0.4, 1.0])
+            .with_rotation(angle));
+        batch.push(InstanceRaw::new([x - 40.0, 320.0, 0.0], [20.0, 20.0]).with_pivot([0.5, 0.5]));
+        // Bottom row: Slanting like rain, then scaled and moved by an affine transform
+        batch.push(InstanceRaw::new([x, 560.0, 0.0], [30.0, 160.0])
+            .with_color([0.4, 0.6, 1.0, 1.0])
+            .with_pivot([0.5, 0.5])
+            .with_skew(-0.6 + i as f32 * 0.24, 0.0)
+            .with_affine([1.0, 0.0, 0.0, 0.75, 20.0, 140.0]));
+    }
+    engine.prepare_frame();
+
+    check_frames(&mut engine, &[("sprite_transforms", 0.0, &|_| {})]);
+}
+
+#[test]
+fn sprite_layers() {
+    common::require_adapter!();
+
+    // A soft-edged disc: Its batch is translucent whatever the colour
+    let disc: Vec<u8> = (0..32 * 32)
+        .flat_map(|i| {
+            let (x, y) = ((i % 32) as f32 - 15.5, (i / 32) as f32 - 15.5);
+            [255, 255, 255, (255.0 * (1.0 - (x * x + y * y).sqrt() / 16.0).clamp(0.0, 1.0)) as u8]
+        })
+        .collect();
+    let mut png = Vec::new();
+    gyo_core::texture::Texture::new(32, 32, disc).write_png(&mut png).unwrap();
+
+    let shader = include_str!("../src/shader.wgsl");
+    let mut packer = ProjectPacker::new("Layers", "hanga tests");
+    packer.add_asset("main.wgsl", AssetKind::SumiSource, shader.as_bytes())
+        .add_asset("disc.png", AssetKind::TexturePng, &png);
+    packer.background = Background::Color([0.05, 0.05, 0.08, 1.0]);
+    let mut engine = common::headless_engine(&packer.to_bytes().unwrap(), WIDTH, HEIGHT);
+
+    // Pushed in no particular order: The layers (z) decide what is in front
+    let disc = engine.atlas_batch("disc.png").unwrap();
+    disc.clear();
+    disc.push(InstanceRaw::new([640.0, 360.0, 0.1], [560.0, 560.0]).with_pivot([0.5, 0.5]).with_color([1.0, 1.0, 1.0, 1.0]));
+    let batch = engine.get_batch();
+    batch.clear();
+    let square = |x: f32, y: f32, z: f32, color: [f32; 4]| InstanceRaw::new([x, y, z], [360.0, 360.0]).with_color(color);
+    batch.push(square(300.0, 100.0, 0.4, [0.1, 0.9, 0.3, 0.5])); // Translucent, between the opaque squares
+    batch.push(square(160.0, 200.0, 0.2, [0.9, 0.2, 0.1, 1.0])); // Opaque, in front
+    batch.push(square(440.0, 260.0, 0.8, [1.0, 0.9, 0.2, 0.5])); // Translucent, at the back
+    batch.push(square(380.0, 40.0, 0.6, [0.2, 0.3, 0.9, 1.0]));  // Opaque, behind
+    batch.push(square(760.0, 200.0, 0.6, [0.8, 0.3, 0.9, 0.7])); // Translucent, ties keep push order
+    batch.push(square(820.0, 260.0, 0.6, [0.3, 0.8, 0.9, 0.7]));
+    engine.prepare_frame();
+    check_frames(&mut engine, &[("sprite_layers", 0.0, &|_| {})]);
+
+    // The depth buffer follows the frame size
+    engine.resize(winit::dpi::PhysicalSize::new(WIDTH * 2, HEIGHT));
+    common::render_at(&mut engine, 0.0);
+    engine.resize(winit::dpi::PhysicalSize::new(WIDTH, HEIGHT));
+    check_frames(&mut engine, &[("sprite_layers", 0.0, &|_| {})]);
+}
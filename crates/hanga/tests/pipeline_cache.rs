@@ -0,0 +1,76 @@
+//! Keys of the on-disk pipeline cache.
+
+use hanga::pipeline_cache::{self, CacheFiles};
+use std::path::PathBuf;
+
+fn adapter(backend: wgpu::Backend, driver_info: &str) -> wgpu::AdapterInfo {
+    wgpu::AdapterInfo {
+        name: "Test GPU".to_string(),
+        vendor: 0x10de,
+        device: 0x2684,
+        device_type: wgpu::DeviceType::DiscreteGpu,
+        driver: "Test Driver".to_string(),
+        driver_info: driver_info.to_string(),
+        backend,
+    }
+}
+
+#[test]
+fn files_are_keyed_by_driver_and_project() {
+    let old_driver = adapter(wgpu::Backend::Vulkan, "550.54");
+    let new_driver = adapter(wgpu::Backend::Vulkan, "555.42");
+
+    let name = pipeline_cache::file_name(&old_driver, "Rain", 0xabc).unwrap();
+    assert!(name.starts_with("wgpu_pipeline_cache_vulkan_4318_9860_"), "{}", name);
+    assert!(name.ends_with("_0000000000000abc.bin"), "{}", name);
+
+    // Another fingerprint, project or driver gets another file
+    assert_ne!(pipeline_cache::file_name(&old_driver, "Rain", 0xdef), Some(name.clone()));
+    assert_ne!(pipeline_cache::file_name(&old_driver, "Snow", 0xabc), Some(name.clone()));
+    assert_ne!(pipeline_cache::file_name(&new_driver, "Rain", 0xabc), Some(name.clone()));
+    assert_eq!(pipeline_cache::file_name(&old_driver, "Rain", 0xabc), Some(name));
+}
+
+#[test]
+fn backends_without_pipeline_caches_have_no_files() {
+    for backend in [wgpu::Backend::Gl, wgpu::Backend::Metal, wgpu::Backend::Dx12] {
+        assert_eq!(pipeline_cache::file_name(&adapter(backend, "1.0"), "Rain", 0xabc), None);
+    }
+}
+
+#[test]
+fn saving_replaces_older_caches() {
+    // The only test here reading the variable
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("pipeline_cache");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::env::set_var("HANGA_PIPELINE_CACHE", &dir);
+    let files = CacheFiles::new(adapter(wgpu::Backend::Vulkan, "550.54")).expect("Caching is enabled");
+    let cached = || {
+        let mut names: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().path()).collect();
+        names.sort();
+        names
+    };
+
+    // 1. Saved, then loaded back
+    files.write("Rain", 0xabc, b"first").unwrap();
+    files.write("Snow", 0xabc, b"other project").unwrap();
+    assert_eq!(files.read("Rain", 0xabc).as_deref(), Some(&b"first"[..]));
+    assert_eq!(files.read("Rain", 0xdef), None);
+
+    // 2. A reload with other shaders replaces the project's file, not the other project's
+    files.write("Rain", 0xdef, b"second").unwrap();
+    assert_eq!(files.read("Rain", 0xabc), None);
+    assert_eq!(files.read("Rain", 0xdef).as_deref(), Some(&b"second"[..]));
+    let mut expected = vec![files.path("Rain", 0xdef), files.path("Snow", 0xabc)];
+    expected.sort();
+    assert_eq!(cached(), expected);
+
+    // 3. A driver update removes the caches of the old driver
+    let updated = CacheFiles::new(adapter(wgpu::Backend::Vulkan, "555.42")).unwrap();
+    updated.write("Rain", 0xdef, b"updated").unwrap();
+    assert_eq!(cached(), vec![updated.path("Rain", 0xdef)]);
+
+    // Empty disables caching
+    std::env::set_var("HANGA_PIPELINE_CACHE", "");
+    assert!(CacheFiles::new(adapter(wgpu::Backend::Vulkan, "555.42")).is_none());
+}
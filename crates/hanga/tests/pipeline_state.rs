@@ -0,0 +1,48 @@
+//! Entry points, blending and depth declared per shader asset.
+
+mod common;
+
+use common::{check_frames, HEIGHT, WIDTH};
+use gyo_core::pipeline::{BlendMode, DepthState};
+use gyo_core::{AssetKind, EntryPoint, PipelineState, ProjectPacker, ShaderStage};
+use hanga::pipeline_2d::InstanceRaw;
+
+#[test]
+fn declared_pipeline_state() {
+    common::require_adapter!();
+
+    // The sprite shader with other entry point names
+    let shader = include_str!("../src/shader.wgsl")
+        .replace("fn vs_main", "fn vs_glow")
+        .replace("fn fs_main", "fn fs_glow");
+    let project = |state: PipelineState| {
+        let mut packer = ProjectPacker::new("Pipeline State", "hanga tests");
+        packer.add_asset("glow.wgsl", AssetKind::SumiSource, shader.as_bytes())
+            .add_pipeline(state
+                .with_entry_point(EntryPoint::new("vs_glow", ShaderStage::Vertex))
+                .with_entry_point(EntryPoint::new("fs_glow", ShaderStage::Fragment)));
+        packer.to_bytes().unwrap()
+    };
+
+    let mut engine = common::headless_engine(&project(PipelineState::new("glow.wgsl").with_blend(BlendMode::Additive)), WIDTH, HEIGHT);
+    // Overlapping squares: The near one (Small z) is drawn first
+    let batch = engine.get_batch();
+    batch.clear();
+    for (i, z) in [0.2, 0.5, 0.8].into_iter().enumerate() {
+        let offset = i as f32 * 160.0;
+        batch.push(InstanceRaw {
+            pos: [320.0 + offset, 160.0 + offset, z],
+            size: [400.0, 400.0],
+            uv_rect: [0.0, 0.0, 1.0, 1.0],
+            color: [[0.9, 0.2, 0.1, 0.8], [0.1, 0.8, 0.2, 0.8], [0.2, 0.3, 0.9, 0.8]][i],
+            ..Default::default()
+        });
+    }
+    engine.prepare_frame();
+    check_frames(&mut engine, &[("pipeline_additive", 0.0, &|_| {})]);
+
+    // Same shader, other state: Rebuilt, and the near square stays on top
+    let depth = PipelineState::new("glow.wgsl").with_depth(DepthState::default());
+    engine.reload_project(&project(depth)).unwrap();
+    check_frames(&mut engine, &[("pipeline_depth", 0.0, &|_| {})]);
+}
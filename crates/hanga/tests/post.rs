@@ -0,0 +1,68 @@
+//! Post-processing chains over the frame.
+
+mod common;
+
+use common::{check_frames, HEIGHT, WIDTH};
+use gyo_core::post::{Bloom, Effect, ToneMap, ToneMapper};
+use gyo_core::{AssetKind, ProjectPacker};
+
+/// HDR lights, up to 12 times brighter than white, over a dim gradient
+const LIGHTS_SHADER: &str = "\
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    let uv = frag_coord / u.resolution;
+    var color = vec3<f32>(0.1, 0.15, 0.3) * uv.y;
+    for (var i = 0; i < 3; i++) {
+        let center = vec2<f32>(0.25 + 0.25 * f32(i), 0.5);
+        let d = length((uv - center) * vec2<f32>(u.resolution.x / u.resolution.y, 1.0));
+        color += vec3<f32>(4.0, 2.5, 1.0) * (1.0 - smoothstep(0.04, 0.05, d)) * f32(i + 1);
+    }
+    return vec4<f32>(color, 1.0);
+}
+";
+
+/// A custom effect: Darkens the corners of the frame
+const VIGNETTE_SHADER: &str = "\
+@group(1) @binding(0) var frame: texture_2d<f32>;
+@group(1) @binding(1) var frame_sampler: sampler;
+
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    let uv = frag_coord / u.resolution;
+    let vignette = 1.0 - 1.6 * dot(uv - 0.5, uv - 0.5);
+    return vec4<f32>(image(frame, frame_sampler, uv).rgb * vignette, 1.0);
+}
+";
+
+#[test]
+fn post_processing_chain() {
+    common::require_adapter!();
+
+    // A warm 4x4x4 grading LUT: Less blue, lifted reds
+    let size = 4u32;
+    let mut lut = Vec::new();
+    for g in 0..size {
+        for b in 0..size {
+            for r in 0..size {
+                let level = |v: u32| v as f32 / (size - 1) as f32;
+                let graded = [0.15 + 0.85 * level(r), level(g), 0.7 * level(b)];
+                lut.extend(graded.map(|v| (v * 255.0).round() as u8));
+                lut.push(255);
+            }
+        }
+    }
+    let mut png = Vec::new();
+    gyo_core::texture::Texture::new(size * size, size, lut).write_png(&mut png).unwrap();
+
+    // The effect shader comes first: It is not drawn as the scene
+    let mut packer = ProjectPacker::new("Post-Processing", "hanga tests");
+    packer.add_asset("vignette.wgsl", AssetKind::SumiSource, VIGNETTE_SHADER.as_bytes())
+        .add_asset("lights.wgsl", AssetKind::SumiSource, LIGHTS_SHADER.as_bytes())
+        .add_asset("warm.png", AssetKind::TexturePng, &png)
+        .add_effect(Effect::Bloom(Bloom::default().with_radius(2.0)))
+        .add_effect(Effect::ToneMap(ToneMap::new(ToneMapper::Aces)))
+        .add_effect(Effect::ColorGrade { lut: "warm.png".to_string() })
+        .add_effect(Effect::Fxaa)
+        .add_effect(Effect::Custom { asset_id: "vignette.wgsl".to_string() });
+    let mut engine = common::headless_engine(&packer.to_bytes().unwrap(), WIDTH, HEIGHT);
+
+    check_frames(&mut engine, &[("post_chain", 0.0, &|_| {})]);
+}
@@ -0,0 +1,91 @@
+//! Differential tests for the CPU reference renderer.
+//!
+//! The interpreter is checked against the golden references (No GPU needed)
+//! and, when an adapter is available, against a live `HangaEngine`.
+
+mod common;
+
+use hanga::reference::ReferenceRenderer;
+use hanga::uniform::Uniform;
+
+const WIDTH: u32 = 256;
+const HEIGHT: u32 = 144;
+
+fn example_source(name: &str) -> String {
+    let path = common::workspace_dir().join("examples/examples").join(name).join("frag.wgsl");
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e))
+}
+
+fn load_golden(name: &str) -> gyo_core::texture::Texture {
+    let path = common::golden_dir().join(format!("{}.png", name));
+    let file = std::fs::File::open(&path).unwrap_or_else(|e| panic!("Failed to open {}: {}", path.display(), e));
+    gyo_core::texture::Texture::read_png(std::io::BufReader::new(file)).unwrap()
+}
+
+fn assert_similar(name: &str, expected: &gyo_core::texture::Texture, actual: &gyo_core::texture::Texture) {
+    let comparison = common::compare(expected, actual);
+    assert!(
+        comparison.passed(),
+        "{}: reference renderer differs on {:.2}% of pixels",
+        name,
+        comparison.ratio() * 100.0
+    );
+}
+
+#[test]
+fn matches_golden_default() {
+    let renderer = ReferenceRenderer::new(&example_source("default")).unwrap();
+
+    for (name, time) in [("default_t0", 0.0), ("default_t1", 1.0), ("default_t2_5", 2.5)] {
+        let mut uniform = Uniform::default();
+        uniform.time = time;
+        let frame = renderer.render(&uniform, WIDTH, HEIGHT).unwrap();
+        assert_similar(name, &load_golden(name), &frame);
+    }
+}
+
+#[test]
+fn matches_golden_mouse_input() {
+    let renderer = ReferenceRenderer::new(&example_source("mouse_input")).unwrap();
+
+    let mut uniform = Uniform::default();
+    let released = renderer.render(&uniform, WIDTH, HEIGHT).unwrap();
+    assert_similar("mouse_input_released", &load_golden("mouse_input_released"), &released);
+
+    uniform.cursor = [96.0, 64.0];
+    uniform.mouse_down = 1;
+    let pressed = renderer.render(&uniform, WIDTH, HEIGHT).unwrap();
+    assert_similar("mouse_input_pressed", &load_golden("mouse_input_pressed"), &pressed);
+}
+
+#[test]
+fn matches_engine() {
+    common::require_adapter!();
+
+    let source = example_source("default");
+    let mut packer = gyo_core::ProjectPacker::new("Default", "hanga tests");
+    packer.add_asset("frag.wgsl", gyo_core::AssetKind::SumiSource, source.as_bytes());
+    let mut engine = common::headless_engine(&packer.to_bytes().unwrap(), WIDTH, HEIGHT);
+    let renderer = ReferenceRenderer::new(&source).unwrap();
+
+    for time in [0.25, 4.0] {
+        let gpu = common::render_at(&mut engine, time);
+        let mut uniform = Uniform::default();
+        uniform.time = time;
+        let cpu = renderer.render(&uniform, WIDTH, HEIGHT).unwrap();
+        assert_similar(&format!("default at {}s", time), &gpu, &cpu);
+    }
+}
+
+#[test]
+fn matches_golden_glsl() {
+    let path = common::workspace_dir().join("examples/examples/shadertoy/frag.glsl");
+    let source = std::fs::read_to_string(path).unwrap();
+    let shader = gyo_core::validate::validate_glsl_asset("frag.glsl", &source).unwrap();
+    let renderer = ReferenceRenderer::from_shader(shader).unwrap();
+
+    let mut uniform = Uniform::default();
+    uniform.time = 1.0;
+    let frame = renderer.render(&uniform, WIDTH, HEIGHT).unwrap();
+    assert_similar("default_t1", &load_golden("default_t1"), &frame);
+}
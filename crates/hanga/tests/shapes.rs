@@ -0,0 +1,45 @@
+//! SDF shapes drawn between sprites.
+
+mod common;
+
+use common::{check_frames, HEIGHT, WIDTH};
+use gyo_core::background::Background;
+use gyo_core::{AssetKind, ProjectPacker};
+use hanga::camera::Camera2D;
+use hanga::pipeline_2d::{InstanceRaw, ShapeInstance};
+
+#[test]
+fn sdf_shapes() {
+    common::require_adapter!();
+
+    let shader = include_str!("../src/shader.wgsl");
+    let mut packer = ProjectPacker::new("Shapes", "hanga tests");
+    packer.add_asset("main.wgsl", AssetKind::SumiSource, shader.as_bytes());
+    packer.background = Background::Color([0.05, 0.05, 0.08, 1.0]);
+    let mut engine = common::headless_engine(&packer.to_bytes().unwrap(), WIDTH, HEIGHT);
+
+    // An opaque sprite hides the shapes behind it (Layer 0.5)
+    let batch = engine.get_batch();
+    batch.clear();
+    batch.push(InstanceRaw::new([900.0, 380.0, 0.3], [300.0, 300.0]).with_color([0.2, 0.3, 0.6, 1.0]));
+
+    let shapes = engine.shape_batch();
+    shapes.clear();
+    shapes.circle([200.0, 200.0], 120.0, [0.9, 0.3, 0.2, 1.0]);
+    shapes.rounded_rect([380.0, 80.0], [300.0, 220.0], 40.0, [0.2, 0.8, 0.4, 0.8]);
+    shapes.ring([900.0, 200.0], 130.0, 24.0, [1.0, 0.9, 0.3, 1.0]);
+    shapes.capsule([120.0, 560.0], [520.0, 440.0], 50.0, [0.3, 0.7, 0.9, 1.0]);
+    shapes.line([600.0, 680.0], [1240.0, 360.0], 12.0, [1.0, 1.0, 1.0, 1.0]);
+    shapes.push(ShapeInstance::rect([640.0, 420.0], [320.0, 160.0])
+        .with_fill([0.8, 0.3, 0.9, 0.6])
+        .with_stroke(16.0, [1.0, 1.0, 1.0, 1.0])
+        .with_rotation(0.3)
+        .with_layer(0.5));
+    engine.prepare_frame();
+
+    check_frames(&mut engine, &[
+        ("shapes", 0.0, &|_| {}),
+        // Edges stay a pixel soft up close
+        ("shapes_zoom", 0.0, &|engine| engine.set_camera(Camera2D::default().with_position([200.0, 200.0]).with_zoom(4.0))),
+    ]);
+}
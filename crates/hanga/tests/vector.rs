@@ -0,0 +1,55 @@
+//! Vector paths tessellated into fills and strokes.
+
+mod common;
+
+use common::{check_frames, HEIGHT, WIDTH};
+use gyo_core::background::Background;
+use gyo_core::vector::{FillRule, LineCap, LineJoin, Path, StrokeStyle};
+use gyo_core::{AssetKind, ProjectPacker};
+use hanga::pipeline_2d::InstanceRaw;
+use hanga::vector::VectorMesh;
+
+/// A heart, as an SVG path asset
+const HEART: &str = "M 0 -60 C -20 -110 -100 -100 -100 -40 C -100 20 -30 50 0 100 C 30 50 100 20 100 -40 C 100 -100 20 -110 0 -60 Z";
+
+#[test]
+fn vector_paths() {
+    common::require_adapter!();
+
+    let shader = include_str!("../src/shader.wgsl");
+    let mut packer = ProjectPacker::new("Paths", "hanga tests");
+    packer.add_asset("main.wgsl", AssetKind::SumiSource, shader.as_bytes())
+        .add_asset("heart.svgpath", AssetKind::SvgPath, HEART.as_bytes());
+    packer.background = Background::Color([0.05, 0.05, 0.08, 1.0]);
+    let mut engine = common::headless_engine(&packer.to_bytes().unwrap(), WIDTH, HEIGHT);
+
+    // Five-pointed stars: Their middle is a hole by the even-odd rule
+    let star = |[cx, cy]: [f32; 2]| {
+        let point = |i: usize| {
+            let angle = std::f32::consts::PI * (i as f32 * 0.8 - 0.5);
+            [cx + 150.0 * angle.cos(), cy + 150.0 * angle.sin()]
+        };
+        (1..5).fold(Path::new().move_to(point(0)), |path, i| path.line_to(point(i))).close()
+    };
+    // A calligraphic stroke
+    let swash = Path::new().move_to([100.0, 600.0]).cubic_to([300.0, 420.0], [500.0, 780.0], [700.0, 560.0]).quad_to([800.0, 450.0], [760.0, 640.0]);
+    let style = StrokeStyle::new(24.0).with_join(LineJoin::Round).with_cap(LineCap::Round);
+
+    // The heart filled with a stroke over it, behind an opaque sprite
+    let heart = engine.path("heart.svgpath").unwrap().translated([1050.0, 300.0]);
+    let mut logo = VectorMesh::fill(&heart, FillRule::NonZero, [0.9, 0.2, 0.3, 1.0]).unwrap();
+    logo.append(&VectorMesh::stroke(&heart, &StrokeStyle::new(10.0).with_join(LineJoin::Miter), [1.0, 0.9, 0.8, 1.0]).unwrap());
+
+    let batch = engine.get_batch();
+    batch.clear();
+    batch.push(InstanceRaw::new([1000.0, 380.0, 0.2], [300.0, 200.0]).with_color([0.2, 0.3, 0.6, 1.0]));
+    let vectors = engine.vector_batch();
+    vectors.clear();
+    vectors.fill(&star([200.0, 220.0]), FillRule::NonZero, [1.0, 0.8, 0.2, 1.0]).unwrap();
+    vectors.fill(&star([560.0, 220.0]), FillRule::EvenOdd, [0.3, 0.8, 1.0, 1.0]).unwrap();
+    vectors.stroke(&swash, &style, [0.8, 0.9, 1.0, 0.8]).unwrap();
+    vectors.push(&logo.with_layer(0.5));
+    engine.prepare_frame();
+
+    check_frames(&mut engine, &[("vector_paths", 0.0, &|_| {})]);
+}
@@ -0,0 +1,64 @@
+//! Hot reload of unpacked project directories.
+
+use hanga::loader::ProjectLoader;
+use hanga::watch::{ProjectSource, ProjectWatcher};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+fn project_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("watch").join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("frag.wgsl"), gyo_core::shader::DEFAULT_FRAGMENT).unwrap();
+    dir
+}
+
+fn wait_for_change(watcher: &ProjectWatcher) -> anyhow::Result<Vec<u8>> {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        if let Some(result) = watcher.poll() {
+            return result;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    panic!("No change reported for {}", watcher.source().path().display());
+}
+
+#[test]
+fn directory_changes_are_repacked() {
+    let dir = project_dir("repack");
+    let source = ProjectSource::new(&dir);
+    assert_eq!(source, ProjectSource::Directory(dir.clone()));
+    let watcher = ProjectWatcher::new(source).unwrap();
+
+    let edited = gyo_core::shader::DEFAULT_FRAGMENT.replace("u.time", "u.time * 2.0");
+    std::fs::write(dir.join("frag.wgsl"), &edited).unwrap();
+
+    let project = ProjectLoader::load(&wait_for_change(&watcher).unwrap()).unwrap();
+    assert_eq!(project.source_code, edited);
+    assert!(watcher.poll().is_none(), "A single save should reload once");
+}
+
+#[test]
+fn broken_edits_still_produce_a_project() {
+    let dir = project_dir("broken");
+    let watcher = ProjectWatcher::new(ProjectSource::new(&dir)).unwrap();
+
+    std::fs::write(dir.join("frag.wgsl"), "fn main_image(").unwrap();
+
+    // Packing succeeds, loading reports the diagnostics
+    let bytes = wait_for_change(&watcher).unwrap();
+    let error = ProjectLoader::load(&bytes).err().expect("Expected a shader error");
+    assert!(error.downcast_ref::<gyo_core::validate::ShaderError>().is_some(), "{:?}", error);
+}
+
+#[test]
+fn declarations_are_watched() {
+    let dir = project_dir("declarations");
+    let watcher = ProjectWatcher::new(ProjectSource::new(&dir)).unwrap();
+
+    std::fs::write(dir.join(gyo_core::pack::DECLARATIONS_FILE), "(background: Some(Color((0.2, 0.4, 0.6, 1.0))))").unwrap();
+
+    let project = ProjectLoader::load(&wait_for_change(&watcher).unwrap()).unwrap();
+    assert_eq!(project.manifest.background, gyo_core::background::Background::Color([0.2, 0.4, 0.6, 1.0]));
+}
@@ -0,0 +1,14 @@
+// Drifting clouds built from shared noise functions.
+// `noise.wgsl` imports `hash.wgsl` too: It is only included once.
+#import "noise.wgsl"
+#import "hash.wgsl"
+
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    let uv = frag_coord / u.resolution.y;
+    let sky = vec3<f32>(0.0, 0.05, 0.2);
+    let clouds = vec3<f32>(0.8, 0.8, 0.9);
+
+    let n = fbm(uv * 3.0 + vec2<f32>(u.time * 0.1, 0.0));
+    let sparkle = step(0.995, hash(floor(frag_coord / 4.0)));
+    return vec4<f32>(mix(sky, clouds, n) + sparkle * 0.2, 1.0);
+}
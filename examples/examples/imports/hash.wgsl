@@ -0,0 +1,6 @@
+// A simple hash function
+fn hash(p: vec2<f32>) -> f32 {
+    var p2 = fract(p * vec2<f32>(123.34, 456.21));
+    p2 = p2 + dot(p2, p2 + 45.32);
+    return fract(p2.x * p2.y);
+}
@@ -0,0 +1,26 @@
+#import "hash.wgsl"
+
+// Bilinear value noise
+fn noise(p: vec2<f32>) -> f32 {
+    let i = floor(p);
+    let f = fract(p);
+    let u = f * f * (3.0 - 2.0 * f);
+
+    return mix(mix(hash(i + vec2<f32>(0.0, 0.0)),
+                   hash(i + vec2<f32>(1.0, 0.0)), u.x),
+               mix(hash(i + vec2<f32>(0.0, 1.0)),
+                   hash(i + vec2<f32>(1.0, 1.0)), u.x), u.y);
+}
+
+// Fractal Brownian Motion (The "Cloud" look)
+fn fbm(p: vec2<f32>) -> f32 {
+    var value = 0.0;
+    var amplitude = 0.5;
+    var st = p;
+    for (var i = 0; i < 5; i++) {
+        value += amplitude * noise(st);
+        st *= 2.0;
+        amplitude *= 0.5;
+    }
+    return value;
+}
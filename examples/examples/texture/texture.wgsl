@@ -1,3 +1,6 @@
+@group(1) @binding(0) var texture0: texture_2d<f32>;
+@group(1) @binding(1) var sampler0: sampler;
+
 fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
     let uv = frag_coord / u.resolution;
     return image(texture0, sampler0, uv);
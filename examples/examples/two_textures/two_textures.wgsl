@@ -1,3 +1,8 @@
+@group(1) @binding(0) var texture0: texture_2d<f32>;
+@group(1) @binding(1) var sampler0: sampler;
+@group(1) @binding(2) var texture1: texture_2d<f32>;
+@group(1) @binding(3) var sampler1: sampler;
+
 fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
     let uv = frag_coord / u.resolution;
     let uv0 = vec2(uv.x, (uv.y - 0.5) * 2.0);